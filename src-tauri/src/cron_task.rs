@@ -25,6 +25,7 @@ use crate::sidecar::{
     execute_cron_task, CronExecutePayload, ManagedSidecarManager, ProviderEnv,
     SidecarOwner, ensure_session_sidecar, release_session_sidecar,
 };
+use crate::turn_scheduler::{get_turn_scheduler, CRON_OWNER};
 
 /// Normalize a path for comparison (removes trailing slashes)
 /// This ensures consistent path matching regardless of how paths are formatted
@@ -1331,6 +1332,16 @@ async fn execute_task_directly(
 
     log::info!("[CronTask] Built payload for task {}, calling execute_cron_task with workspace: {}", task.id, task.workspace_path);
 
+    // Wait for a slot in the app-wide turn scheduler (shared with IM bots and heartbeats).
+    // Bot-owned tasks count against their bot's share; others share the "cron" owner.
+    let scheduler_owner = task
+        .source_bot_id
+        .as_deref()
+        .unwrap_or(CRON_OWNER);
+    let _turn_permit = get_turn_scheduler()
+        .acquire(scheduler_owner, &task.id)
+        .await;
+
     // Execute via Sidecar
     let result = execute_cron_task(handle, &sidecar_state, &task.workspace_path, payload).await
         .map_err(|e| {
@@ -1350,6 +1361,11 @@ async fn execute_task_directly(
 
     log::info!("[CronTask] execute_cron_task completed for task {}, task_success={}", task.id, result.success);
 
+    // Bot-owned tasks count towards their bot's usage ledger
+    if let (Some(bot_id), Some(usage)) = (task.source_bot_id.as_deref(), result.usage.as_ref()) {
+        crate::im::record_cron_usage(handle, bot_id, &task.id, usage).await;
+    }
+
     // Send notification if enabled
     if task.notify_enabled {
         send_task_notification(handle, task, &result);
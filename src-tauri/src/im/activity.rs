@@ -0,0 +1,282 @@
+// Tool-activity status line for streamed replies.
+// Built from the Sidecar's `activity` SSE events ({ kind, toolName?, toolUseId?, input?,
+// isError? }) and shown in the draft message until the next text block replaces it:
+//   compact — the latest running step:    🔧 Running Bash: npm test (00:42)
+//   verbose — finished steps, then the running ones.
+// Tool steps are keyed by their tool_use id, so parallel calls each keep their own label.
+
+use std::time::{Duration, Instant};
+
+use serde_json::Value;
+
+use super::types::ActivityMode;
+
+/// Longest tool detail shown (chars)
+const MAX_DETAIL_CHARS: usize = 60;
+/// Finished steps kept in verbose mode
+const MAX_HISTORY: usize = 8;
+/// Input fields that best describe a tool call, in order of preference
+const DETAIL_KEYS: [&str; 9] = [
+    "command",
+    "file_path",
+    "path",
+    "pattern",
+    "url",
+    "query",
+    "description",
+    "prompt",
+    "skill",
+];
+
+struct Step {
+    /// tool_use id; None for thinking (and tool events from a Sidecar that sends no ids)
+    id: Option<String>,
+    label: String,
+    started: Instant,
+}
+
+impl Step {
+    fn is_thinking(&self) -> bool {
+        self.label.starts_with("💭")
+    }
+}
+
+/// Status line state for one streamed turn
+pub struct ActivityStatus {
+    mode: ActivityMode,
+    /// Running steps, oldest first (parallel tool calls run side by side)
+    running: Vec<Step>,
+    /// Finished steps (verbose): "✓ Read: src/main.rs (00:01)"
+    finished: Vec<String>,
+    /// Finished steps dropped from `finished`
+    hidden: usize,
+}
+
+impl ActivityStatus {
+    pub fn new(mode: ActivityMode) -> Self {
+        Self {
+            mode,
+            running: Vec::new(),
+            finished: Vec::new(),
+            hidden: 0,
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.mode != ActivityMode::Off
+    }
+
+    /// Apply an `activity` event
+    pub fn on_event(&mut self, event: &Value, now: Instant) {
+        let tool = event["toolName"].as_str().unwrap_or("tool");
+        let id = event["toolUseId"].as_str();
+        match event["kind"].as_str().unwrap_or("") {
+            "thinking" => self.start(None, "💭 Thinking".to_string(), now),
+            "tool_use" | "server_tool_use" => self.start(id, format!("🔧 Running {}", tool), now),
+            "tool_input" => {
+                let detail = tool_detail(&event["input"]);
+                if let (Some(i), Some(detail)) = (self.find(id), detail) {
+                    self.running[i].label = format!("🔧 Running {}: {}", tool, detail);
+                }
+            }
+            "tool_result" => {
+                if let Some(i) = self.find(id) {
+                    let step = self.running.remove(i);
+                    let mark = if event["isError"].as_bool() == Some(true) { "✗" } else { "✓" };
+                    let label = step.label.trim_start_matches("🔧 Running ");
+                    self.push_finished(format!("{} {} ({})", mark, label, clock(now - step.started)));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Text is about to replace the status line — the next status starts fresh
+    pub fn reset(&mut self) {
+        self.running.clear();
+        self.finished.clear();
+        self.hidden = 0;
+    }
+
+    /// Status text, None when off or nothing happened yet
+    pub fn render(&self, now: Instant) -> Option<String> {
+        if !self.enabled() {
+            return None;
+        }
+        let line = |s: &Step| format!("{} ({})", s.label, clock(now - s.started));
+        let mut lines = Vec::new();
+        if self.mode == ActivityMode::Verbose {
+            if self.hidden > 0 {
+                lines.push(format!("… {} earlier steps", self.hidden));
+            }
+            lines.extend(self.finished.iter().cloned());
+            lines.extend(self.running.iter().map(line));
+        } else {
+            lines.extend(self.running.last().map(line));
+        }
+        // Between steps (compact) or before the first one
+        if lines.is_empty() {
+            return None;
+        }
+        Some(lines.join("\n"))
+    }
+
+    fn start(&mut self, id: Option<&str>, label: String, now: Instant) {
+        // Thinking ends when the next step starts; so does a tool step without an id (no
+        // result could be matched to it). Tool steps with ids run until their result.
+        let mut i = 0;
+        while i < self.running.len() {
+            if self.running[i].is_thinking() {
+                self.running.remove(i);
+            } else if id.is_none() && self.running[i].id.is_none() {
+                let step = self.running.remove(i);
+                let label = step.label.trim_start_matches("🔧 Running ");
+                self.push_finished(format!("✓ {} ({})", label, clock(now - step.started)));
+            } else {
+                i += 1;
+            }
+        }
+        self.running.push(Step {
+            id: id.map(String::from),
+            label,
+            started: now,
+        });
+    }
+
+    /// Running tool step an event is about: the one with its tool_use id, or without an id
+    /// the latest tool step
+    fn find(&self, id: Option<&str>) -> Option<usize> {
+        match id {
+            Some(id) => self.running.iter().position(|s| s.id.as_deref() == Some(id)),
+            None => self.running.iter().rposition(|s| !s.is_thinking()),
+        }
+    }
+
+    fn push_finished(&mut self, line: String) {
+        self.finished.push(line);
+        if self.finished.len() > MAX_HISTORY {
+            self.finished.remove(0);
+            self.hidden += 1;
+        }
+    }
+}
+
+/// Short description of a tool call from its input (first line, truncated)
+fn tool_detail(input: &Value) -> Option<String> {
+    let value = DETAIL_KEYS
+        .iter()
+        .find_map(|key| input[*key].as_str().filter(|v| !v.trim().is_empty()))?;
+    let line = value.trim().lines().next().unwrap_or_default();
+    if line.chars().count() > MAX_DETAIL_CHARS {
+        Some(format!("{}…", line.chars().take(MAX_DETAIL_CHARS).collect::<String>()))
+    } else {
+        Some(line.to_string())
+    }
+}
+
+/// "00:42", "12:03" (minutes past an hour keep counting)
+fn clock(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    format!("{:02}:{:02}", secs / 60, secs % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_compact_status_line() {
+        let t0 = Instant::now();
+        let mut status = ActivityStatus::new(ActivityMode::Compact);
+        assert!(status.render(t0).is_none());
+
+        status.on_event(&json!({ "kind": "tool_use", "toolName": "Bash" }), t0);
+        status.on_event(
+            &json!({ "kind": "tool_input", "toolName": "Bash", "input": { "command": "npm test\necho done" } }),
+            t0,
+        );
+        assert_eq!(
+            status.render(t0 + Duration::from_secs(42)).as_deref(),
+            Some("🔧 Running Bash: npm test (00:42)")
+        );
+
+        status.on_event(&json!({ "kind": "tool_result", "toolName": "Bash" }), t0 + Duration::from_secs(50));
+        assert!(status.render(t0 + Duration::from_secs(50)).is_none());
+    }
+
+    #[test]
+    fn test_verbose_keeps_finished_steps() {
+        let t0 = Instant::now();
+        let mut status = ActivityStatus::new(ActivityMode::Verbose);
+        status.on_event(&json!({ "kind": "thinking" }), t0);
+        status.on_event(&json!({ "kind": "tool_use", "toolName": "Read" }), t0 + Duration::from_secs(3));
+        status.on_event(
+            &json!({ "kind": "tool_input", "toolName": "Read", "input": { "file_path": "src/main.rs" } }),
+            t0 + Duration::from_secs(3),
+        );
+        status.on_event(
+            &json!({ "kind": "tool_result", "toolName": "Read", "isError": true }),
+            t0 + Duration::from_secs(4),
+        );
+        status.on_event(&json!({ "kind": "tool_use", "toolName": "Grep" }), t0 + Duration::from_secs(5));
+        assert_eq!(
+            status.render(t0 + Duration::from_secs(65)).as_deref(),
+            Some("✗ Read: src/main.rs (00:01)\n🔧 Running Grep (01:00)")
+        );
+
+        status.reset();
+        assert!(status.render(t0).is_none());
+        assert!(ActivityStatus::new(ActivityMode::Off).render(t0).is_none());
+    }
+
+    #[test]
+    fn test_parallel_tool_calls_keep_their_labels() {
+        let t0 = Instant::now();
+        let mut status = ActivityStatus::new(ActivityMode::Verbose);
+        status.on_event(&json!({ "kind": "thinking" }), t0);
+        status.on_event(&json!({ "kind": "tool_use", "toolName": "Read", "toolUseId": "a" }), t0);
+        status.on_event(&json!({ "kind": "tool_use", "toolName": "Grep", "toolUseId": "b" }), t0);
+        // Inputs complete after both calls started, results arrive out of order
+        status.on_event(
+            &json!({ "kind": "tool_input", "toolName": "Read", "toolUseId": "a", "input": { "file_path": "a.rs" } }),
+            t0,
+        );
+        status.on_event(
+            &json!({ "kind": "tool_input", "toolName": "Grep", "toolUseId": "b", "input": { "pattern": "fn main" } }),
+            t0,
+        );
+        assert_eq!(
+            status.render(t0 + Duration::from_secs(1)).as_deref(),
+            Some("🔧 Running Read: a.rs (00:01)\n🔧 Running Grep: fn main (00:01)")
+        );
+
+        status.on_event(
+            &json!({ "kind": "tool_result", "toolName": "Grep", "toolUseId": "b" }),
+            t0 + Duration::from_secs(2),
+        );
+        status.on_event(
+            &json!({ "kind": "tool_result", "toolName": "Read", "toolUseId": "a", "isError": true }),
+            t0 + Duration::from_secs(3),
+        );
+        assert_eq!(
+            status.render(t0 + Duration::from_secs(3)).as_deref(),
+            Some("✓ Grep: fn main (00:02)\n✗ Read: a.rs (00:03)")
+        );
+
+        // Compact shows the latest step still running
+        let mut status = ActivityStatus::new(ActivityMode::Compact);
+        status.on_event(&json!({ "kind": "tool_use", "toolName": "Read", "toolUseId": "a" }), t0);
+        status.on_event(&json!({ "kind": "tool_use", "toolName": "Grep", "toolUseId": "b" }), t0);
+        status.on_event(&json!({ "kind": "tool_result", "toolName": "Grep", "toolUseId": "b" }), t0);
+        assert_eq!(status.render(t0).as_deref(), Some("🔧 Running Read (00:00)"));
+    }
+
+    #[test]
+    fn test_tool_detail() {
+        assert_eq!(tool_detail(&json!({ "pattern": "fn main", "path": "src" })).as_deref(), Some("src"));
+        let long = "x".repeat(100);
+        assert_eq!(tool_detail(&json!({ "command": long })).unwrap().chars().count(), MAX_DETAIL_CHARS + 1);
+        assert!(tool_detail(&json!({ "content": "..." })).is_none());
+    }
+}
@@ -7,6 +7,21 @@
 /// mapped to String at the impl boundary).
 pub type AdapterResult<T> = Result<T, String>;
 
+/// Metadata about a completed response, for platforms that can show it
+/// alongside the final message (e.g. Feishu card footer).
+pub struct ResponseMeta<'a> {
+    /// Model that produced the response, if known
+    pub model: Option<&'a str>,
+    /// Wall time from request to completion
+    pub duration: std::time::Duration,
+    /// Tools invoked during the turn, in order
+    pub tools: &'a [String],
+    /// Provider failover notice, e.g. "DeepSeek 服务异常，已切换至 Kimi"
+    pub notice: Option<&'a str>,
+    /// Token usage of the turn, when the bot shows it in the footer
+    pub usage: Option<&'a super::types::TurnUsage>,
+}
+
 pub trait ImAdapter: Send + Sync + 'static {
     /// Verify the bot connection and return a human-readable identifier
     /// (e.g. Telegram bot username, Discord bot tag).
@@ -34,6 +49,15 @@ pub trait ImAdapter: Send + Sync + 'static {
         text: &str,
     ) -> impl std::future::Future<Output = AdapterResult<()>> + Send;
 
+    /// Upload a file as a document message, with an optional Markdown caption.
+    fn send_document(
+        &self,
+        chat_id: &str,
+        file_name: &str,
+        data: &[u8],
+        caption: Option<&str>,
+    ) -> impl std::future::Future<Output = AdapterResult<()>> + Send;
+
     /// React to indicate the message was received (e.g. 👀).
     fn ack_received(
         &self,
@@ -88,9 +112,37 @@ pub trait ImStreamAdapter: ImAdapter {
         message_id: &str,
     ) -> impl std::future::Future<Output = AdapterResult<()>> + Send;
 
-    /// Max message length for this platform (Telegram: 4096, Feishu: 30000).
+    /// Edit a streaming draft on a best-effort basis. Returns `Ok(false)` when the
+    /// platform send budget is spent and the edit was skipped — the caller simply
+    /// retries with newer text on the next update.
+    fn edit_draft(
+        &self,
+        chat_id: &str,
+        message_id: &str,
+        text: &str,
+    ) -> impl std::future::Future<Output = AdapterResult<bool>> + Send;
+
+    /// Max message length for this platform, in `length_unit()`
+    /// (Telegram: 4096 UTF-16 code units, Feishu: 20000 bytes of Markdown).
     fn max_message_length(&self) -> usize;
 
+    /// Unit `max_message_length` is measured in.
+    fn length_unit(&self) -> super::split::LengthUnit;
+
+    /// Record the workspace the current turn in `chat_id` runs in, for platforms that
+    /// resolve relative paths in replies (Feishu image uploads). No-op by default.
+    fn set_chat_workspace(&self, _chat_id: &str, _workspace: &std::path::Path) {}
+
+    /// Re-render the last message of a completed response with its metadata
+    /// (tool summary, model, duration). No-op where the platform has no rich layout.
+    fn finish_response(
+        &self,
+        chat_id: &str,
+        message_id: &str,
+        text: &str,
+        meta: &ResponseMeta<'_>,
+    ) -> impl std::future::Future<Output = AdapterResult<()>> + Send;
+
     /// Send an interactive approval card/keyboard and return its message ID.
     /// Used for permission requests when the bot runs in non-fullAgency mode.
     fn send_approval_card(
@@ -108,4 +160,22 @@ pub trait ImStreamAdapter: ImAdapter {
         message_id: &str,
         status: &str,
     ) -> impl std::future::Future<Output = AdapterResult<()>> + Send;
+
+    /// Send a message with one button per option (Telegram inline keyboard,
+    /// Feishu card buttons). Clicks come back as the option's command.
+    /// Returns the menu message ID.
+    fn send_menu(
+        &self,
+        chat_id: &str,
+        text: &str,
+        options: &[super::types::MenuOption],
+    ) -> impl std::future::Future<Output = AdapterResult<Option<String>>> + Send;
+
+    /// Replace a menu's text and remove its buttons (shows the selection in place).
+    fn update_menu(
+        &self,
+        chat_id: &str,
+        message_id: &str,
+        text: &str,
+    ) -> impl std::future::Future<Output = AdapterResult<()>> + Send;
 }
@@ -0,0 +1,169 @@
+// Image preparation for vision input.
+// Phone photos are often 4000+ px and several MB — more than vision models use, and past the
+// API's base64 size limit they can't be sent at all. Oversized images are downscaled so the
+// longer side is at most `MAX_DIMENSION` and re-encoded as JPEG, lowering the quality until
+// they fit `MAX_VISION_BYTES`. EXIF orientation is applied first, since the re-encoded JPEG
+// carries no EXIF. The caller keeps the original in the workspace for tools.
+
+use std::io::Cursor;
+
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::metadata::Orientation;
+use image::{DynamicImage, ImageDecoder, ImageReader, Rgb, RgbImage};
+
+/// Longer side (px) of an image sent for vision
+pub const MAX_DIMENSION: u32 = 2048;
+/// Largest image sent for vision (base64 of this stays under the 5 MB API limit)
+pub const MAX_VISION_BYTES: usize = 3_750_000;
+/// JPEG qualities tried in order until the encoded image fits
+const JPEG_QUALITIES: [u8; 3] = [85, 70, 55];
+
+/// Image ready for vision encoding
+#[derive(Debug)]
+pub struct PreparedImage {
+    pub data: Vec<u8>,
+    pub mime_type: String,
+    /// False when the original was small enough to send as is
+    pub resized: bool,
+}
+
+/// Downscale / re-encode an image for vision if it is too large; small images pass through
+/// untouched (keeping PNG transparency, GIF, etc. — including formats not decoded here).
+/// Err if a too-large image can't be decoded or still doesn't fit at the lowest quality.
+/// CPU-bound — run it off the async runtime.
+pub fn prepare_for_vision(data: &[u8], mime_type: &str) -> Result<PreparedImage, String> {
+    let passthrough = || PreparedImage {
+        data: data.to_vec(),
+        mime_type: mime_type.to_string(),
+        resized: false,
+    };
+    let dimensions = ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .map_err(|e| e.to_string())
+        .and_then(|reader| reader.into_dimensions().map_err(|e| e.to_string()));
+    let (width, height) = match dimensions {
+        Ok(dimensions) => dimensions,
+        Err(_) if data.len() <= MAX_VISION_BYTES => return Ok(passthrough()),
+        Err(e) => return Err(format!("unsupported image: {}", e)),
+    };
+    if width.max(height) <= MAX_DIMENSION && data.len() <= MAX_VISION_BYTES {
+        return Ok(passthrough());
+    }
+
+    let image = decode_upright(data).map_err(|e| format!("failed to decode image: {}", e))?;
+    let image = if width.max(height) > MAX_DIMENSION {
+        image.resize(MAX_DIMENSION, MAX_DIMENSION, FilterType::Triangle)
+    } else {
+        image
+    };
+    let rgb = flatten(&image);
+
+    for quality in JPEG_QUALITIES {
+        let mut out = Vec::new();
+        JpegEncoder::new_with_quality(&mut out, quality)
+            .encode_image(&rgb)
+            .map_err(|e| format!("failed to encode JPEG: {}", e))?;
+        if out.len() <= MAX_VISION_BYTES {
+            return Ok(PreparedImage {
+                data: out,
+                mime_type: "image/jpeg".to_string(),
+                resized: true,
+            });
+        }
+    }
+    Err(format!(
+        "still larger than {} bytes at JPEG quality {}",
+        MAX_VISION_BYTES,
+        JPEG_QUALITIES[JPEG_QUALITIES.len() - 1]
+    ))
+}
+
+/// Decode an image turned upright per its EXIF orientation (phone photos are stored
+/// sideways with an orientation tag)
+fn decode_upright(data: &[u8]) -> image::ImageResult<DynamicImage> {
+    let mut decoder = ImageReader::new(Cursor::new(data))
+        .with_guessed_format()?
+        .into_decoder()?;
+    let orientation = decoder.orientation().unwrap_or(Orientation::NoTransforms);
+    let mut image = DynamicImage::from_decoder(decoder)?;
+    image.apply_orientation(orientation);
+    Ok(image)
+}
+
+/// RGB copy of the image; transparent areas are composited onto white (JPEG has no alpha)
+fn flatten(image: &DynamicImage) -> RgbImage {
+    if !image.color().has_alpha() {
+        return image.to_rgb8();
+    }
+    let rgba = image.to_rgba8();
+    RgbImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+        let [r, g, b, a] = rgba.get_pixel(x, y).0;
+        let blend = |c: u8| ((c as u16 * a as u16 + 255 * (255 - a as u16)) / 255) as u8;
+        Rgb([blend(r), blend(g), blend(b)])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageEncoder, ImageFormat, Rgba, RgbaImage};
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let img = RgbaImage::from_fn(width, height, |x, y| Rgba([(x % 256) as u8, (y % 256) as u8, 128, 0]));
+        let mut out = Cursor::new(Vec::new());
+        img.write_to(&mut out, ImageFormat::Png).unwrap();
+        out.into_inner()
+    }
+
+    #[test]
+    fn test_small_image_passes_through() {
+        let data = png(64, 32);
+        let prepared = prepare_for_vision(&data, "image/png").unwrap();
+        assert!(!prepared.resized);
+        assert_eq!(prepared.mime_type, "image/png");
+        assert_eq!(prepared.data, data);
+    }
+
+    #[test]
+    fn test_large_image_is_downscaled_to_jpeg() {
+        let prepared = prepare_for_vision(&png(3000, 1000), "image/png").unwrap();
+        assert!(prepared.resized);
+        assert_eq!(prepared.mime_type, "image/jpeg");
+        let out = image::load_from_memory(&prepared.data).unwrap();
+        assert_eq!((out.width(), out.height()), (MAX_DIMENSION, 683));
+        // Fully transparent pixels end up white, not black
+        assert!(out.to_rgb8().get_pixel(10, 10).0.iter().all(|c| *c > 240));
+    }
+
+    #[test]
+    fn test_exif_orientation_applied_before_resize() {
+        // Landscape pixels tagged "rotate 90° clockwise" (orientation 6), red on the left
+        let img = RgbImage::from_fn(3000, 1000, |x, _| if x < 300 { Rgb([255, 0, 0]) } else { Rgb([0, 0, 255]) });
+        let exif = vec![
+            b'M', b'M', 0, 42, 0, 0, 0, 8, // big-endian TIFF header, IFD at offset 8
+            0, 1, // one entry
+            0x01, 0x12, 0, 3, 0, 0, 0, 1, 0, 6, 0, 0, // Orientation (SHORT) = 6
+            0, 0, 0, 0, // no next IFD
+        ];
+        let mut data = Vec::new();
+        let mut encoder = JpegEncoder::new_with_quality(&mut data, 90);
+        encoder.set_exif_metadata(exif).unwrap();
+        encoder.encode_image(&img).unwrap();
+
+        let prepared = prepare_for_vision(&data, "image/jpeg").unwrap();
+        assert!(prepared.resized);
+        let out = image::load_from_memory(&prepared.data).unwrap();
+        assert_eq!((out.width(), out.height()), (683, MAX_DIMENSION));
+        // The left edge ends up on top once rotated
+        let top = out.to_rgb8().get_pixel(341, 20).0;
+        assert!(top[0] > 200 && top[2] < 60, "top pixel {:?}", top);
+    }
+
+    #[test]
+    fn test_undecodable_image_only_passes_when_small() {
+        let prepared = prepare_for_vision(b"GIF89a...", "image/gif").unwrap();
+        assert!(!prepared.resized && prepared.mime_type == "image/gif");
+        assert!(prepare_for_vision(&vec![0u8; MAX_VISION_BYTES + 1], "image/heic").is_err());
+    }
+}
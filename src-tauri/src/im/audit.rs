@@ -0,0 +1,259 @@
+// IM Audit log — append-only record of approvals and privileged commands.
+// One JSON object per line in ~/.myagents/im_{bot_id}_audit.jsonl (beside im_{bot_id}_state.json).
+
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use super::types::{AuditAction, AuditEntry};
+use crate::ulog_warn;
+
+/// Append-only audit log for a single bot
+pub struct AuditLog {
+    path: PathBuf,
+    /// Serializes appends so concurrent tasks never interleave partial lines
+    write_lock: std::sync::Mutex<()>,
+}
+
+impl AuditLog {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            write_lock: std::sync::Mutex::new(()),
+        }
+    }
+
+    /// Append an entry. Failures are logged, never propagated — auditing must not
+    /// break message handling.
+    pub fn record(
+        &self,
+        action: AuditAction,
+        user_id: &str,
+        user_name: Option<&str>,
+        chat_id: Option<&str>,
+        session_key: Option<&str>,
+        detail: Value,
+    ) {
+        let entry = AuditEntry {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            action,
+            user_id: user_id.to_string(),
+            user_name: user_name.map(String::from),
+            chat_id: chat_id.map(String::from),
+            session_key: session_key.map(String::from),
+            detail,
+        };
+        let line = match serde_json::to_string(&entry) {
+            Ok(l) => l,
+            Err(e) => {
+                ulog_warn!("[im-audit] Serialize error: {}", e);
+                return;
+            }
+        };
+
+        let _guard = self.write_lock.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(parent) = self.path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        let result = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut f| writeln!(f, "{}", line));
+        if let Err(e) = result {
+            ulog_warn!("[im-audit] Failed to append to {:?}: {}", self.path, e);
+        }
+    }
+}
+
+/// Filter for reading the audit log (all fields optional)
+#[derive(Debug, Default)]
+pub struct AuditFilter {
+    /// Inclusive lower bound (RFC3339)
+    pub since: Option<chrono::DateTime<chrono::Utc>>,
+    /// Exclusive upper bound (RFC3339)
+    pub until: Option<chrono::DateTime<chrono::Utc>>,
+    pub user_id: Option<String>,
+    /// Keep only the most recent N matching entries
+    pub limit: Option<usize>,
+}
+
+/// Read entries matching `filter`, oldest first. Malformed lines are skipped.
+pub fn read_entries(path: &Path, filter: &AuditFilter) -> Result<Vec<AuditEntry>, String> {
+    let file = match std::fs::File::open(path) {
+        Ok(f) => f,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to open audit log: {}", e)),
+    };
+
+    let mut entries = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(|e| format!("Failed to read audit log: {}", e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry: AuditEntry = match serde_json::from_str(&line) {
+            Ok(e) => e,
+            Err(_) => continue,
+        };
+        if let Some(ref uid) = filter.user_id {
+            if &entry.user_id != uid {
+                continue;
+            }
+        }
+        if filter.since.is_some() || filter.until.is_some() {
+            let ts = match chrono::DateTime::parse_from_rfc3339(&entry.timestamp) {
+                Ok(t) => t.with_timezone(&chrono::Utc),
+                Err(_) => continue,
+            };
+            if filter.since.is_some_and(|since| ts < since) {
+                continue;
+            }
+            if filter.until.is_some_and(|until| ts >= until) {
+                continue;
+            }
+        }
+        entries.push(entry);
+    }
+
+    if let Some(limit) = filter.limit {
+        if entries.len() > limit {
+            entries.drain(..entries.len() - limit);
+        }
+    }
+    Ok(entries)
+}
+
+/// Stable digest of a tool input for the audit trail (FNV-1a 64-bit, hex).
+/// The input itself is not stored — it may contain file contents or secrets.
+pub fn input_digest(input: &str) -> String {
+    const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+    let hash = input.bytes().fold(FNV_OFFSET, |acc, b| {
+        (acc ^ b as u64).wrapping_mul(FNV_PRIME)
+    });
+    format!("fnv1a64:{:016x}", hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn temp_log(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("im-audit-{}-{}.jsonl", name, std::process::id()))
+    }
+
+    fn write_lines(path: &Path, entries: &[(&str, &str)]) {
+        let lines: Vec<String> = entries
+            .iter()
+            .map(|(ts, user)| {
+                json!({ "timestamp": ts, "action": "mode", "userId": user, "detail": {} }).to_string()
+            })
+            .collect();
+        std::fs::write(path, lines.join("\n") + "\n").unwrap();
+    }
+
+    fn utc(ts: &str) -> chrono::DateTime<chrono::Utc> {
+        chrono::DateTime::parse_from_rfc3339(ts).unwrap().with_timezone(&chrono::Utc)
+    }
+
+    fn timestamps(entries: &[AuditEntry]) -> Vec<&str> {
+        entries.iter().map(|e| e.timestamp.as_str()).collect()
+    }
+
+    #[test]
+    fn test_read_entries_time_bounds() {
+        let path = temp_log("bounds");
+        write_lines(
+            &path,
+            &[
+                ("2026-01-01T00:00:00Z", "u1"),
+                ("2026-01-02T00:00:00Z", "u1"),
+                ("2026-01-03T00:00:00Z", "u1"),
+            ],
+        );
+        let filter = AuditFilter {
+            since: Some(utc("2026-01-02T00:00:00Z")),
+            until: Some(utc("2026-01-03T00:00:00Z")),
+            ..Default::default()
+        };
+        let entries = read_entries(&path, &filter).unwrap();
+        // since is inclusive, until exclusive
+        assert_eq!(timestamps(&entries), vec!["2026-01-02T00:00:00Z"]);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_read_entries_user_filter_and_limit() {
+        let path = temp_log("user-limit");
+        write_lines(
+            &path,
+            &[
+                ("2026-01-01T00:00:00Z", "u1"),
+                ("2026-01-02T00:00:00Z", "u2"),
+                ("2026-01-03T00:00:00Z", "u1"),
+                ("2026-01-04T00:00:00Z", "u1"),
+            ],
+        );
+        let filter = AuditFilter { user_id: Some("u1".to_string()), ..Default::default() };
+        assert_eq!(read_entries(&path, &filter).unwrap().len(), 3);
+
+        // limit keeps the newest matches, still oldest first
+        let filter = AuditFilter { user_id: Some("u1".to_string()), limit: Some(2), ..Default::default() };
+        let entries = read_entries(&path, &filter).unwrap();
+        assert_eq!(timestamps(&entries), vec!["2026-01-03T00:00:00Z", "2026-01-04T00:00:00Z"]);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_read_entries_skips_malformed_lines() {
+        let path = temp_log("malformed");
+        let good = json!({ "timestamp": "2026-01-01T00:00:00Z", "action": "bind", "userId": "u1" });
+        let bad_ts = json!({ "timestamp": "yesterday", "action": "bind", "userId": "u1" });
+        std::fs::write(
+            &path,
+            format!("{}\nnot json\n\n{{\"action\":\"unknown\"}}\n{}\n", good, bad_ts),
+        )
+        .unwrap();
+
+        let entries = read_entries(&path, &AuditFilter::default()).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].action, AuditAction::Bind);
+
+        // An unparseable timestamp can't satisfy a time bound
+        let filter = AuditFilter { since: Some(utc("2025-01-01T00:00:00Z")), ..Default::default() };
+        assert_eq!(timestamps(&read_entries(&path, &filter).unwrap()), vec!["2026-01-01T00:00:00Z"]);
+        let _ = std::fs::remove_file(&path);
+
+        // Missing file reads as empty
+        assert!(read_entries(&path, &AuditFilter::default()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_record_then_read() {
+        let path = temp_log("record");
+        let _ = std::fs::remove_file(&path);
+        let log = AuditLog::new(path.clone());
+        log.record(AuditAction::Workspace, "u1", Some("Alice"), Some("c1"), None, json!({ "workspace": "/tmp" }));
+        log.record(AuditAction::Mode, "u2", None, None, None, json!({}));
+
+        let entries = read_entries(&path, &AuditFilter::default()).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].action, AuditAction::Workspace);
+        assert_eq!(entries[0].user_name.as_deref(), Some("Alice"));
+        assert_eq!(entries[0].detail["workspace"], "/tmp");
+        assert_eq!(entries[1].user_id, "u2");
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_input_digest_is_stable() {
+        // FNV-1a 64 reference values
+        assert_eq!(input_digest(""), "fnv1a64:cbf29ce484222325");
+        assert_eq!(input_digest("a"), "fnv1a64:af63dc4c8601ec8c");
+        assert_eq!(input_digest(r#"{"command":"ls"}"#), input_digest(r#"{"command":"ls"}"#));
+        assert_ne!(input_digest(r#"{"command":"ls"}"#), input_digest(r#"{"command":"rm"}"#));
+    }
+}
@@ -0,0 +1,477 @@
+// Message coalescing — a platform-agnostic stage between the adapter and the processing loop.
+// Merges media groups (Telegram albums), fragments of long pastes (Telegram splits >4096 chars)
+// and attachment-only messages with the text that follows them (Feishu sends every picture as
+// its own message), so one user intent becomes one agent turn.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use tokio::sync::{mpsc, watch};
+use tokio::time::{sleep, Instant};
+
+use super::types::{ImMessage, ImPlatform};
+use crate::{ulog_error, ulog_info};
+
+// Defaults (Telegram): fragments of a long paste arrive back to back
+const DEFAULT_DEBOUNCE_MS: u64 = 500;
+const DEFAULT_FRAGMENT_MERGE_MS: u64 = 1500;
+const FRAGMENT_MIN_LENGTH: usize = 4000;
+// Feishu: no paste splitting, but pictures arrive one message each — leave time for the question
+const FEISHU_DEBOUNCE_MS: u64 = 3000;
+const FEISHU_FRAGMENT_MERGE_MS: u64 = 3000;
+const MAX_FRAGMENTS: usize = 12;
+const MAX_MERGED_LENGTH: usize = 50000;
+
+const MEDIA_GROUP_TIMEOUT: Duration = Duration::from_millis(500);
+const CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Per-platform coalescing windows
+#[derive(Debug, Clone)]
+pub struct CoalesceTuning {
+    /// A pending batch is dispatched after this long without a new part
+    pub debounce_ms: u64,
+    /// Max gap between two parts of the same batch
+    pub fragment_merge_ms: u64,
+    /// Text at least this long is treated as a fragment of a split paste (None = never)
+    pub fragment_min_length: Option<usize>,
+}
+
+impl Default for CoalesceTuning {
+    fn default() -> Self {
+        Self {
+            debounce_ms: DEFAULT_DEBOUNCE_MS,
+            fragment_merge_ms: DEFAULT_FRAGMENT_MERGE_MS,
+            fragment_min_length: Some(FRAGMENT_MIN_LENGTH),
+        }
+    }
+}
+
+impl CoalesceTuning {
+    pub fn for_platform(platform: &ImPlatform) -> Self {
+        match platform {
+            ImPlatform::Telegram => Self::default(),
+            ImPlatform::Feishu => Self {
+                debounce_ms: FEISHU_DEBOUNCE_MS,
+                fragment_merge_ms: FEISHU_FRAGMENT_MERGE_MS,
+                fragment_min_length: None,
+            },
+        }
+    }
+}
+
+/// Pending batch of messages being coalesced (one per chat + sender)
+struct PendingBatch {
+    /// Parts merged so far (sender metadata from the first, message ID from the last, the
+    /// other parts' IDs in `merged_message_ids`)
+    merged: ImMessage,
+    parts: usize,
+    last_received: Instant,
+}
+
+/// Merges fragmented messages and attachment-only messages with what follows them,
+/// and debounces the batch until the sender pauses.
+pub struct MessageCoalescer {
+    pending: HashMap<String, PendingBatch>,
+    tuning: CoalesceTuning,
+}
+
+impl Default for MessageCoalescer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MessageCoalescer {
+    pub fn new() -> Self {
+        Self::with_tuning(CoalesceTuning::default())
+    }
+
+    pub fn with_tuning(tuning: CoalesceTuning) -> Self {
+        Self {
+            pending: HashMap::new(),
+            tuning,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Push a message. Returns a vec of messages ready to send.
+    ///
+    /// Plain messages are returned immediately — they bypass the pending buffer
+    /// entirely. Fragments (long pastes the platform split up) and attachment-only
+    /// messages open or extend a batch; a plain message that follows within the merge
+    /// window closes the batch and is sent with it (the question about the pictures).
+    ///
+    /// Edits, menu clicks and commands are never merged. When a message can't extend
+    /// the existing batch, the old batch is flushed first.
+    pub fn push(&mut self, msg: &ImMessage) -> Vec<ImMessage> {
+        let now = Instant::now();
+        let key = batch_key(msg);
+        let passthrough = msg.edited || msg.menu_message_id.is_some() || msg.text.trim_start().starts_with('/');
+        let is_fragment = self
+            .tuning
+            .fragment_min_length
+            .is_some_and(|min| msg.text.len() >= min);
+        let batchable = !passthrough && (is_fragment || is_media_only(msg));
+        let mut ready = Vec::new();
+
+        if let Some(batch) = self.pending.get_mut(&key) {
+            let time_since_last = now.duration_since(batch.last_received).as_millis() as u64;
+
+            // Check if this continues the batch
+            let is_continuation = !passthrough
+                && time_since_last < self.tuning.fragment_merge_ms
+                && is_next_message(&batch.merged.message_id, &msg.message_id);
+
+            if is_continuation
+                && batch.parts < MAX_FRAGMENTS
+                && batch.merged.text.len() + msg.text.len() < MAX_MERGED_LENGTH
+            {
+                append(&mut batch.merged, msg);
+                batch.parts += 1;
+                batch.last_received = now;
+                if !batchable {
+                    // The text the batch was waiting for — send it all now
+                    ready.extend(self.pending.remove(&key).map(|b| b.merged));
+                }
+                return ready; // Otherwise still waiting for more parts
+            }
+
+            // Not a continuation — flush the old batch
+            ready.extend(self.pending.remove(&key).map(|b| b.merged));
+        }
+
+        if batchable {
+            // Buffer: wait for more parts before sending
+            self.pending.insert(
+                key,
+                PendingBatch {
+                    merged: msg.clone(),
+                    parts: 1,
+                    last_received: now,
+                },
+            );
+        } else {
+            // Plain message: return immediately, no debounce needed
+            ready.push(msg.clone());
+        }
+
+        ready
+    }
+
+    /// Flush all batches that have exceeded the debounce timeout.
+    pub fn flush_expired(&mut self) -> Vec<ImMessage> {
+        let now = Instant::now();
+
+        let expired_keys: Vec<String> = self
+            .pending
+            .iter()
+            .filter(|(_, batch)| {
+                now.duration_since(batch.last_received).as_millis() as u64 >= self.tuning.debounce_ms
+            })
+            .map(|(k, _)| k.clone())
+            .collect();
+
+        expired_keys
+            .iter()
+            .filter_map(|key| self.pending.remove(key))
+            .map(|batch| batch.merged)
+            .collect()
+    }
+}
+
+/// Batches are per sender — in groups, two people's messages never merge
+fn batch_key(msg: &ImMessage) -> String {
+    format!("{}:{}", msg.chat_id, msg.sender_id)
+}
+
+/// Attachments whose text is only adapter placeholders ("[图片]", "[文件: a.pdf]")
+fn is_media_only(msg: &ImMessage) -> bool {
+    !msg.attachments.is_empty()
+        && msg
+            .text
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .all(|l| l.starts_with('[') && l.ends_with(']'))
+}
+
+/// Numeric IDs (Telegram) must be consecutive; opaque IDs (Feishu) can't be checked
+fn is_next_message(prev_id: &str, next_id: &str) -> bool {
+    match (prev_id.parse::<i64>(), next_id.parse::<i64>()) {
+        (Ok(prev), Ok(next)) => next == prev + 1,
+        _ => true,
+    }
+}
+
+/// Fold `msg` into the batch's merged message
+fn append(merged: &mut ImMessage, msg: &ImMessage) {
+    if merged.text.is_empty() {
+        merged.text = msg.text.clone();
+    } else if !msg.text.is_empty() {
+        merged.text = format!("{}\n{}", merged.text, msg.text);
+    }
+    merged.attachments.extend(msg.attachments.iter().cloned());
+    merged.rich_content.extend(msg.rich_content.iter().cloned());
+    if merged.forwarded_from.is_none() {
+        merged.forwarded_from = msg.forwarded_from.clone();
+    }
+    // The merged message answers to the last part; earlier parts still carry reactions
+    let previous_id = std::mem::replace(&mut merged.message_id, msg.message_id.clone());
+    merged.merged_message_ids.push(previous_id);
+    merged.merged_message_ids.extend(msg.merged_message_ids.iter().cloned());
+    merged.timestamp = msg.timestamp;
+}
+
+/// Merge buffered media group messages into one combined message
+fn merge_media_group(mut messages: Vec<ImMessage>) -> ImMessage {
+    messages.sort_by_key(|m| m.message_id.parse::<i64>().unwrap_or(0));
+    let mut base = messages.remove(0);
+    // Use first non-empty text as caption
+    if base.text.is_empty() {
+        if let Some(msg_with_text) = messages.iter().find(|m| !m.text.is_empty()) {
+            base.text = msg_with_text.text.clone();
+        }
+    }
+    // Merge all attachments
+    for msg in messages {
+        base.attachments.extend(msg.attachments);
+        base.merged_message_ids.push(msg.message_id);
+    }
+    base.media_group_id = None; // Already merged
+    base
+}
+
+/// Media group buffering (Telegram albums)
+struct MediaGroupEntry {
+    messages: Vec<ImMessage>,
+    first_received: Instant,
+}
+
+/// Run the coalescing stage: reads adapter messages from `rx`, forwards coalesced ones to
+/// `tx` (the processing loop). Exits on shutdown or when either channel closes.
+pub fn spawn_stage(
+    platform: ImPlatform,
+    mut rx: mpsc::Receiver<ImMessage>,
+    tx: mpsc::Sender<ImMessage>,
+    mut shutdown_rx: watch::Receiver<bool>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut coalescer = MessageCoalescer::with_tuning(CoalesceTuning::for_platform(&platform));
+        let mut media_groups: HashMap<String, MediaGroupEntry> = HashMap::new();
+
+        loop {
+            // Only wake up for flushing while something is pending
+            let flush_timeout = if media_groups.is_empty() && coalescer.is_empty() {
+                Duration::from_secs(3600)
+            } else {
+                CHECK_INTERVAL
+            };
+
+            let ready = tokio::select! {
+                msg = rx.recv() => {
+                    let Some(msg) = msg else { break };
+                    // Buffer media group messages
+                    if let Some(ref group_id) = msg.media_group_id {
+                        media_groups
+                            .entry(group_id.clone())
+                            .or_insert_with(|| MediaGroupEntry {
+                                messages: Vec::new(),
+                                first_received: Instant::now(),
+                            })
+                            .messages
+                            .push(msg);
+                        continue;
+                    }
+                    coalescer.push(&msg)
+                }
+                _ = sleep(flush_timeout) => {
+                    let mut ready = Vec::new();
+                    let expired_keys: Vec<String> = media_groups
+                        .iter()
+                        .filter(|(_, entry)| entry.first_received.elapsed() >= MEDIA_GROUP_TIMEOUT)
+                        .map(|(k, _)| k.clone())
+                        .collect();
+                    for group_id in expired_keys {
+                        if let Some(entry) = media_groups.remove(&group_id) {
+                            let merged = merge_media_group(entry.messages);
+                            ulog_info!(
+                                "[im] Flushed media group {} ({} attachments)",
+                                group_id,
+                                merged.attachments.len(),
+                            );
+                            // An album can still be followed by its question
+                            ready.extend(coalescer.push(&merged));
+                        }
+                    }
+                    for expired in coalescer.flush_expired() {
+                        ulog_info!(
+                            "[im] Flushing coalesced batch for chat {} ({} attachments, {} chars)",
+                            expired.chat_id,
+                            expired.attachments.len(),
+                            expired.text.len(),
+                        );
+                        ready.push(expired);
+                    }
+                    ready
+                }
+                _ = shutdown_rx.changed() => {
+                    if *shutdown_rx.borrow() {
+                        break;
+                    }
+                    continue;
+                }
+            };
+
+            for msg in ready {
+                if tx.send(msg).await.is_err() {
+                    ulog_error!("[im] Processing loop channel closed");
+                    return;
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::im::types::{ImAttachment, ImAttachmentType, ImSourceType};
+
+    fn make_test_msg(chat_id: &str, msg_id: i64, text: &str) -> ImMessage {
+        ImMessage {
+            chat_id: chat_id.to_string(),
+            message_id: msg_id.to_string(),
+            text: text.to_string(),
+            sender_id: "42".to_string(),
+            sender_name: Some("testuser".to_string()),
+            source_type: ImSourceType::Private,
+            platform: ImPlatform::Telegram,
+            timestamp: chrono::Utc::now(),
+            attachments: Vec::new(),
+            media_group_id: None,
+            menu_message_id: None,
+            edited: false,
+            rich_content: Vec::new(),
+            forwarded_from: None,
+            merged_message_ids: Vec::new(),
+        }
+    }
+
+    fn make_image_msg(chat_id: &str, msg_id: &str) -> ImMessage {
+        let mut msg = make_test_msg(chat_id, 0, "[图片]");
+        msg.message_id = msg_id.to_string();
+        msg.platform = ImPlatform::Feishu;
+        msg.attachments.push(ImAttachment {
+            file_name: format!("{}.png", msg_id),
+            mime_type: "image/png".to_string(),
+            data: vec![1, 2, 3],
+            attachment_type: ImAttachmentType::Image,
+        });
+        msg
+    }
+
+    #[test]
+    fn test_coalescer_single_short_message_immediate() {
+        let mut c = MessageCoalescer::new();
+        // Short message should be returned immediately (not buffered)
+        let msg = make_test_msg("chat1", 1, "hello");
+        let result = c.push(&msg);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].text, "hello");
+        assert_eq!(result[0].sender_id, "42");
+        assert_eq!(result[0].sender_name.as_deref(), Some("testuser"));
+    }
+
+    #[test]
+    fn test_coalescer_fragment_merge() {
+        let mut c = MessageCoalescer::new();
+        let long_text = "a".repeat(4100);
+        // First fragment — buffered, waiting for more
+        let msg1 = make_test_msg("chat1", 1, &long_text);
+        let result = c.push(&msg1);
+        assert!(result.is_empty());
+
+        // Second fragment (continuation: >= 4000 chars, consecutive msg_id)
+        let long_text2 = "b".repeat(4100);
+        let msg2 = make_test_msg("chat1", 2, &long_text2);
+        let result = c.push(&msg2);
+        assert!(result.is_empty()); // Still pending
+
+        // Non-fragment message flushes old batch and is returned immediately
+        let msg3 = make_test_msg("chat1", 100, "new message");
+        let result = c.push(&msg3);
+        assert_eq!(result.len(), 2); // flushed batch + new message
+        assert!(result[0].text.contains("aaa"));
+        assert!(result[0].text.contains("bbb"));
+        assert_eq!(result[0].sender_id, "42"); // sender metadata preserved
+        assert_eq!(result[1].text, "new message");
+    }
+
+    #[test]
+    fn test_coalescer_images_then_question() {
+        let mut c = MessageCoalescer::with_tuning(CoalesceTuning::for_platform(&ImPlatform::Feishu));
+        for id in ["om_1", "om_2", "om_3"] {
+            assert!(c.push(&make_image_msg("oc_1", id)).is_empty());
+        }
+        let mut question = make_test_msg("oc_1", 0, "这三张图有什么区别？");
+        question.message_id = "om_4".to_string();
+        let result = c.push(&question);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].attachments.len(), 3);
+        assert_eq!(result[0].message_id, "om_4");
+        assert!(result[0].text.ends_with("这三张图有什么区别？"));
+        assert!(c.is_empty());
+
+        // Commands are never merged: the pending image goes out first, on its own
+        assert!(c.push(&make_image_msg("oc_1", "om_5")).is_empty());
+        let result = c.push(&make_test_msg("oc_1", 6, "/new"));
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[1].text, "/new");
+    }
+
+    #[test]
+    fn test_merged_message_keeps_part_ids() {
+        // Pictures then the question: every part is acknowledged, not just the question
+        let mut c = MessageCoalescer::with_tuning(CoalesceTuning::for_platform(&ImPlatform::Feishu));
+        for id in ["om_1", "om_2", "om_3"] {
+            assert!(c.push(&make_image_msg("oc_1", id)).is_empty());
+        }
+        let mut question = make_test_msg("oc_1", 0, "这三张图有什么区别？");
+        question.message_id = "om_4".to_string();
+        let merged = c.push(&question).remove(0);
+        assert_eq!(merged.message_id, "om_4");
+        assert_eq!(merged.part_ids(), vec!["om_1", "om_2", "om_3", "om_4"]);
+
+        // A Telegram album keeps its other items' IDs
+        let album: Vec<ImMessage> = [12, 10, 11]
+            .iter()
+            .map(|id| {
+                let mut msg = make_image_msg("chat1", &id.to_string());
+                msg.platform = ImPlatform::Telegram;
+                msg.media_group_id = Some("g1".to_string());
+                msg
+            })
+            .collect();
+        let mut c = MessageCoalescer::with_tuning(CoalesceTuning { debounce_ms: 0, ..CoalesceTuning::default() });
+        assert!(c.push(&merge_media_group(album)).is_empty());
+        let flushed = c.flush_expired();
+        assert_eq!(flushed[0].message_id, "10");
+        assert_eq!(flushed[0].part_ids(), vec!["11", "12", "10"]);
+    }
+
+    #[test]
+    fn test_coalescer_flush_expired() {
+        let mut c = MessageCoalescer::with_tuning(CoalesceTuning {
+            debounce_ms: 0,
+            ..CoalesceTuning::for_platform(&ImPlatform::Feishu)
+        });
+        assert!(c.push(&make_image_msg("oc_1", "om_1")).is_empty());
+        let flushed = c.flush_expired();
+        assert_eq!(flushed.len(), 1);
+        assert_eq!(flushed[0].attachments.len(), 1);
+        assert!(c.is_empty());
+    }
+}
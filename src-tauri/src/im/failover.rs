@@ -0,0 +1,216 @@
+// Provider failover for IM turns.
+// When a turn fails with a provider-side error (auth, quota / rate limit, 5xx) before any
+// text reached the user or any tool ran, the turn is retried on the next provider of the
+// bot's ordered fallback list. A provider that failed is skipped for `PROVIDER_COOLDOWN`.
+// Errors arrive as the Sidecar's `error` SSE event — either raw API text or the localized
+// message produced by `localizeImError`, so both forms are matched.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use super::providers::ProviderInfo;
+
+/// How long a failed provider is skipped before it is tried again
+pub const PROVIDER_COOLDOWN: Duration = Duration::from_secs(5 * 60);
+
+/// Provider-side failure classes that trigger failover
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FailureKind {
+    /// Invalid / revoked API key
+    Auth,
+    /// Out of credit or rate limited
+    Quota,
+    /// Provider overloaded or returning 5xx
+    Server,
+}
+
+impl FailureKind {
+    /// Short label for the reply notice
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Auth => "认证失败",
+            Self::Quota => "额度不足或限流",
+            Self::Server => "服务异常",
+        }
+    }
+}
+
+/// Failure class of an HTTP status; None for statuses another provider wouldn't fix
+/// (400 bad request, 404 unknown model, 413 too large, ...)
+fn classify_status(status: u16) -> Option<FailureKind> {
+    match status {
+        401 | 403 => Some(FailureKind::Auth),
+        402 | 429 => Some(FailureKind::Quota),
+        500..=599 => Some(FailureKind::Server),
+        _ => None,
+    }
+}
+
+/// Three-digit status code at the start of `s`, not followed by another digit or letter
+fn leading_status(s: &str) -> Option<u16> {
+    let len = s.bytes().take_while(u8::is_ascii_digit).count();
+    if len != 3 || s[len..].starts_with(|c: char| c.is_alphanumeric() || c == '.') {
+        return None;
+    }
+    s[..len].parse().ok().filter(|code| (100..600).contains(code))
+}
+
+/// HTTP status stated in an error message: a leading code ("529 {…}", the SDK's API error
+/// format) or one right after "api error", "status", "status code" or "http" ("HTTP/1.1 503").
+/// Bare numbers elsewhere are ignored — token counts and ports contain 401, 500, ...
+fn stated_status(lower: &str) -> Option<u16> {
+    if let Some(code) = leading_status(lower) {
+        return Some(code);
+    }
+    ["api error", "status code", "status", "http"].iter().find_map(|prefix| {
+        lower.match_indices(prefix).find_map(|(i, _)| {
+            if lower[..i].chars().next_back().is_some_and(char::is_alphanumeric) {
+                return None;
+            }
+            let mut rest = &lower[i + prefix.len()..];
+            if *prefix == "http" {
+                if let Some(version) = rest.strip_prefix('/') {
+                    rest = version.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.');
+                }
+            }
+            leading_status(rest.trim_start_matches(|c: char| c == ':' || c == '=' || c.is_whitespace()))
+        })
+    })
+}
+
+/// Classify a Sidecar error. `status` is the HTTP status the Sidecar read from the API error;
+/// without one, the status stated in the text is used, then keywords. None for errors
+/// another provider wouldn't fix (unsupported images, invalid model, prompt too long,
+/// engine crash, superseded request, ...).
+pub fn classify(error: &str, status: Option<u16>) -> Option<FailureKind> {
+    let lower = error.to_lowercase();
+    if let Some(kind) = status.or_else(|| stated_status(&lower)).and_then(classify_status) {
+        return Some(kind);
+    }
+
+    let has = |needles: &[&str]| needles.iter().any(|n| lower.contains(n));
+    if has(&["api 认证失败", "authentication", "unauthorized", "invalid x-api-key"]) {
+        Some(FailureKind::Auth)
+    } else if has(&[
+        "api 余额不足",
+        "api 请求频率超限",
+        "billing",
+        "insufficient_quota",
+        "quota_exceeded",
+        "rate_limit",
+    ]) {
+        Some(FailureKind::Quota)
+    } else if has(&[
+        "ai 服务繁忙",
+        "overloaded",
+        "internal server error",
+        "bad gateway",
+        "service unavailable",
+    ]) {
+        Some(FailureKind::Server)
+    } else {
+        None
+    }
+}
+
+/// Providers to try for a turn: the current provider first, then the configured fallback
+/// IDs in order. Unknown IDs and duplicates are dropped.
+pub fn failover_chain<'a>(
+    providers: &'a [ProviderInfo],
+    current: &'a ProviderInfo,
+    fallback_ids: &[String],
+) -> Vec<&'a ProviderInfo> {
+    let mut chain = vec![current];
+    for id in fallback_ids {
+        if let Some(p) = providers.iter().find(|p| &p.id == id) {
+            if !chain.iter().any(|c| c.id == p.id) {
+                chain.push(p);
+            }
+        }
+    }
+    chain
+}
+
+/// Providers that recently failed, keyed by provider ID
+#[derive(Debug, Default)]
+pub struct ProviderCooldowns {
+    until: HashMap<String, Instant>,
+}
+
+impl ProviderCooldowns {
+    pub fn mark_failed(&mut self, provider_id: &str, now: Instant) {
+        self.until.insert(provider_id.to_string(), now + PROVIDER_COOLDOWN);
+    }
+
+    pub fn is_cooling(&self, provider_id: &str, now: Instant) -> bool {
+        self.until.get(provider_id).is_some_and(|t| now < *t)
+    }
+
+    /// Drop `chain` entries that are cooling down, unless every entry is (then the chain
+    /// is kept as is — trying a cooling provider beats not answering).
+    pub fn filter<'a>(&mut self, chain: Vec<&'a ProviderInfo>, now: Instant) -> Vec<&'a ProviderInfo> {
+        self.until.retain(|_, t| now < *t);
+        if chain.iter().all(|p| self.is_cooling(&p.id, now)) {
+            return chain;
+        }
+        chain.into_iter().filter(|p| !self.is_cooling(&p.id, now)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::im::providers::parse_providers;
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(classify("API 认证失败，请检查 API Key 配置", None), Some(FailureKind::Auth));
+        assert_eq!(classify("API 余额不足，请充值后重试", None), Some(FailureKind::Quota));
+        assert_eq!(classify("429 {\"type\":\"rate_limit_error\"}", None), Some(FailureKind::Quota));
+        assert_eq!(classify("AI 服务繁忙，请稍后重试", None), Some(FailureKind::Server));
+        assert_eq!(classify("API Error: 502 Bad Gateway", None), Some(FailureKind::Server));
+        assert_eq!(classify("upstream returned HTTP/1.1 503", None), Some(FailureKind::Server));
+        assert_eq!(classify("request failed with status code: 401", None), Some(FailureKind::Auth));
+        assert_eq!(classify("当前模型不支持图片，请发送文字消息", None), None);
+        assert_eq!(classify("消息处理被新请求取代，请重新发送", None), None);
+    }
+
+    #[test]
+    fn test_classify_ignores_bare_numbers() {
+        assert_eq!(classify("prompt is too long: 250000 tokens > 200000 maximum", None), None);
+        assert_eq!(classify("prompt is too long: 204010 tokens", None), None);
+        assert_eq!(classify("connect ECONNREFUSED 127.0.0.1:5000", None), None);
+        assert_eq!(classify("proxy http://10.0.0.2:8500 refused the connection (port 500)", None), None);
+        assert_eq!(classify("tool output exceeded 4290 lines", None), None);
+    }
+
+    #[test]
+    fn test_classify_prefers_structured_status() {
+        assert_eq!(classify("prompt is too long: 250000 tokens", Some(400)), None);
+        assert_eq!(classify("模型处理消息时出错", Some(529)), Some(FailureKind::Server));
+        assert_eq!(classify("Your credit balance is too low (billing)", Some(400)), Some(FailureKind::Quota));
+        assert_eq!(classify("Forbidden", Some(403)), Some(FailureKind::Auth));
+    }
+
+    #[test]
+    fn test_chain_and_cooldown() {
+        let list = parse_providers(Some(
+            r#"[{"id": "anthropic-sub"}, {"id": "deepseek", "baseUrl": "https://a"},
+                {"id": "moonshot", "baseUrl": "https://b"}]"#,
+        ));
+        let ids = |c: &[&ProviderInfo]| c.iter().map(|p| p.id.clone()).collect::<Vec<_>>();
+        let fallbacks = vec!["moonshot".to_string(), "gone".to_string(), "deepseek".to_string()];
+        let chain = failover_chain(&list, &list[1], &fallbacks);
+        assert_eq!(ids(&chain), vec!["deepseek", "moonshot"]);
+
+        let now = Instant::now();
+        let mut cooldowns = ProviderCooldowns::default();
+        cooldowns.mark_failed("deepseek", now);
+        assert_eq!(ids(&cooldowns.filter(chain.clone(), now)), vec!["moonshot"]);
+        cooldowns.mark_failed("moonshot", now);
+        assert_eq!(ids(&cooldowns.filter(chain.clone(), now)), vec!["deepseek", "moonshot"]);
+        let later = now + PROVIDER_COOLDOWN + Duration::from_secs(1);
+        assert!(!cooldowns.is_cooling("deepseek", later));
+        assert_eq!(ids(&cooldowns.filter(chain, later)), vec!["deepseek", "moonshot"]);
+    }
+}
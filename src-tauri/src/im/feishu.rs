@@ -2,8 +2,8 @@
 // Handles WebSocket long connection, message sending/editing/deleting,
 // tenant_access_token management, and event parsing.
 
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
@@ -18,9 +18,13 @@ use prost::Message as ProstMessage;
 
 use pulldown_cmark::{CodeBlockKind, Event, Options, Parser, Tag, TagEnd};
 
-use super::types::{ImAttachment, ImAttachmentType, ImConfig, ImMessage, ImPlatform, ImSourceType};
-use super::util::{mime_to_ext, sanitize_filename};
-use super::ApprovalCallback;
+use super::adapter::ResponseMeta;
+use super::outbound::{OutboundLimits, OutboundQueue, Priority};
+use super::split::{part_label, split_markdown, LengthUnit, PART_LABEL_RESERVE};
+use super::telegram_html::render_table;
+use super::types::{ImAttachment, ImAttachmentType, ImConfig, ImMessage, ImPlatform, ImRichContent, ImSourceType, MenuOption};
+use super::util::{mime_to_ext, multipart_body, sanitize_filename};
+use super::{ApprovalCallback, ImCallback};
 use crate::{proxy_config, ulog_info, ulog_warn, ulog_error, ulog_debug};
 
 // ── Feishu WebSocket Protobuf Frame ──────────────────────────
@@ -71,6 +75,17 @@ const DEDUP_PERSIST_INTERVAL_MS: u64 = 500;
 
 /// Feishu API base URL
 const FEISHU_API_BASE: &str = "https://open.feishu.cn/open-apis";
+/// Reaction shown when a message is received / being processed (Feishu emoji_type keys)
+const REACTION_RECEIVED: &str = "Get";
+const REACTION_PROCESSING: &str = "OnIt";
+/// API error codes meaning the bot may not react in a chat: app lacks the scope
+/// (99991672 / 99991679) or no permission to react to messages there (231002)
+const REACTION_PERMISSION_CODES: &[i64] = &[99991672, 99991679, 231002];
+/// Max Markdown bytes per post message. Post/card request bodies are capped at 30 KB and
+/// the post JSON (one element per styled run) is larger than the Markdown it came from.
+const MAX_POST_MARKDOWN_BYTES: usize = 20_000;
+/// Max retries when Feishu reports frequency limiting (HTTP 429 / code 99991400)
+const RATE_LIMIT_MAX_RETRIES: u32 = 3;
 /// Token refresh margin (refresh when < 10 min remaining)
 const TOKEN_REFRESH_MARGIN_SECS: u64 = 600;
 /// Token validity period (Feishu tokens are valid for 2 hours)
@@ -80,6 +95,14 @@ const WS_INITIAL_BACKOFF_SECS: u64 = 1;
 /// WebSocket reconnect max backoff
 const WS_MAX_BACKOFF_SECS: u64 = 60;
 
+/// Whether an `api_call` error ("Feishu API error code N: …") is a permission/scope error
+fn is_reaction_permission_error(err: &str) -> bool {
+    err.strip_prefix("Feishu API error code ")
+        .and_then(|rest| rest.split(':').next())
+        .and_then(|code| code.trim().parse::<i64>().ok())
+        .is_some_and(|code| REACTION_PERMISSION_CODES.contains(&code))
+}
+
 /// Persist dedup cache to disk (atomic: write tmp → rename).
 /// Free function so it can be used from `spawn_blocking` ('static closure).
 fn save_dedup_cache_to_disk(path: &std::path::Path, cache: &HashMap<String, u64>) {
@@ -117,11 +140,56 @@ enum ListKind {
     Ordered(u64), // current item number
 }
 
+/// Table being collected: plain-text cells per row, leading rows are the header
+#[derive(Default)]
+struct PostTable {
+    rows: Vec<Vec<String>>,
+    header_rows: usize,
+}
+
+/// Image sources referenced by `![alt](src)` in Markdown, in order of appearance
+fn markdown_image_sources(md: &str) -> Vec<String> {
+    let mut sources = Vec::new();
+    for event in Parser::new_ext(md, Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH) {
+        if let Event::Start(Tag::Image { dest_url, .. }) = event {
+            if !sources.iter().any(|s| s == dest_url.as_ref()) {
+                sources.push(dest_url.to_string());
+            }
+        }
+    }
+    sources
+}
+
+/// Resolve a Markdown image source to a canonical file inside `root` (already canonical).
+/// Relative paths and `file://` URLs resolve against `root`; remote URLs, missing files,
+/// paths outside `root` and non-image files give `None`.
+fn resolve_image_path(root: &Path, src: &str) -> Option<PathBuf> {
+    if src.contains("://") && !src.starts_with("file://") {
+        return None;
+    }
+    let raw = src.strip_prefix("file://").unwrap_or(src);
+    let candidate = if Path::new(raw).is_absolute() {
+        PathBuf::from(raw)
+    } else {
+        root.join(raw)
+    };
+    let path = candidate.canonicalize().ok()?;
+    if !path.starts_with(root) {
+        ulog_warn!("[feishu] Skipping image outside workspace: {}", src);
+        return None;
+    }
+    let file_name = path.file_name()?.to_str()?;
+    (image_mime(file_name) != "application/octet-stream").then_some(path)
+}
+
 /// Convert Markdown text to Feishu Post rich-text format.
+/// `image_keys` maps image sources to uploaded Feishu image keys; those images become
+/// `img` elements, others fall back to a link (remote URLs) or their alt text.
 /// Returns a serde_json::Value with the structure: {"zh_cn": {"content": [[...], ...]}}
-fn markdown_to_feishu_post(md: &str) -> Value {
+fn markdown_to_feishu_post(md: &str, image_keys: &HashMap<String, String>) -> Value {
     let mut opts = Options::empty();
     opts.insert(Options::ENABLE_STRIKETHROUGH);
+    opts.insert(Options::ENABLE_TABLES);
     let parser = Parser::new_ext(md, opts);
 
     let mut paragraphs: Vec<Vec<Value>> = Vec::new();
@@ -133,9 +201,63 @@ fn markdown_to_feishu_post(md: &str) -> Value {
     let mut list_stack: Vec<ListKind> = Vec::new();
     let mut item_prefix: Option<String> = None;
     let mut in_blockquote = false;
+    let mut table: Option<PostTable> = None;
+    // (src, alt text) of the image being parsed
+    let mut image: Option<(String, String)> = None;
 
     for event in parser {
         match event {
+            Event::Start(Tag::Table(_)) => {
+                if !current_line.is_empty() {
+                    paragraphs.push(std::mem::take(&mut current_line));
+                }
+                table = Some(PostTable::default());
+            }
+            Event::End(TagEnd::Table) => {
+                if let Some(t) = table.take() {
+                    // Feishu Post has no table element: space-aligned columns in a code block,
+                    // whose monospace font keeps them lined up
+                    paragraphs.push(vec![json!({
+                        "tag": "code_block",
+                        "language": "PLAIN_TEXT",
+                        "text": render_table(&t.rows, t.header_rows),
+                    })]);
+                }
+            }
+            Event::Start(Tag::TableHead) | Event::Start(Tag::TableRow) => {
+                if let Some(t) = table.as_mut() {
+                    t.rows.push(Vec::new());
+                }
+            }
+            Event::End(TagEnd::TableHead) => {
+                if let Some(t) = table.as_mut() {
+                    t.header_rows = t.rows.len();
+                }
+            }
+            Event::Start(Tag::TableCell) => {
+                if let Some(row) = table.as_mut().and_then(|t| t.rows.last_mut()) {
+                    row.push(String::new());
+                }
+            }
+            Event::Start(Tag::Image { dest_url, .. }) => {
+                image = Some((dest_url.to_string(), String::new()));
+            }
+            Event::End(TagEnd::Image) => {
+                let Some((src, alt)) = image.take() else { continue };
+                if let Some(key) = image_keys.get(&src) {
+                    // img must sit in its own paragraph
+                    if !current_line.is_empty() {
+                        paragraphs.push(std::mem::take(&mut current_line));
+                    }
+                    paragraphs.push(vec![json!({"tag": "img", "image_key": key})]);
+                } else if src.starts_with("http://") || src.starts_with("https://") {
+                    let label = if alt.is_empty() { src.clone() } else { alt };
+                    current_line.push(json!({"tag": "a", "text": label, "href": src}));
+                } else {
+                    let label = if alt.is_empty() { src } else { alt };
+                    current_line.push(json!({"tag": "text", "text": format!("[🖼 {}]", label)}));
+                }
+            }
             Event::Start(Tag::Strong) => {
                 styles.push("bold".to_string());
             }
@@ -254,6 +376,14 @@ fn markdown_to_feishu_post(md: &str) -> Value {
                     code_block_buf.push_str(&text);
                     continue;
                 }
+                if let Some((_, alt)) = image.as_mut() {
+                    alt.push_str(&text);
+                    continue;
+                }
+                if let Some(cell) = table.as_mut().and_then(|t| t.rows.last_mut()).and_then(|r| r.last_mut()) {
+                    cell.push_str(&text);
+                    continue;
+                }
 
                 let text_str = text.to_string();
 
@@ -296,6 +426,10 @@ fn markdown_to_feishu_post(md: &str) -> Value {
             Event::Code(code) => {
                 // Inline code: map to bold+italic (Feishu has no inline code style)
                 let code_text = code.to_string();
+                if let Some(cell) = table.as_mut().and_then(|t| t.rows.last_mut()).and_then(|r| r.last_mut()) {
+                    cell.push_str(&code_text);
+                    continue;
+                }
                 if let Some(prefix) = item_prefix.take() {
                     // Emit prefix as plain text, then code as styled
                     current_line.push(json!({"tag": "text", "text": prefix}));
@@ -344,6 +478,152 @@ fn markdown_to_feishu_post(md: &str) -> Value {
     })
 }
 
+// ── Streaming reply card (card JSON 2.0) ─────────────────────
+
+/// Most table components sent in one card; later tables stay Markdown
+const MAX_CARD_TABLES: usize = 5;
+/// Rows shown per page of a card table
+const CARD_TABLE_PAGE_SIZE: usize = 10;
+
+/// Card table component for `rows` (leading `header_rows` become the column names)
+fn card_table(rows: &[Vec<String>], header_rows: usize) -> Value {
+    let columns_len = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let header = rows.first().filter(|_| header_rows > 0);
+    let columns: Vec<Value> = (0..columns_len)
+        .map(|c| {
+            let name = header.and_then(|h| h.get(c)).map(|n| n.trim().to_string()).unwrap_or_default();
+            json!({ "name": format!("c{}", c), "display_name": name, "data_type": "text", "width": "auto" })
+        })
+        .collect();
+    let body: Vec<Value> = rows
+        .iter()
+        .skip(header_rows)
+        .map(|row| {
+            let cells: serde_json::Map<String, Value> = (0..columns_len)
+                .map(|c| (format!("c{}", c), json!(row.get(c).map(|v| v.trim()).unwrap_or(""))))
+                .collect();
+            Value::Object(cells)
+        })
+        .collect();
+    json!({
+        "tag": "table",
+        "page_size": body.len().clamp(1, CARD_TABLE_PAGE_SIZE),
+        "row_height": "low",
+        "header_style": { "bold": true, "background_style": "grey" },
+        "columns": columns,
+        "rows": body,
+    })
+}
+
+/// Card body elements for Markdown `text`: markdown components, with each table (up to
+/// `MAX_CARD_TABLES`) as a table component in its place
+fn card_body_elements(text: &str) -> Vec<Value> {
+    fn push_markdown(elements: &mut Vec<Value>, md: &str) {
+        if !md.trim().is_empty() {
+            elements.push(json!({ "tag": "markdown", "content": md.trim_matches('\n') }));
+        }
+    }
+    let mut elements = Vec::new();
+    let mut markdown_from = 0;
+    let mut table: Option<PostTable> = None;
+    let mut tables = 0;
+    let parser = Parser::new_ext(text, Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH);
+    for (event, range) in parser.into_offset_iter() {
+        match event {
+            Event::Start(Tag::Table(_)) if tables < MAX_CARD_TABLES => {
+                push_markdown(&mut elements, &text[markdown_from..range.start]);
+                table = Some(PostTable::default());
+            }
+            Event::End(TagEnd::Table) => {
+                if let Some(t) = table.take() {
+                    elements.push(card_table(&t.rows, t.header_rows));
+                    tables += 1;
+                    markdown_from = range.end;
+                }
+            }
+            Event::Start(Tag::TableHead) | Event::Start(Tag::TableRow) => {
+                if let Some(t) = table.as_mut() {
+                    t.rows.push(Vec::new());
+                }
+            }
+            Event::End(TagEnd::TableHead) => {
+                if let Some(t) = table.as_mut() {
+                    t.header_rows = t.rows.len();
+                }
+            }
+            Event::Start(Tag::TableCell) => {
+                if let Some(row) = table.as_mut().and_then(|t| t.rows.last_mut()) {
+                    row.push(String::new());
+                }
+            }
+            Event::Text(t) | Event::Code(t) => {
+                if let Some(cell) = table.as_mut().and_then(|t| t.rows.last_mut()).and_then(|r| r.last_mut()) {
+                    cell.push_str(&t);
+                }
+            }
+            _ => {}
+        }
+    }
+    push_markdown(&mut elements, &text[markdown_from..]);
+    if elements.is_empty() {
+        // Keep the card's body element while the reply is still empty
+        elements.push(json!({ "tag": "markdown", "content": text }));
+    }
+    elements
+}
+
+/// Build a reply card: markdown body (tables as table components), plus (once the
+/// response is complete) a collapsed tool-activity panel and a model/duration (and
+/// optionally token usage) footer.
+fn build_reply_card(text: &str, meta: Option<&ResponseMeta<'_>>) -> Value {
+    let mut elements = card_body_elements(text);
+
+    if let Some(meta) = meta {
+        if !meta.tools.is_empty() {
+            // Aggregate repeated tools, keeping first-use order: "Read ×3"
+            let mut counts: Vec<(&str, usize)> = Vec::new();
+            for name in meta.tools {
+                match counts.iter_mut().find(|(n, _)| n == name) {
+                    Some((_, c)) => *c += 1,
+                    None => counts.push((name, 1)),
+                }
+            }
+            let lines: Vec<String> = counts
+                .iter()
+                .map(|(name, c)| if *c > 1 { format!("- `{}` ×{}", name, c) } else { format!("- `{}`", name) })
+                .collect();
+            elements.push(json!({
+                "tag": "collapsible_panel",
+                "expanded": false,
+                "header": {
+                    "title": { "tag": "markdown", "content": format!("🛠 工具调用（{}）", meta.tools.len()) }
+                },
+                "elements": [{ "tag": "markdown", "content": lines.join("\n") }]
+            }));
+        }
+
+        let duration = format!("{:.1}s", meta.duration.as_secs_f64());
+        let mut footer = match meta.model {
+            Some(model) => format!("{} · {}", model, duration),
+            None => duration,
+        };
+        if let Some(usage) = meta.usage {
+            footer = format!("{} · {}", footer, super::usage::format_footer(usage));
+        }
+        if let Some(notice) = meta.notice {
+            footer = format!("{}\n⚠️ {}", footer, notice);
+        }
+        elements.push(json!({ "tag": "hr" }));
+        elements.push(json!({ "tag": "markdown", "content": footer, "text_size": "notation" }));
+    }
+
+    json!({
+        "schema": "2.0",
+        "config": { "update_multi": true },
+        "body": { "elements": elements }
+    })
+}
+
 // ── Feishu Post → plain text converter (receive direction) ──
 
 /// Extract plain text from a Feishu Post rich-text content JSON.
@@ -487,6 +767,21 @@ fn extract_post_image_keys(content: &Value) -> Vec<String> {
     keys
 }
 
+/// MIME type for an image file name (octet-stream for anything that isn't an image)
+fn image_mime(file_name: &str) -> &'static str {
+    let ext = file_name.rsplit('.').next().unwrap_or("").to_ascii_lowercase();
+    match ext.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "bmp" => "image/bmp",
+        "ico" => "image/x-icon",
+        "tif" | "tiff" => "image/tiff",
+        _ => "application/octet-stream",
+    }
+}
+
 /// Feishu Bot API adapter
 pub struct FeishuAdapter {
     app_id: String,
@@ -506,6 +801,39 @@ pub struct FeishuAdapter {
     dedup_last_persist_ms: AtomicU64,
     /// Channel for forwarding approval callbacks from card button clicks
     approval_tx: mpsc::Sender<ApprovalCallback>,
+    /// Outbound pacing (Feishu per-app / per-chat limits)
+    outbound: OutboundQueue,
+    /// Stream replies as interactive cards (falls back to post when a card fails)
+    card_streaming: bool,
+    /// Message IDs sent as reply cards — edits to these go through the card update API
+    card_messages: Arc<Mutex<HashSet<String>>>,
+    /// Ack state per inbound message_id (reaction to remove, placeholder card to delete)
+    acks: Arc<Mutex<HashMap<String, AckState>>>,
+    /// Chats where adding a reaction failed (no permission / reactions off) — skip further attempts
+    reactions_disabled: Arc<Mutex<HashSet<String>>>,
+    /// Send a "processing" placeholder card where reactions are unavailable
+    processing_card: bool,
+    /// Bot's default workspace — only images inside the turn's workspace are uploaded
+    /// for `![](path)` in replies
+    workspace_root: Option<PathBuf>,
+    /// chat_id → workspace of the chat's current turn (per-chat override, /workspace, /resume)
+    chat_workspaces: std::sync::Mutex<HashMap<String, PathBuf>>,
+    /// Uploaded workspace images: canonical path → (mtime, image_key). Draft edits
+    /// re-render the same Markdown many times; this keeps each image to one upload.
+    uploaded_images: Arc<Mutex<HashMap<PathBuf, (SystemTime, String)>>>,
+    /// chat_id → private/group, learned from inbound messages (card actions don't carry it)
+    chat_types: Arc<Mutex<HashMap<String, ImSourceType>>>,
+}
+
+/// Acknowledgement state for one inbound message
+#[derive(Default)]
+struct AckState {
+    chat_id: String,
+    /// Emoji currently shown and the reaction_id needed to remove it
+    reaction: Option<(&'static str, String)>,
+    /// True once ack_processing ran (send_typing re-asserts the processing reaction)
+    processing: bool,
+    placeholder_id: Option<String>,
 }
 
 impl FeishuAdapter {
@@ -543,6 +871,16 @@ impl FeishuAdapter {
             dedup_persist_path: dedup_path,
             dedup_last_persist_ms: AtomicU64::new(0),
             approval_tx,
+            outbound: OutboundQueue::new(OutboundLimits::feishu()),
+            card_streaming: config.feishu_card_streaming,
+            card_messages: Arc::new(Mutex::new(HashSet::new())),
+            acks: Arc::new(Mutex::new(HashMap::new())),
+            reactions_disabled: Arc::new(Mutex::new(HashSet::new())),
+            processing_card: config.feishu_processing_card,
+            workspace_root: config.default_workspace_path.as_ref().map(PathBuf::from),
+            chat_workspaces: std::sync::Mutex::new(HashMap::new()),
+            uploaded_images: Arc::new(Mutex::new(HashMap::new())),
+            chat_types: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
@@ -680,6 +1018,7 @@ impl FeishuAdapter {
     /// Make an authenticated API call, auto-retrying on 401 (token expired).
     async fn api_call(&self, method: &str, url: &str, body: Option<&Value>) -> Result<Value, String> {
         let mut retries = 0;
+        let mut rate_limit_retries = 0;
 
         loop {
             let token = self.get_token().await?;
@@ -702,6 +1041,12 @@ impl FeishuAdapter {
                 .map_err(|e| format!("Feishu API error: {}", e))?;
 
             let status = resp.status();
+            // Seconds until the rate-limit window resets (sent with frequency-limit errors)
+            let reset_secs = resp
+                .headers()
+                .get("x-ogw-ratelimit-reset")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse::<u64>().ok());
             let text = resp.text().await.unwrap_or_default();
 
             // Handle 401 — refresh token and retry once
@@ -724,6 +1069,16 @@ impl FeishuAdapter {
                 return Ok(json);
             }
 
+            // Frequency limited — block the whole app's outbound queue, then retry
+            if (status.as_u16() == 429 || code == 99991400) && rate_limit_retries < RATE_LIMIT_MAX_RETRIES {
+                let retry_after = reset_secs.unwrap_or(1);
+                ulog_warn!("[feishu] Rate limited (code {}), retry after {}s", code, retry_after);
+                self.outbound.backoff(None, Duration::from_secs(retry_after));
+                sleep(Duration::from_secs(retry_after)).await;
+                rate_limit_retries += 1;
+                continue;
+            }
+
             // Token invalid error codes
             if (code == 99991663 || code == 99991661) && retries == 0 {
                 ulog_warn!("[feishu] Token invalid (code {}), refreshing", code);
@@ -820,6 +1175,128 @@ impl FeishuAdapter {
         }
     }
 
+    /// Upload an image for use in messages and return its image_key.
+    /// API: POST /im/v1/images (multipart: image_type=message, image=<file>)
+    async fn upload_image(&self, file_name: &str, data: Vec<u8>) -> Result<String, String> {
+        let url = format!("{}/im/v1/images", FEISHU_API_BASE);
+        let (content_type, body) =
+            multipart_body(&[("image_type", "message")], "image", file_name, image_mime(file_name), &data);
+
+        let token = self.get_token().await?;
+        let resp = self.client
+            .post(&url)
+            .header("Authorization", format!("Bearer {}", token))
+            .header("Content-Type", content_type)
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| format!("Image upload error: {}", e))?;
+
+        let json: Value = resp.json().await
+            .map_err(|e| format!("Image upload parse error: {}", e))?;
+        if json["code"].as_i64() != Some(0) {
+            return Err(format!(
+                "Image upload failed: code={} msg={}",
+                json["code"], json["msg"].as_str().unwrap_or("unknown")
+            ));
+        }
+        json["data"]["image_key"].as_str()
+            .map(String::from)
+            .ok_or_else(|| "Image upload returned no image_key".to_string())
+    }
+
+    /// Upload a file for sending as a file message and return its file_key.
+    /// API: POST /im/v1/files (multipart: file_type=stream, file_name, file=<data>)
+    async fn upload_file(&self, file_name: &str, data: &[u8]) -> Result<String, String> {
+        let url = format!("{}/im/v1/files", FEISHU_API_BASE);
+        let (content_type, body) = multipart_body(
+            &[("file_type", "stream"), ("file_name", file_name)],
+            "file",
+            file_name,
+            "application/octet-stream",
+            data,
+        );
+
+        let token = self.get_token().await?;
+        let resp = self.client
+            .post(&url)
+            .header("Authorization", format!("Bearer {}", token))
+            .header("Content-Type", content_type)
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| format!("File upload error: {}", e))?;
+
+        let json: Value = resp.json().await
+            .map_err(|e| format!("File upload parse error: {}", e))?;
+        if json["code"].as_i64() != Some(0) {
+            return Err(format!(
+                "File upload failed: code={} msg={}",
+                json["code"], json["msg"].as_str().unwrap_or("unknown")
+            ));
+        }
+        json["data"]["file_key"].as_str()
+            .map(String::from)
+            .ok_or_else(|| "File upload returned no file_key".to_string())
+    }
+
+    /// Upload workspace images referenced by `![alt](path)` in `md`, resolved against the
+    /// workspace of `chat_id`'s current turn.
+    /// Returns src → image_key for every image that could be resolved; remote URLs,
+    /// paths outside the workspace and non-image files are left out.
+    async fn resolve_markdown_images(&self, chat_id: &str, md: &str) -> HashMap<String, String> {
+        /// Feishu rejects message images above 10 MB
+        const MAX_IMAGE_SIZE: u64 = 10 * 1024 * 1024;
+
+        let mut keys = HashMap::new();
+        let workspace = self
+            .chat_workspaces
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(chat_id)
+            .cloned()
+            .or_else(|| self.workspace_root.clone());
+        let Some(root) = workspace.and_then(|r| r.canonicalize().ok()) else {
+            return keys;
+        };
+
+        for src in markdown_image_sources(md) {
+            let Some(path) = resolve_image_path(&root, &src) else {
+                continue;
+            };
+            let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("image").to_string();
+            let meta = match std::fs::metadata(&path) {
+                Ok(m) if m.is_file() && m.len() <= MAX_IMAGE_SIZE => m,
+                _ => continue,
+            };
+            let mtime = meta.modified().unwrap_or(UNIX_EPOCH);
+
+            if let Some((cached_mtime, key)) = self.uploaded_images.lock().await.get(&path) {
+                if *cached_mtime == mtime {
+                    keys.insert(src, key.clone());
+                    continue;
+                }
+            }
+
+            let data = match tokio::fs::read(&path).await {
+                Ok(d) => d,
+                Err(e) => {
+                    ulog_warn!("[feishu] Failed to read image {}: {}", path.display(), e);
+                    continue;
+                }
+            };
+            match self.upload_image(&file_name, data).await {
+                Ok(key) => {
+                    ulog_info!("[feishu] Uploaded image {} → {}", path.display(), key);
+                    self.uploaded_images.lock().await.insert(path, (mtime, key.clone()));
+                    keys.insert(src, key);
+                }
+                Err(e) => ulog_warn!("[feishu] {}", e),
+            }
+        }
+        keys
+    }
+
     // ===== Bot info =====
 
     /// Get bot info to verify credentials.
@@ -835,11 +1312,29 @@ impl FeishuAdapter {
 
     // ===== Message operations =====
 
-    /// Send a rich-text (post) message and return the message_id.
-    /// Automatically converts Markdown to Feishu Post format.
+    /// Send Markdown as rich-text (post) messages and return the last message_id.
+    /// Text over the post size limit is split (code fences kept balanced) and numbered "(1/3)".
     pub async fn send_text_message(&self, chat_id: &str, text: &str) -> Result<Option<String>, String> {
+        let parts = split_markdown(text, MAX_POST_MARKDOWN_BYTES - PART_LABEL_RESERVE, LengthUnit::Bytes);
+        let total = parts.len();
+        let mut last_id = None;
+        for (i, part) in parts.iter().enumerate() {
+            last_id = if total == 1 {
+                self.send_post(chat_id, part).await?
+            } else {
+                self.send_post(chat_id, &format!("{}\n\n*{}*", part, part_label(i, total))).await?
+            };
+        }
+        Ok(last_id)
+    }
+
+    /// Send one rich-text (post) message and return the message_id.
+    /// Automatically converts Markdown to Feishu Post format.
+    async fn send_post(&self, chat_id: &str, text: &str) -> Result<Option<String>, String> {
+        let _ = self.outbound.admit(chat_id, Priority::Final).await;
         let url = format!("{}/im/v1/messages?receive_id_type=chat_id", FEISHU_API_BASE);
-        let post_content = markdown_to_feishu_post(text);
+        let image_keys = self.resolve_markdown_images(chat_id, text).await;
+        let post_content = markdown_to_feishu_post(text, &image_keys);
         let content = serde_json::to_string(&post_content).unwrap_or_default();
         let body = json!({
             "receive_id": chat_id,
@@ -852,12 +1347,37 @@ impl FeishuAdapter {
         Ok(msg_id)
     }
 
+    /// Send a file message: optional caption as a post first, then the uploaded file.
+    pub async fn send_file_message(
+        &self,
+        chat_id: &str,
+        file_name: &str,
+        data: &[u8],
+        caption: Option<&str>,
+    ) -> Result<Option<String>, String> {
+        let file_key = self.upload_file(file_name, data).await?;
+        if let Some(caption) = caption {
+            self.send_post(chat_id, caption).await?;
+        }
+
+        let _ = self.outbound.admit(chat_id, Priority::Final).await;
+        let url = format!("{}/im/v1/messages?receive_id_type=chat_id", FEISHU_API_BASE);
+        let body = json!({
+            "receive_id": chat_id,
+            "msg_type": "file",
+            "content": json!({ "file_key": file_key }).to_string(),
+        });
+        let resp = self.api_call("POST", &url, Some(&body)).await?;
+        Ok(resp["data"]["message_id"].as_str().map(String::from))
+    }
+
     /// Edit an existing message with rich-text (post) content.
     /// Uses PUT (not PATCH — PATCH is for message cards only).
     /// Automatically converts Markdown to Feishu Post format.
-    pub async fn edit_text_message(&self, message_id: &str, text: &str) -> Result<(), String> {
+    pub async fn edit_text_message(&self, chat_id: &str, message_id: &str, text: &str) -> Result<(), String> {
         let url = format!("{}/im/v1/messages/{}", FEISHU_API_BASE, message_id);
-        let post_content = markdown_to_feishu_post(text);
+        let image_keys = self.resolve_markdown_images(chat_id, text).await;
+        let post_content = markdown_to_feishu_post(text, &image_keys);
         let content = serde_json::to_string(&post_content).unwrap_or_default();
         let body = json!({
             "msg_type": "post",
@@ -868,6 +1388,52 @@ impl FeishuAdapter {
         Ok(())
     }
 
+    /// Send a reply card and return the message_id.
+    async fn send_reply_card(&self, chat_id: &str, text: &str) -> Result<Option<String>, String> {
+        let url = format!("{}/im/v1/messages?receive_id_type=chat_id", FEISHU_API_BASE);
+        let card = build_reply_card(text, None);
+        let body = json!({
+            "receive_id": chat_id,
+            "msg_type": "interactive",
+            "content": serde_json::to_string(&card).unwrap_or_default(),
+        });
+
+        let _ = self.outbound.admit(chat_id, Priority::Final).await;
+        let resp = self.api_call("POST", &url, Some(&body)).await?;
+        let msg_id = resp["data"]["message_id"].as_str().map(String::from);
+        if let Some(ref id) = msg_id {
+            let mut cards = self.card_messages.lock().await;
+            // Drafts are finished within one turn; a large set means leaked IDs
+            if cards.len() > 1000 {
+                cards.clear();
+            }
+            cards.insert(id.clone());
+        }
+        Ok(msg_id)
+    }
+
+    /// Update a reply card in place (PATCH — card messages only).
+    async fn update_reply_card(&self, message_id: &str, text: &str, meta: Option<&ResponseMeta<'_>>) -> Result<(), String> {
+        let url = format!("{}/im/v1/messages/{}", FEISHU_API_BASE, message_id);
+        let card = build_reply_card(text, meta);
+        let body = json!({ "content": serde_json::to_string(&card).unwrap_or_default() });
+        self.api_call("PATCH", &url, Some(&body)).await?;
+        Ok(())
+    }
+
+    async fn is_reply_card(&self, message_id: &str) -> bool {
+        self.card_messages.lock().await.contains(message_id)
+    }
+
+    /// Edit a streamed message, as card or post depending on how it was sent.
+    async fn edit_streamed_message(&self, chat_id: &str, message_id: &str, text: &str) -> Result<(), String> {
+        if self.is_reply_card(message_id).await {
+            self.update_reply_card(message_id, text, None).await
+        } else {
+            self.edit_text_message(chat_id, message_id, text).await
+        }
+    }
+
     /// Delete a message.
     pub async fn delete_text_message(&self, message_id: &str) -> Result<(), String> {
         let url = format!("{}/im/v1/messages/{}", FEISHU_API_BASE, message_id);
@@ -875,6 +1441,79 @@ impl FeishuAdapter {
         Ok(())
     }
 
+    // ===== Ack reactions =====
+
+    /// Add an emoji reaction to a message and return its reaction_id.
+    /// API: POST /im/v1/messages/{message_id}/reactions
+    async fn add_reaction(&self, message_id: &str, emoji_type: &str) -> Result<String, String> {
+        let url = format!("{}/im/v1/messages/{}/reactions", FEISHU_API_BASE, message_id);
+        let body = json!({ "reaction_type": { "emoji_type": emoji_type } });
+        let resp = self.api_call("POST", &url, Some(&body)).await?;
+        resp["data"]["reaction_id"]
+            .as_str()
+            .map(String::from)
+            .ok_or_else(|| "reaction_id missing in response".to_string())
+    }
+
+    /// Remove a reaction previously added by the bot.
+    /// API: DELETE /im/v1/messages/{message_id}/reactions/{reaction_id}
+    async fn remove_reaction(&self, chat_id: &str, message_id: &str, reaction_id: &str) -> Result<(), String> {
+        let _ = self.outbound.admit(chat_id, Priority::Final).await;
+        let url = format!(
+            "{}/im/v1/messages/{}/reactions/{}",
+            FEISHU_API_BASE, message_id, reaction_id
+        );
+        self.api_call("DELETE", &url, None).await?;
+        Ok(())
+    }
+
+    /// Replace the bot's reaction on `message_id` with `emoji_type` (best effort:
+    /// skipped when the outbound budget is spent or reactions are unavailable in the chat).
+    async fn set_ack_reaction(&self, chat_id: &str, message_id: &str, emoji_type: &'static str) {
+        if self.reactions_disabled.lock().await.contains(chat_id) {
+            return;
+        }
+        let previous = {
+            let mut acks = self.acks.lock().await;
+            // Messages handled without ack_clear (e.g. /help) leave entries behind
+            if acks.len() > 1000 {
+                acks.retain(|_, s| s.processing);
+            }
+            let state = acks.entry(message_id.to_string()).or_insert_with(|| AckState {
+                chat_id: chat_id.to_string(),
+                ..Default::default()
+            });
+            if state.reaction.as_ref().is_some_and(|(e, _)| *e == emoji_type) {
+                return;
+            }
+            state.reaction.take()
+        };
+        if let Some((_, rid)) = previous {
+            if let Err(e) = self.remove_reaction(chat_id, message_id, &rid).await {
+                ulog_debug!("[feishu] Remove reaction failed: {}", e);
+            }
+        }
+        if self.outbound.admit(chat_id, Priority::BestEffort).await.is_err() {
+            return;
+        }
+        match self.add_reaction(message_id, emoji_type).await {
+            Ok(rid) => {
+                if let Some(state) = self.acks.lock().await.get_mut(message_id) {
+                    state.reaction = Some((emoji_type, rid));
+                }
+            }
+            Err(e) if is_reaction_permission_error(&e) => {
+                // Missing scope or reactions not allowed in the chat — don't retry there
+                ulog_warn!("[feishu] Reactions unavailable in chat {}: {}", chat_id, e);
+                self.reactions_disabled.lock().await.insert(chat_id.to_string());
+            }
+            Err(e) => {
+                // Timeouts, 5xx, rate limits: transient, try again on the next message
+                ulog_debug!("[feishu] Add reaction failed in chat {}: {}", chat_id, e);
+            }
+        }
+    }
+
     // ===== WebSocket long connection =====
 
     /// Get WebSocket endpoint URL from Feishu.
@@ -937,13 +1576,17 @@ impl FeishuAdapter {
 
     /// Parse a Feishu IM event into an ImMessage.
     /// Async because image/file/audio/video messages require downloading resources.
+    /// Message edits (`im.message.updated_v1`, same payload shape) come through as
+    /// `ImMessage { edited: true, .. }`.
     async fn parse_im_event(&self, event: &Value) -> Option<ImMessage> {
         let header = event.get("header")?;
         let event_type = header["event_type"].as_str()?;
 
-        if event_type != "im.message.receive_v1" {
-            return None;
-        }
+        let edited = match event_type {
+            "im.message.receive_v1" => false,
+            "im.message.updated_v1" => true,
+            _ => return None,
+        };
 
         let event_data = event.get("event")?;
         let message = event_data.get("message")?;
@@ -965,6 +1608,7 @@ impl FeishuAdapter {
         // ── Collect attachments + text by message type ──
         let mut attachments: Vec<ImAttachment> = Vec::new();
         let mut text_parts: Vec<String> = Vec::new();
+        let mut rich_content: Vec<ImRichContent> = Vec::new();
 
         let text = match msg_type {
             "text" => {
@@ -1032,6 +1676,12 @@ impl FeishuAdapter {
                 if let Some(file_key) = content["file_key"].as_str() {
                     match self.download_resource(&message_id, file_key, "file").await {
                         Ok((data, content_type)) => {
+                            // Voice messages are Ogg/Opus; the download may come back as octet-stream
+                            let content_type = if content_type.starts_with("audio/") {
+                                content_type
+                            } else {
+                                "audio/ogg".to_string()
+                            };
                             let ext = mime_to_ext(&content_type);
                             let ts = chrono::Utc::now().format("%Y%m%d_%H%M%S");
                             attachments.push(ImAttachment {
@@ -1073,6 +1723,19 @@ impl FeishuAdapter {
                 }
                 String::new()
             }
+            "location" => {
+                // Location message: {"name": "xxx", "longitude": "116.40", "latitude": "39.90"}
+                let coord = |key: &str| content[key].as_str().and_then(|v| v.parse::<f64>().ok());
+                let location = ImRichContent::Location {
+                    latitude: coord("latitude").unwrap_or(0.0),
+                    longitude: coord("longitude").unwrap_or(0.0),
+                    title: content["name"].as_str().filter(|n| !n.is_empty()).map(String::from),
+                    address: None,
+                };
+                text_parts.push(location.render());
+                rich_content.push(location);
+                String::new()
+            }
             _ => {
                 ulog_debug!("[feishu] Ignoring unsupported message type: {}", msg_type);
                 return None;
@@ -1113,6 +1776,13 @@ impl FeishuAdapter {
             "group" => ImSourceType::Group,
             _ => ImSourceType::Private, // "p2p" or default
         };
+        {
+            let mut chat_types = self.chat_types.lock().await;
+            if chat_types.len() > 1000 && !chat_types.contains_key(&chat_id) {
+                chat_types.clear();
+            }
+            chat_types.insert(chat_id.clone(), source_type.clone());
+        }
 
         Some(ImMessage {
             chat_id,
@@ -1125,6 +1795,11 @@ impl FeishuAdapter {
             timestamp: chrono::Utc::now(),
             attachments,
             media_group_id: None,
+            menu_message_id: None,
+            edited,
+            rich_content,
+            forwarded_from: None,
+            merged_message_ids: Vec::new(),
         })
     }
 
@@ -1426,6 +2101,7 @@ impl FeishuAdapter {
             "content": card_str,
         });
 
+        let _ = self.outbound.admit(chat_id, Priority::Final).await;
         match self.api_call("POST", &url, Some(&body)).await {
             Ok(resp) => {
                 let msg_id = resp["data"]["message_id"].as_str().map(String::from);
@@ -1482,8 +2158,59 @@ impl FeishuAdapter {
         Ok(())
     }
 
-    /// Parse a card.action.trigger event into an ApprovalCallback.
-    fn parse_card_action(&self, event: &Value) -> Option<ApprovalCallback> {
+    /// Send an interactive card with one button per option. Button values carry
+    /// `{"cmd": <command>}`, which `parse_card_action` turns back into a command message.
+    pub async fn send_menu(
+        &self,
+        chat_id: &str,
+        text: &str,
+        options: &[MenuOption],
+    ) -> Result<Option<String>, String> {
+        let url = format!("{}/im/v1/messages?receive_id_type=chat_id", FEISHU_API_BASE);
+        let buttons: Vec<Value> = options
+            .iter()
+            .map(|opt| json!({
+                "tag": "button",
+                "text": { "tag": "plain_text", "content": opt.label },
+                "type": "default",
+                "value": { "cmd": opt.command },
+            }))
+            .collect();
+        let card = json!({
+            "config": { "wide_screen_mode": true, "update_multi": true },
+            "elements": [
+                { "tag": "div", "text": { "tag": "lark_md", "content": text } },
+                { "tag": "action", "layout": "flow", "actions": buttons }
+            ]
+        });
+        let body = json!({
+            "receive_id": chat_id,
+            "msg_type": "interactive",
+            "content": serde_json::to_string(&card).unwrap_or_default(),
+        });
+
+        let _ = self.outbound.admit(chat_id, Priority::Final).await;
+        let resp = self.api_call("POST", &url, Some(&body)).await?;
+        Ok(resp["data"]["message_id"].as_str().map(String::from))
+    }
+
+    /// Replace a menu card with the selection result (buttons removed).
+    pub async fn update_menu(&self, message_id: &str, text: &str) -> Result<(), String> {
+        let url = format!("{}/im/v1/messages/{}", FEISHU_API_BASE, message_id);
+        let card = json!({
+            "config": { "wide_screen_mode": true, "update_multi": true },
+            "elements": [
+                { "tag": "div", "text": { "tag": "lark_md", "content": text } }
+            ]
+        });
+        let body = json!({ "content": serde_json::to_string(&card).unwrap_or_default() });
+        self.api_call("PATCH", &url, Some(&body)).await?;
+        Ok(())
+    }
+
+    /// Parse a card.action.trigger event: approval buttons (`rid` + `action`) or a menu
+    /// button (`cmd`), which is dispatched as a command message from the clicking user.
+    async fn parse_card_action(&self, event: &Value) -> Option<ImCallback> {
         let event_type = event["header"]["event_type"].as_str()?;
         if event_type != "card.action.trigger" {
             return None;
@@ -1491,14 +2218,48 @@ impl FeishuAdapter {
 
         let action = &event["event"]["action"];
         let value = &action["value"];
-        let request_id = value["rid"].as_str()?.to_string();
-        let decision = value["action"].as_str()?.to_string();
         let user_id = event["event"]["operator"]["open_id"]
             .as_str()
             .unwrap_or("")
             .to_string();
 
-        Some(ApprovalCallback { request_id, decision, user_id })
+        if let Some(command) = value["cmd"].as_str() {
+            if !self.is_allowed(&user_id).await {
+                ulog_warn!("[feishu] Menu click from non-whitelisted user {}", user_id);
+                return None;
+            }
+            let context = &event["event"]["context"];
+            let chat_id = context["open_chat_id"].as_str()?.to_string();
+            let menu_id = context["open_message_id"].as_str()?.to_string();
+            let source_type = self
+                .chat_types
+                .lock()
+                .await
+                .get(&chat_id)
+                .cloned()
+                .unwrap_or(ImSourceType::Private);
+            return Some(ImCallback::Command(ImMessage {
+                chat_id,
+                message_id: menu_id.clone(),
+                text: command.to_string(),
+                sender_id: user_id,
+                sender_name: None,
+                source_type,
+                platform: ImPlatform::Feishu,
+                timestamp: chrono::Utc::now(),
+                attachments: Vec::new(),
+                media_group_id: None,
+                menu_message_id: Some(menu_id),
+                edited: false,
+                rich_content: Vec::new(),
+                forwarded_from: None,
+                merged_message_ids: Vec::new(),
+            }));
+        }
+
+        let request_id = value["rid"].as_str()?.to_string();
+        let decision = value["action"].as_str()?.to_string();
+        Some(ImCallback::Approval(ApprovalCallback { request_id, decision, user_id }))
     }
 
     /// Handle event payload extracted from a protobuf data frame.
@@ -1540,13 +2301,23 @@ impl FeishuAdapter {
             return;
         };
 
-        // Handle card.action.trigger (approval button clicks)
-        if let Some(cb) = self.parse_card_action(&event) {
-            ulog_info!("[feishu] Card action: decision={}, rid={}", cb.decision, &cb.request_id[..cb.request_id.len().min(16)]);
-            if self.approval_tx.send(cb).await.is_err() {
-                ulog_error!("[feishu] Approval channel closed");
+        // Handle card.action.trigger (approval / menu button clicks)
+        match self.parse_card_action(&event).await {
+            Some(ImCallback::Approval(cb)) => {
+                ulog_info!("[feishu] Card action: decision={}, rid={}", cb.decision, &cb.request_id[..cb.request_id.len().min(16)]);
+                if self.approval_tx.send(cb).await.is_err() {
+                    ulog_error!("[feishu] Approval channel closed");
+                }
+                return;
             }
-            return;
+            Some(ImCallback::Command(msg)) => {
+                ulog_info!("[feishu] Menu selection: {} (chat {})", msg.text, msg.chat_id);
+                if self.msg_tx.send(msg).await.is_err() {
+                    ulog_error!("[feishu] Message channel closed");
+                }
+                return;
+            }
+            None => {}
         }
 
         if let Some(msg) = self.parse_im_event(&event).await {
@@ -1561,13 +2332,19 @@ impl FeishuAdapter {
                 if cache.len() > DEDUP_MAX_SIZE || cache.len() % 100 == 0 {
                     cache.retain(|_, ts| now.saturating_sub(*ts) < DEDUP_TTL_SECS);
                 }
-                if let Some(prev) = cache.get(&msg.message_id) {
+                // Edits reuse the message_id — key them by the event instead
+                let dedup_key = if msg.edited {
+                    format!("{}:{}", msg.message_id, event["header"]["event_id"].as_str().unwrap_or(""))
+                } else {
+                    msg.message_id.clone()
+                };
+                if let Some(prev) = cache.get(&dedup_key) {
                     if now.saturating_sub(*prev) < DEDUP_TTL_SECS {
-                        ulog_debug!("[feishu] Dedup: skipping duplicate message {}", msg.message_id);
+                        ulog_debug!("[feishu] Dedup: skipping duplicate message {}", dedup_key);
                         return;
                     }
                 }
-                cache.insert(msg.message_id.clone(), now);
+                cache.insert(dedup_key, now);
                 // Debounced persist: snapshot the cache if enough time elapsed since last write.
                 // Dedup hits (duplicates) return early above — only new messages reach here,
                 // so burst writes only occur on first startup with empty cache, not on reconnect replay.
@@ -1609,6 +2386,10 @@ impl FeishuAdapter {
                 msg.text.len(),
             );
 
+            // ACK received — before handing off, so the processing loop's ack_processing
+            // always replaces (and later clears) this reaction rather than racing it
+            super::adapter::ImAdapter::ack_received(self, &msg.chat_id, &msg.message_id).await;
+
             if self.msg_tx.send(msg).await.is_err() {
                 ulog_error!("[feishu] Message channel closed");
             }
@@ -1637,20 +2418,74 @@ impl super::adapter::ImAdapter for FeishuAdapter {
         self.send_text_message(chat_id, text).await.map(|_| ())
     }
 
-    async fn ack_received(&self, _chat_id: &str, _message_id: &str) {
-        // No-op for Feishu MVP (no emoji reaction equivalent)
+    async fn send_document(
+        &self,
+        chat_id: &str,
+        file_name: &str,
+        data: &[u8],
+        caption: Option<&str>,
+    ) -> super::adapter::AdapterResult<()> {
+        self.send_file_message(chat_id, file_name, data, caption).await.map(|_| ())
     }
 
-    async fn ack_processing(&self, _chat_id: &str, _message_id: &str) {
-        // No-op for Feishu MVP
+    async fn ack_received(&self, chat_id: &str, message_id: &str) {
+        self.set_ack_reaction(chat_id, message_id, REACTION_RECEIVED).await;
     }
 
-    async fn ack_clear(&self, _chat_id: &str, _message_id: &str) {
-        // No-op for Feishu MVP
+    async fn ack_processing(&self, chat_id: &str, message_id: &str) {
+        self.set_ack_reaction(chat_id, message_id, REACTION_PROCESSING).await;
+        let reactions_off = self.reactions_disabled.lock().await.contains(chat_id);
+        let needs_placeholder = {
+            let mut acks = self.acks.lock().await;
+            let state = acks.entry(message_id.to_string()).or_insert_with(|| AckState {
+                chat_id: chat_id.to_string(),
+                ..Default::default()
+            });
+            state.processing = true;
+            reactions_off && self.processing_card && state.placeholder_id.is_none()
+        };
+        if needs_placeholder {
+            match self.send_reply_card(chat_id, "⏳ 正在处理…").await {
+                Ok(Some(id)) => {
+                    if let Some(state) = self.acks.lock().await.get_mut(message_id) {
+                        state.placeholder_id = Some(id);
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => ulog_warn!("[feishu] Processing card failed: {}", e),
+            }
+        }
+    }
+
+    async fn ack_clear(&self, chat_id: &str, message_id: &str) {
+        let Some(state) = self.acks.lock().await.remove(message_id) else {
+            return;
+        };
+        if let Some((_, rid)) = state.reaction {
+            if let Err(e) = self.remove_reaction(chat_id, message_id, &rid).await {
+                ulog_debug!("[feishu] Remove reaction failed: {}", e);
+            }
+        }
+        if let Some(pid) = state.placeholder_id {
+            self.card_messages.lock().await.remove(&pid);
+            let _ = self.delete_text_message(&pid).await;
+        }
     }
 
-    async fn send_typing(&self, _chat_id: &str) {
-        // No-op for Feishu (no typing indicator API)
+    async fn send_typing(&self, chat_id: &str) {
+        // No typing API on Feishu — re-assert the processing reaction on this chat's
+        // in-flight messages (restores it if an earlier add was skipped)
+        let pending: Vec<String> = self
+            .acks
+            .lock()
+            .await
+            .iter()
+            .filter(|(_, s)| s.chat_id == chat_id && s.processing && s.reaction.is_none())
+            .map(|(id, _)| id.clone())
+            .collect();
+        for message_id in pending {
+            self.set_ack_reaction(chat_id, &message_id, REACTION_PROCESSING).await;
+        }
     }
 }
 
@@ -1662,28 +2497,76 @@ impl super::adapter::ImStreamAdapter for FeishuAdapter {
         chat_id: &str,
         text: &str,
     ) -> super::adapter::AdapterResult<Option<String>> {
+        if self.card_streaming {
+            match self.send_reply_card(chat_id, text).await {
+                Ok(Some(id)) => return Ok(Some(id)),
+                Ok(None) => ulog_warn!("[feishu] Reply card sent without message_id, falling back to post"),
+                Err(e) => ulog_warn!("[feishu] Reply card failed: {}, falling back to post", e),
+            }
+        }
         self.send_text_message(chat_id, text).await
     }
 
     async fn edit_message(
         &self,
-        _chat_id: &str,
+        chat_id: &str,
         message_id: &str,
         text: &str,
     ) -> super::adapter::AdapterResult<()> {
-        self.edit_text_message(message_id, text).await
+        let _ = self.outbound.admit(chat_id, Priority::Final).await;
+        self.edit_streamed_message(chat_id, message_id, text).await
     }
 
     async fn delete_message(
         &self,
-        _chat_id: &str,
+        chat_id: &str,
         message_id: &str,
     ) -> super::adapter::AdapterResult<()> {
+        let _ = self.outbound.admit(chat_id, Priority::Final).await;
+        self.card_messages.lock().await.remove(message_id);
         self.delete_text_message(message_id).await
     }
 
+    async fn edit_draft(
+        &self,
+        chat_id: &str,
+        message_id: &str,
+        text: &str,
+    ) -> super::adapter::AdapterResult<bool> {
+        if self.outbound.admit(chat_id, Priority::BestEffort).await.is_err() {
+            return Ok(false);
+        }
+        self.edit_streamed_message(chat_id, message_id, text).await.map(|_| true)
+    }
+
+    async fn finish_response(
+        &self,
+        chat_id: &str,
+        message_id: &str,
+        text: &str,
+        meta: &ResponseMeta<'_>,
+    ) -> super::adapter::AdapterResult<()> {
+        // Only reply cards carry the tool panel + footer; post replies stay as they are
+        if !self.card_messages.lock().await.remove(message_id) {
+            return Ok(());
+        }
+        let _ = self.outbound.admit(chat_id, Priority::Final).await;
+        self.update_reply_card(message_id, text, Some(meta)).await
+    }
+
     fn max_message_length(&self) -> usize {
-        30000
+        MAX_POST_MARKDOWN_BYTES
+    }
+
+    fn length_unit(&self) -> LengthUnit {
+        LengthUnit::Bytes
+    }
+
+    fn set_chat_workspace(&self, chat_id: &str, workspace: &Path) {
+        self.chat_workspaces
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(chat_id.to_string(), workspace.to_path_buf());
     }
 
     async fn send_approval_card(
@@ -1698,10 +2581,154 @@ impl super::adapter::ImStreamAdapter for FeishuAdapter {
 
     async fn update_approval_status(
         &self,
-        _chat_id: &str,
+        chat_id: &str,
         message_id: &str,
         status: &str,
     ) -> super::adapter::AdapterResult<()> {
+        let _ = self.outbound.admit(chat_id, Priority::Final).await;
         self.update_approval_status(message_id, status).await
     }
+
+    async fn send_menu(
+        &self,
+        chat_id: &str,
+        text: &str,
+        options: &[MenuOption],
+    ) -> super::adapter::AdapterResult<Option<String>> {
+        self.send_menu(chat_id, text, options).await
+    }
+
+    async fn update_menu(
+        &self,
+        chat_id: &str,
+        message_id: &str,
+        text: &str,
+    ) -> super::adapter::AdapterResult<()> {
+        let _ = self.outbound.admit(chat_id, Priority::Final).await;
+        self.update_menu(message_id, text).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table_rendered_as_aligned_code_block() {
+        let post = markdown_to_feishu_post("前言\n\n| 名称 | 值 |\n|---|---|\n| a | x\\|y |\n| 中文 |", &HashMap::new());
+        let content = &post["zh_cn"]["content"];
+        assert_eq!(content[0][0]["text"], "前言");
+        assert_eq!(
+            content[1][0],
+            json!({
+                "tag": "code_block",
+                "language": "PLAIN_TEXT",
+                "text": "名称 │ 值\n─────┼────\na    │ x|y\n中文 │",
+            })
+        );
+    }
+
+    #[test]
+    fn test_card_table_component() {
+        let card = build_reply_card("Results:\n\n| 名称 | 值 |\n|---|---|\n| a | `1` |\n| b |\n\nDone.", None);
+        let elements = card["body"]["elements"].as_array().unwrap();
+        assert_eq!(elements.len(), 3);
+        assert_eq!(elements[0], json!({ "tag": "markdown", "content": "Results:" }));
+        assert_eq!(elements[2], json!({ "tag": "markdown", "content": "Done." }));
+        let table = &elements[1];
+        assert_eq!(table["tag"], "table");
+        assert_eq!(table["page_size"], 2);
+        assert_eq!(
+            table["columns"],
+            json!([
+                { "name": "c0", "display_name": "名称", "data_type": "text", "width": "auto" },
+                { "name": "c1", "display_name": "值", "data_type": "text", "width": "auto" },
+            ])
+        );
+        assert_eq!(table["rows"], json!([{ "c0": "a", "c1": "1" }, { "c0": "b", "c1": "" }]));
+
+        // No table: a single markdown body, as before
+        let card = build_reply_card("plain *text*", None);
+        assert_eq!(card["body"]["elements"], json!([{ "tag": "markdown", "content": "plain *text*" }]));
+    }
+
+    #[test]
+    fn test_uploaded_image_becomes_img_element() {
+        let keys = HashMap::from([("out/chart.png".to_string(), "img_v3_key".to_string())]);
+        let post = markdown_to_feishu_post("See ![chart](out/chart.png) and ![gone](missing.png)", &keys);
+        let content = &post["zh_cn"]["content"];
+        assert_eq!(content[0][0]["text"], "See ");
+        assert_eq!(content[1][0], json!({"tag": "img", "image_key": "img_v3_key"}));
+        assert_eq!(content[2][1]["text"], "[🖼 gone]");
+    }
+
+    #[test]
+    fn test_resolve_image_path_stays_inside_workspace() {
+        let base = std::env::temp_dir().join(format!("feishu-images-test-{}", std::process::id()));
+        let ws = base.join("ws");
+        std::fs::create_dir_all(ws.join("out")).unwrap();
+        std::fs::write(ws.join("out/chart.png"), b"png").unwrap();
+        std::fs::write(ws.join("notes.txt"), b"txt").unwrap();
+        std::fs::write(base.join("secret.png"), b"png").unwrap();
+        let root = ws.canonicalize().unwrap();
+        let chart = root.join("out/chart.png");
+
+        assert_eq!(resolve_image_path(&root, "out/chart.png"), Some(chart.clone()));
+        assert_eq!(resolve_image_path(&root, "./out/../out/chart.png"), Some(chart.clone()));
+        let file_url = format!("file://{}", chart.display());
+        assert_eq!(resolve_image_path(&root, &file_url), Some(chart));
+        assert_eq!(resolve_image_path(&root, "../secret.png"), None);
+        assert_eq!(resolve_image_path(&root, "notes.txt"), None);
+        assert_eq!(resolve_image_path(&root, "missing.png"), None);
+        assert_eq!(resolve_image_path(&root, "https://example.com/a.png"), None);
+
+        let _ = std::fs::remove_dir_all(&base);
+    }
+
+    fn message_event(event_type: &str, message_id: &str, text: &str) -> Value {
+        json!({
+            "schema": "2.0",
+            "header": { "event_id": format!("ev-{}-{}", event_type, text), "event_type": event_type },
+            "event": {
+                "sender": { "sender_id": { "open_id": "ou_user" }, "sender_type": "user" },
+                "message": {
+                    "chat_id": "oc_chat",
+                    "chat_type": "p2p",
+                    "message_id": message_id,
+                    "message_type": "text",
+                    "content": json!({ "text": text }).to_string(),
+                },
+            },
+        })
+    }
+
+    #[tokio::test]
+    async fn test_updated_message_reruns_last_turn() {
+        let (msg_tx, _) = mpsc::channel(1);
+        let (approval_tx, _) = mpsc::channel(1);
+        let allowed = Arc::new(RwLock::new(vec!["ou_user".to_string()]));
+        let adapter = FeishuAdapter::new(&ImConfig::default(), msg_tx, allowed, approval_tx, None);
+
+        let last = adapter
+            .parse_im_event(&message_event("im.message.receive_v1", "om_1", "wrong question"))
+            .await
+            .unwrap();
+        assert!(!last.edited);
+
+        let edit = adapter
+            .parse_im_event(&message_event("im.message.updated_v1", "om_1", "right question"))
+            .await
+            .unwrap();
+        assert!(edit.edited);
+        assert_eq!(edit.message_id, "om_1");
+
+        let rerun = crate::im::rerun_message(edit, Some(last.clone())).unwrap();
+        assert_eq!(rerun.text, "right question");
+
+        let old_edit = adapter
+            .parse_im_event(&message_event("im.message.updated_v1", "om_0", "older"))
+            .await
+            .unwrap();
+        assert!(crate::im::rerun_message(old_edit, Some(last)).is_none());
+    }
 }
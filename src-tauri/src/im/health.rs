@@ -1,7 +1,7 @@
 // IM Health State — periodic persistence to ~/.myagents/im_state.json
 // Used for Desktop UI status display, restart recovery, and diagnostics.
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use tokio::sync::Mutex;
@@ -21,18 +21,8 @@ pub struct HealthManager {
 
 impl HealthManager {
     pub fn new(persist_path: PathBuf) -> Self {
-        // Try to load existing state, or start fresh
-        let state = if persist_path.exists() {
-            match std::fs::read_to_string(&persist_path) {
-                Ok(content) => serde_json::from_str::<ImHealthState>(&content).unwrap_or_default(),
-                Err(_) => ImHealthState::default(),
-            }
-        } else {
-            ImHealthState::default()
-        };
-
         Self {
-            state: Arc::new(Mutex::new(state)),
+            state: Arc::new(Mutex::new(load_state(&persist_path))),
             persist_path,
         }
     }
@@ -160,6 +150,14 @@ impl HealthManager {
     }
 }
 
+/// Persisted health state at `path` (a stopped bot's last session list), or a fresh one
+pub fn load_state(path: &Path) -> ImHealthState {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
 /// Get default health state file path (legacy single-bot)
 pub fn default_health_path() -> PathBuf {
     dirs::home_dir()
@@ -192,6 +190,22 @@ pub fn bot_dedup_path(bot_id: &str) -> PathBuf {
         .join(format!("im_{}_dedup.json", bot_id))
 }
 
+/// Get per-bot audit log file path (append-only JSONL)
+pub fn bot_audit_path(bot_id: &str) -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".myagents")
+        .join(format!("im_{}_audit.jsonl", bot_id))
+}
+
+/// Get per-bot usage ledger file path (append-only JSONL)
+pub fn bot_usage_path(bot_id: &str) -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".myagents")
+        .join(format!("im_{}_usage.jsonl", bot_id))
+}
+
 /// Migrate legacy health/buffer files to per-bot paths.
 ///
 /// Strategy: copy then rename original to `.migrated`.
@@ -11,11 +11,13 @@ use tauri::{AppHandle, Runtime};
 use tokio::sync::{mpsc, watch, Mutex, RwLock};
 
 use crate::sidecar::ManagedSidecarManager;
+use crate::turn_scheduler::get_turn_scheduler;
 use crate::{ulog_info, ulog_warn, ulog_debug};
 
 use super::adapter::ImAdapter;
 use super::router::SessionRouter;
-use super::types::{ActiveHours, HeartbeatConfig, WakeReason};
+use super::types::{ActiveHours, HeartbeatConfig, TurnUsage, UsageSource, WakeReason};
+use super::usage::UsageLedger;
 use super::{AnyAdapter, PeerLocks};
 
 /// Response from Bun /api/im/heartbeat endpoint
@@ -25,6 +27,9 @@ struct HeartbeatResponse {
     text: Option<String>,
     #[allow(dead_code)]
     reason: Option<String>,
+    /// Token usage of the heartbeat turn (absent when the AI call was skipped)
+    #[serde(default)]
+    usage: Option<TurnUsage>,
 }
 
 /// Heartbeat prompt sent to Bun
@@ -40,6 +45,7 @@ struct HeartbeatRequest {
 
 /// HeartbeatRunner manages the periodic heartbeat loop for an IM Bot.
 pub struct HeartbeatRunner {
+    bot_id: String, // scheduler owner key (shared with the bot's IM turns)
     bot_label: String, // e.g. "feishu_mino" or "@mino115_bot" for log identification
     config: Arc<RwLock<HeartbeatConfig>>,
     last_push_text: Arc<Mutex<Option<String>>>,
@@ -48,6 +54,8 @@ pub struct HeartbeatRunner {
     // Hot-reloadable config refs — needed to sync AI config when waking up an idle-collected sidecar
     current_model: Arc<RwLock<Option<String>>>,
     mcp_servers_json: Arc<RwLock<Option<String>>>,
+    /// Bot's usage ledger (heartbeat turns are recorded alongside chat turns)
+    usage: Arc<UsageLedger>,
 }
 
 impl HeartbeatRunner {
@@ -55,12 +63,15 @@ impl HeartbeatRunner {
     /// Returns (runner, config_arc) — caller keeps config_arc for hot-updating heartbeat config.
     pub fn new(
         config: HeartbeatConfig,
+        bot_id: String,
         bot_label: String,
         current_model: Arc<RwLock<Option<String>>>,
         mcp_servers_json: Arc<RwLock<Option<String>>>,
+        usage: Arc<UsageLedger>,
     ) -> (Self, Arc<RwLock<HeartbeatConfig>>) {
         let config = Arc::new(RwLock::new(config));
         let runner = Self {
+            bot_id,
             bot_label,
             config: Arc::clone(&config),
             last_push_text: Arc::new(Mutex::new(None)),
@@ -71,6 +82,7 @@ impl HeartbeatRunner {
             executing: Arc::new(Mutex::new(false)),
             current_model,
             mcp_servers_json,
+            usage,
         };
         (runner, config)
     }
@@ -240,6 +252,10 @@ impl HeartbeatRunner {
 
         ulog_debug!("[heartbeat] Acquired peer lock for {}", session_key);
 
+        // Heartbeat turns count against the same app-wide scheduler as IM messages
+        // (acquired after the peer lock, same ordering as the processing loop).
+        let _turn_permit = get_turn_scheduler().acquire(&self.bot_id, &session_key).await;
+
         // Ensure sidecar is running — same pattern as user message flow.
         // If sidecar was idle-collected (port=0), this will restart it automatically.
         let (port, is_new_sidecar) = {
@@ -322,6 +338,10 @@ impl HeartbeatRunner {
             }
         };
 
+        if let Some(ref usage) = result.usage {
+            self.usage.record(UsageSource::Heartbeat, Some(&session_key), None, None, usage);
+        }
+
         // Handle response (still under peer_lock — IM message send is safe)
         match result.status.as_str() {
             "silent" => {
@@ -0,0 +1,252 @@
+// Attachment inbox: files received over IM are saved under
+// `<workspace>/<dir>/<botId>/<YYYY-MM-DD>/` rather than next to the project's own files.
+// Each workspace's inbox has a size quota (the oldest files are evicted to make room) and a
+// janitor prunes files older than the retention period. Blocking I/O — callers run it on the
+// blocking pool.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::ulog_info;
+
+use super::types::{InboxConfig, InboxUsage};
+
+pub const DEFAULT_DIR: &str = "myagents_files/inbox";
+pub const DEFAULT_RETENTION_DAYS: u32 = 30;
+pub const DEFAULT_QUOTA_MB: u64 = 1024;
+/// How often the janitor prunes expired files
+pub const JANITOR_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// A saved inbox file
+struct Entry {
+    path: PathBuf,
+    modified: SystemTime,
+    size: u64,
+}
+
+/// One bot's inbox layout and limits (the same in every workspace)
+#[derive(Debug, Clone)]
+pub struct Inbox {
+    /// Workspace-relative `<dir>/<botId>`
+    relative_root: PathBuf,
+    retention: Option<Duration>,
+    quota_bytes: Option<u64>,
+}
+
+impl Inbox {
+    pub fn new(bot_id: &str, config: &InboxConfig) -> Self {
+        // Only plain relative paths — the inbox must stay inside the workspace
+        let dir = config
+            .dir
+            .as_deref()
+            .map(|d| d.trim().trim_matches('/'))
+            .filter(|d| !d.is_empty() && !Path::new(d).is_absolute() && !d.split(['/', '\\']).any(|c| c == ".."))
+            .unwrap_or(DEFAULT_DIR);
+        let retention_days = config.retention_days.unwrap_or(DEFAULT_RETENTION_DAYS);
+        let quota_mb = config.quota_mb.unwrap_or(DEFAULT_QUOTA_MB);
+        Self {
+            relative_root: Path::new(dir).join(bot_id),
+            retention: (retention_days > 0).then(|| Duration::from_secs(retention_days as u64 * 86_400)),
+            quota_bytes: (quota_mb > 0).then(|| quota_mb * 1024 * 1024),
+        }
+    }
+
+    /// Absolute inbox directory of this bot in `workspace`
+    pub fn root(&self, workspace: &Path) -> PathBuf {
+        workspace.join(&self.relative_root)
+    }
+
+    /// Save a file into today's directory, evicting the oldest inbox files if the quota
+    /// would be exceeded. Returns the workspace-relative path ('/'-separated, for @refs).
+    pub fn save(&self, workspace: &Path, date: &str, file_name: &str, data: &[u8]) -> io::Result<String> {
+        let size = data.len() as u64;
+        if let Some(quota) = self.quota_bytes {
+            if size > quota {
+                return Err(io::Error::other(format!(
+                    "{} bytes exceeds the inbox quota of {} bytes",
+                    size, quota
+                )));
+            }
+            let entries = list_files(&self.root(workspace));
+            let mut total: u64 = entries.iter().map(|e| e.size).sum();
+            for entry in entries {
+                if total + size <= quota {
+                    break;
+                }
+                if std::fs::remove_file(&entry.path).is_ok() {
+                    total -= entry.size;
+                    ulog_info!("[im-inbox] Evicted {} (quota)", entry.path.display());
+                }
+            }
+        }
+
+        let dir = self.root(workspace).join(date);
+        std::fs::create_dir_all(&dir)?;
+        let path = auto_rename_path(&dir.join(file_name));
+        std::fs::write(&path, data)?;
+        let relative = path.strip_prefix(workspace).unwrap_or(&path);
+        Ok(relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/"))
+    }
+
+    /// Delete files older than the retention period and the date directories they leave
+    /// empty. Returns (files, bytes) removed.
+    pub fn prune_expired(&self, workspace: &Path, now: SystemTime) -> (u64, u64) {
+        let Some(retention) = self.retention else {
+            return (0, 0);
+        };
+        let Some(cutoff) = now.checked_sub(retention) else {
+            return (0, 0);
+        };
+        let root = self.root(workspace);
+        let (mut files, mut bytes) = (0, 0);
+        for entry in list_files(&root).into_iter().filter(|e| e.modified < cutoff) {
+            if std::fs::remove_file(&entry.path).is_ok() {
+                files += 1;
+                bytes += entry.size;
+            }
+        }
+        if let Ok(dirs) = std::fs::read_dir(&root) {
+            for dir in dirs.flatten() {
+                // Fails (and is skipped) unless the directory is empty
+                let _ = std::fs::remove_dir(dir.path());
+            }
+        }
+        (files, bytes)
+    }
+
+    /// Disk usage of this bot's inbox in `workspace`
+    pub fn usage(&self, workspace: &Path) -> InboxUsage {
+        let root = self.root(workspace);
+        let entries = list_files(&root);
+        InboxUsage {
+            workspace_path: workspace.display().to_string(),
+            inbox_path: root.display().to_string(),
+            files: entries.len() as u64,
+            bytes: entries.iter().map(|e| e.size).sum(),
+            quota_bytes: self.quota_bytes,
+        }
+    }
+}
+
+/// Files in the date directories under `root`, oldest first
+fn list_files(root: &Path) -> Vec<Entry> {
+    let mut entries = Vec::new();
+    let Ok(dirs) = std::fs::read_dir(root) else {
+        return entries;
+    };
+    for dir in dirs.flatten().filter(|d| d.path().is_dir()) {
+        let Ok(files) = std::fs::read_dir(dir.path()) else {
+            continue;
+        };
+        for file in files.flatten() {
+            let Ok(meta) = file.metadata() else { continue };
+            if meta.is_file() {
+                entries.push(Entry {
+                    path: file.path(),
+                    modified: meta.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                    size: meta.len(),
+                });
+            }
+        }
+    }
+    entries.sort_by_key(|e| e.modified);
+    entries
+}
+
+/// Generate a non-conflicting file path by appending _1, _2, etc.
+fn auto_rename_path(path: &Path) -> PathBuf {
+    if !path.exists() {
+        return path.to_path_buf();
+    }
+    let stem = path
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    let ext = path
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+    let parent = path.parent().unwrap_or(path);
+    for i in 1..100 {
+        let new_name = format!("{}_{}{}", stem, i, ext);
+        let new_path = parent.join(new_name);
+        if !new_path.exists() {
+            return new_path;
+        }
+    }
+    path.to_path_buf()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn workspace(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("im-inbox-test-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_save_layout_and_rename() {
+        let ws = workspace("layout");
+        let inbox = Inbox::new("bot1", &InboxConfig::default());
+        let first = inbox.save(&ws, "2026-10-18", "voice.ogg", b"abc").unwrap();
+        let second = inbox.save(&ws, "2026-10-18", "voice.ogg", b"abc").unwrap();
+        let usage = inbox.usage(&ws);
+        std::fs::remove_dir_all(&ws).ok();
+
+        assert_eq!(first, "myagents_files/inbox/bot1/2026-10-18/voice.ogg");
+        assert_eq!(second, "myagents_files/inbox/bot1/2026-10-18/voice_1.ogg");
+        assert_eq!((usage.files, usage.bytes), (2, 6));
+    }
+
+    #[test]
+    fn test_quota_evicts_oldest() {
+        let ws = workspace("quota");
+        let mut inbox = Inbox::new("bot1", &InboxConfig::default());
+        inbox.quota_bytes = Some(10);
+        inbox.save(&ws, "2026-10-17", "a.bin", &[0; 4]).unwrap();
+        std::thread::sleep(Duration::from_millis(20));
+        inbox.save(&ws, "2026-10-18", "b.bin", &[0; 4]).unwrap();
+        inbox.save(&ws, "2026-10-18", "c.bin", &[0; 4]).unwrap();
+        let a_exists = inbox.root(&ws).join("2026-10-17/a.bin").exists();
+        let usage = inbox.usage(&ws);
+        let too_big = inbox.save(&ws, "2026-10-18", "d.bin", &[0; 11]);
+        std::fs::remove_dir_all(&ws).ok();
+
+        assert!(!a_exists);
+        assert_eq!((usage.files, usage.bytes), (2, 8));
+        assert!(too_big.is_err());
+    }
+
+    #[test]
+    fn test_prune_expired_and_config() {
+        let ws = workspace("prune");
+        let inbox = Inbox::new("bot1", &InboxConfig::default());
+        inbox.save(&ws, "2026-10-18", "a.txt", b"hello").unwrap();
+        let now = SystemTime::now();
+        assert_eq!(inbox.prune_expired(&ws, now), (0, 0));
+        let later = now + Duration::from_secs(31 * 86_400);
+        assert_eq!(inbox.prune_expired(&ws, later), (1, 5));
+        let date_dir_left = inbox.root(&ws).join("2026-10-18").exists();
+        std::fs::remove_dir_all(&ws).ok();
+        assert!(!date_dir_left);
+
+        let config = InboxConfig {
+            dir: Some("../outside".into()),
+            retention_days: Some(0),
+            quota_mb: Some(0),
+        };
+        let inbox = Inbox::new("bot1", &config);
+        assert_eq!(inbox.relative_root, Path::new(DEFAULT_DIR).join("bot1"));
+        assert!(inbox.retention.is_none() && inbox.quota_bytes.is_none());
+    }
+}
@@ -1,18 +1,34 @@
 // IM Bot integration module
 // Manages the Telegram Bot lifecycle, routing IM messages to AI Sidecars.
 
+pub mod activity;
 pub mod adapter;
+pub mod attachments;
+pub mod audit;
 pub mod buffer;
+pub mod coalesce;
+pub mod failover;
 pub mod feishu;
 pub mod health;
 pub mod heartbeat;
+pub mod inbox;
+pub mod outbound;
+pub mod providers;
+pub mod rate_limit;
 pub mod router;
+pub mod sessions;
+pub mod split;
 pub mod telegram;
+pub mod telegram_html;
+pub mod transcribe;
+pub mod transcript;
 pub mod types;
+pub mod usage;
 mod util;
 
 use std::collections::HashMap;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
@@ -21,44 +37,86 @@ use reqwest::Client;
 use serde_json::json;
 use tauri::{AppHandle, Emitter, Runtime};
 use crate::{ulog_info, ulog_warn, ulog_error, ulog_debug};
-use tokio::sync::{watch, Mutex, Semaphore};
+use tokio::sync::{watch, Mutex};
 use tokio::task::JoinSet;
 
 use tokio::sync::mpsc;
 
 use crate::sidecar::ManagedSidecarManager;
+use crate::turn_scheduler::get_turn_scheduler;
 
 /// Approval callback from IM platform (button click or text command)
 pub struct ApprovalCallback {
     pub request_id: String,
     pub decision: String,  // "allow_once" | "always_allow" | "deny"
-    #[allow(dead_code)]
     pub user_id: String,
 }
 
+/// Button click from an IM platform (inline keyboard / card action)
+pub enum ImCallback {
+    /// Tool approval decision
+    Approval(ApprovalCallback),
+    /// Menu selection — dispatched as the command it stands for
+    Command(ImMessage),
+}
+
 /// Pending approval waiting for user response
 struct PendingApproval {
     sidecar_port: u16,
     chat_id: String,
     card_message_id: String,
     created_at: Instant,
+    /// For the audit log (input itself is only kept as a digest)
+    tool_name: String,
+    input_digest: String,
 }
 
 type PendingApprovals = Arc<Mutex<HashMap<String, PendingApproval>>>;
 
+/// A peer's most recent turn, kept so /retry and an edit of that message can re-run it
+struct LastTurn {
+    /// The user message as dispatched (attachments included)
+    message: ImMessage,
+    /// Bot messages of its reply — deleted when the turn is re-run
+    reply_ids: Vec<String>,
+}
+
+type LastTurns = Arc<Mutex<HashMap<String, LastTurn>>>;
+
+/// The turn a `/retry` or an edited message re-runs, given the peer's last turn: /retry
+/// repeats it as is, an edit of its message re-runs it with the corrected text. None when
+/// there is nothing to re-run (no last turn, or an edit of an earlier message).
+fn rerun_message(msg: ImMessage, last: Option<ImMessage>) -> Option<ImMessage> {
+    let last = last?;
+    if msg.text.trim() == "/retry" {
+        Some(last)
+    } else if msg.edited && last.message_id == msg.message_id {
+        Some(msg)
+    } else {
+        None
+    }
+}
+
 /// Per-peer locks: serializes requests (IM chat + heartbeat) to the same Sidecar.
 /// Required because /api/im/chat uses a single imStreamCallback; concurrent
 /// requests would conflict. Shared between processing loop and heartbeat runner.
 pub(crate) type PeerLocks = Arc<Mutex<HashMap<String, Arc<Mutex<()>>>>>;
 
+use activity::ActivityStatus;
+use audit::AuditLog;
 use buffer::MessageBuffer;
+use failover::ProviderCooldowns;
 use feishu::FeishuAdapter;
 use health::HealthManager;
-use router::{
-    create_sidecar_stream_client, RouteError, SessionRouter, GLOBAL_CONCURRENCY,
-};
+use inbox::Inbox;
+use providers::ProviderInfo;
+use rate_limit::RateLimiter;
+use router::{create_sidecar_stream_client, RouteError, SessionRouter};
+use sessions::SessionSummary;
 use telegram::TelegramAdapter;
-use types::{ImAttachmentType, ImBotStatus, ImConfig, ImConversation, ImMessage, ImPlatform, ImSourceType, ImStatus};
+use transcribe::{HttpTranscriber, Transcriber};
+use usage::UsageLedger;
+use types::{ActivityMode, AuditAction, ChatOverrides, ImAttachmentType, ImBotStatus, ImConfig, ImConversation, ImMessage, ImPlatform, ImSourceType, ImStatus, MenuOption, TurnUsage, UsageSource};
 
 /// Platform-agnostic adapter enum — avoids dyn dispatch overhead.
 pub(crate) enum AnyAdapter {
@@ -91,6 +149,18 @@ impl adapter::ImAdapter for AnyAdapter {
             Self::Feishu(a) => adapter::ImAdapter::send_message(a.as_ref(), chat_id, text).await,
         }
     }
+    async fn send_document(
+        &self,
+        chat_id: &str,
+        file_name: &str,
+        data: &[u8],
+        caption: Option<&str>,
+    ) -> adapter::AdapterResult<()> {
+        match self {
+            Self::Telegram(a) => adapter::ImAdapter::send_document(a.as_ref(), chat_id, file_name, data, caption).await,
+            Self::Feishu(a) => adapter::ImAdapter::send_document(a.as_ref(), chat_id, file_name, data, caption).await,
+        }
+    }
     async fn ack_received(&self, chat_id: &str, message_id: &str) {
         match self {
             Self::Telegram(a) => adapter::ImAdapter::ack_received(a.as_ref(), chat_id, message_id).await,
@@ -136,12 +206,42 @@ impl adapter::ImStreamAdapter for AnyAdapter {
             Self::Feishu(a) => adapter::ImStreamAdapter::delete_message(a.as_ref(), chat_id, message_id).await,
         }
     }
+    async fn edit_draft(&self, chat_id: &str, message_id: &str, text: &str) -> adapter::AdapterResult<bool> {
+        match self {
+            Self::Telegram(a) => adapter::ImStreamAdapter::edit_draft(a.as_ref(), chat_id, message_id, text).await,
+            Self::Feishu(a) => adapter::ImStreamAdapter::edit_draft(a.as_ref(), chat_id, message_id, text).await,
+        }
+    }
+    async fn finish_response(
+        &self,
+        chat_id: &str,
+        message_id: &str,
+        text: &str,
+        meta: &adapter::ResponseMeta<'_>,
+    ) -> adapter::AdapterResult<()> {
+        match self {
+            Self::Telegram(a) => a.finish_response(chat_id, message_id, text, meta).await,
+            Self::Feishu(a) => a.finish_response(chat_id, message_id, text, meta).await,
+        }
+    }
     fn max_message_length(&self) -> usize {
         match self {
             Self::Telegram(a) => a.max_message_length(),
             Self::Feishu(a) => a.max_message_length(),
         }
     }
+    fn length_unit(&self) -> split::LengthUnit {
+        match self {
+            Self::Telegram(a) => a.length_unit(),
+            Self::Feishu(a) => a.length_unit(),
+        }
+    }
+    fn set_chat_workspace(&self, chat_id: &str, workspace: &std::path::Path) {
+        match self {
+            Self::Telegram(a) => a.set_chat_workspace(chat_id, workspace),
+            Self::Feishu(a) => a.set_chat_workspace(chat_id, workspace),
+        }
+    }
     async fn send_approval_card(
         &self,
         chat_id: &str,
@@ -162,7 +262,29 @@ impl adapter::ImStreamAdapter for AnyAdapter {
     ) -> adapter::AdapterResult<()> {
         match self {
             Self::Telegram(a) => a.update_approval_status(chat_id, message_id, status).await.map_err(|e| e.to_string()),
-            Self::Feishu(a) => a.update_approval_status(message_id, status).await,
+            Self::Feishu(a) => adapter::ImStreamAdapter::update_approval_status(a.as_ref(), chat_id, message_id, status).await,
+        }
+    }
+    async fn send_menu(
+        &self,
+        chat_id: &str,
+        text: &str,
+        options: &[MenuOption],
+    ) -> adapter::AdapterResult<Option<String>> {
+        match self {
+            Self::Telegram(a) => adapter::ImStreamAdapter::send_menu(a.as_ref(), chat_id, text, options).await,
+            Self::Feishu(a) => adapter::ImStreamAdapter::send_menu(a.as_ref(), chat_id, text, options).await,
+        }
+    }
+    async fn update_menu(
+        &self,
+        chat_id: &str,
+        message_id: &str,
+        text: &str,
+    ) -> adapter::AdapterResult<()> {
+        match self {
+            Self::Telegram(a) => adapter::ImStreamAdapter::update_menu(a.as_ref(), chat_id, message_id, text).await,
+            Self::Feishu(a) => adapter::ImStreamAdapter::update_menu(a.as_ref(), chat_id, message_id, text).await,
         }
     }
 }
@@ -202,6 +324,14 @@ pub struct ImBotInstance {
     heartbeat_config: Option<Arc<tokio::sync::RwLock<types::HeartbeatConfig>>>,
     /// Platform adapter (retained for graceful shutdown — e.g. dedup flush)
     adapter: Arc<AnyAdapter>,
+    /// Append-only audit log (approvals, privileged commands, whitelist changes)
+    audit: Arc<AuditLog>,
+    /// Per-sender / per-chat rate limiter (counters reported in ImBotStatus)
+    rate_limiter: Arc<RateLimiter>,
+    /// Attachment inbox layout and limits (usage reported by cmd_im_inbox_usage)
+    inbox: Arc<Inbox>,
+    /// Token usage ledger (chat turns, heartbeats, bot-owned cron tasks)
+    pub(crate) usage: Arc<UsageLedger>,
     // ===== Hot-reloadable config =====
     pub(crate) current_model: Arc<tokio::sync::RwLock<Option<String>>>,
     pub(crate) current_provider_env: Arc<tokio::sync::RwLock<Option<serde_json::Value>>>,
@@ -306,6 +436,12 @@ pub async fn start_im_bot<R: Runtime>(
     let buffer_path = health::bot_buffer_path(&bot_id);
     let buffer = Arc::new(Mutex::new(MessageBuffer::load_from_disk(&buffer_path)));
 
+    let audit = Arc::new(AuditLog::new(health::bot_audit_path(&bot_id)));
+    let usage = Arc::new(UsageLedger::new(health::bot_usage_path(&bot_id)));
+    let rate_limiter = Arc::new(RateLimiter::new(config.rate_limit.clone().unwrap_or_default()));
+    let inbox = Arc::new(Inbox::new(&bot_id, &config.inbox.clone().unwrap_or_default()));
+    get_turn_scheduler().set_weight(&bot_id, config.scheduler_weight.unwrap_or(1));
+
     let router = {
         let mut r = SessionRouter::new(default_workspace);
         // Restore peer→session mapping from previous run's im_state.json
@@ -330,8 +466,9 @@ pub async fn start_im_bot<R: Runtime>(
     let pending_approvals: PendingApprovals = Arc::new(Mutex::new(HashMap::new()));
 
     // Create platform adapter (implements ImAdapter + ImStreamAdapter traits)
-    let (msg_tx, mut msg_rx) = tokio::sync::mpsc::channel(256);
-    let msg_tx_for_reinjection = msg_tx.clone(); // For media group merge re-injection
+    // Adapter → coalescing stage (raw) → processing loop (coalesced)
+    let (msg_tx, raw_msg_rx) = tokio::sync::mpsc::channel(256);
+    let (coalesced_tx, mut msg_rx) = tokio::sync::mpsc::channel(256);
     let adapter: Arc<AnyAdapter> = match config.platform {
         ImPlatform::Telegram => Arc::new(AnyAdapter::Telegram(Arc::new(TelegramAdapter::new(
             &config,
@@ -383,6 +520,14 @@ pub async fn start_im_bot<R: Runtime>(
     // Start health persist loop
     let health_handle = health.start_persist_loop(shutdown_rx.clone());
 
+    // Start coalescing stage (media groups, split pastes, attachments + follow-up text)
+    let _coalesce_handle = coalesce::spawn_stage(
+        config.platform.clone(),
+        raw_msg_rx,
+        coalesced_tx,
+        shutdown_rx.clone(),
+    );
+
     // Start Telegram long-poll loop
     let adapter_clone = Arc::clone(&adapter);
     let poll_shutdown_rx = shutdown_rx.clone();
@@ -393,6 +538,7 @@ pub async fn start_im_bot<R: Runtime>(
     // Start approval callback handler
     let pending_approvals_for_handler = Arc::clone(&pending_approvals);
     let adapter_for_approval = Arc::clone(&adapter);
+    let audit_for_approval = Arc::clone(&audit);
     let approval_client = Client::new();
     let mut approval_shutdown_rx = shutdown_rx.clone();
     let approval_handle = tokio::spawn(async move {
@@ -410,6 +556,19 @@ pub async fn start_im_bot<R: Runtime>(
 
             let pending = pending_approvals_for_handler.lock().await.remove(&cb.request_id);
             if let Some(p) = pending {
+                audit_for_approval.record(
+                    AuditAction::Approval,
+                    &cb.user_id,
+                    None,
+                    Some(&p.chat_id),
+                    None,
+                    json!({
+                        "requestId": cb.request_id,
+                        "decision": cb.decision,
+                        "toolName": p.tool_name,
+                        "inputDigest": p.input_digest,
+                    }),
+                );
                 // POST decision to Sidecar
                 let url = format!("http://127.0.0.1:{}/api/im/permission-response", p.sidecar_port);
                 let result = approval_client
@@ -451,6 +610,7 @@ pub async fn start_im_bot<R: Runtime>(
     // Both must acquire the lock for a session_key before calling Sidecar HTTP APIs,
     // because /api/im/chat uses a single imStreamCallback — concurrent requests would conflict.
     let peer_locks: PeerLocks = Arc::new(Mutex::new(HashMap::new()));
+    let last_turns: LastTurns = Arc::new(Mutex::new(HashMap::new()));
 
     // Start message processing loop
     //
@@ -462,8 +622,9 @@ pub async fn start_im_bot<R: Runtime>(
     //     1. Per-peer lock — serializes requests to the same Sidecar (required because
     //        /api/im/chat uses a single imStreamCallback; concurrent requests would conflict).
     //        Heartbeat runner also acquires this lock to prevent callback conflicts.
-    //     2. Global semaphore — limits total concurrent Sidecar I/O across all peers.
-    //        Acquired AFTER the peer lock so queued same-peer tasks don't hold permits
+    //     2. Turn scheduler slot — app-wide bound on concurrent AI turns (shared with
+    //        other bots, heartbeats and cron), weighted per bot and round-robin per peer.
+    //        Acquired AFTER the peer lock so queued same-peer tasks don't hold slots
     //        while waiting, which would starve other peers.
     //     3. Router lock — held briefly for data ops (ensure_sidecar, record_response),
     //        never during the HTTP POST itself.
@@ -487,6 +648,28 @@ pub async fn start_im_bot<R: Runtime>(
     let mcp_servers_json = Arc::new(tokio::sync::RwLock::new(config.mcp_servers_json.clone()));
     let bind_code_for_loop = bind_code.clone();
     let bot_id_for_loop = bot_id.clone();
+    let document_threshold = config
+        .document_reply_threshold
+        .map_or(DEFAULT_DOCUMENT_REPLY_THRESHOLD, |n| n as usize);
+    let tool_status = config.tool_status;
+    let usage_footer = config.usage_footer;
+    let usage_command = config.usage_command;
+    // Provider failover: ordered fallback IDs + providers that recently failed
+    let fallback_provider_ids = Arc::new(config.fallback_provider_ids.clone());
+    let failover_cooldowns = Arc::new(Mutex::new(ProviderCooldowns::default()));
+    // Voice transcription (None = audio attachments are only saved as files)
+    let transcriber: Option<Arc<HttpTranscriber>> = config
+        .transcription
+        .as_ref()
+        .and_then(HttpTranscriber::from_config)
+        .map(Arc::new);
+    if let (Some(_), Some(t)) = (&transcriber, &config.transcription) {
+        ulog_info!("[im] Voice transcription enabled via {}", t.base_url);
+    }
+    let inbox_for_loop = Arc::clone(&inbox);
+    let audit_for_loop = Arc::clone(&audit);
+    let usage_for_loop = Arc::clone(&usage);
+    let rate_limiter_for_loop = Arc::clone(&rate_limiter);
     let allowed_users_for_loop = Arc::clone(&allowed_users);
     let current_model_for_loop = Arc::clone(&current_model);
     let current_provider_env_for_loop = Arc::clone(&current_provider_env);
@@ -497,8 +680,6 @@ pub async fn start_im_bot<R: Runtime>(
     let approval_tx_for_loop = approval_tx.clone();
     let mut process_shutdown_rx = shutdown_rx.clone();
 
-    // Concurrency primitives (live outside the router for lock-free access)
-    let global_semaphore = Arc::new(Semaphore::new(GLOBAL_CONCURRENCY));
     // peer_locks is created in start_im_bot() and shared with heartbeat runner;
     // the Arc is cloned here for the processing loop.
     let peer_locks_for_loop = Arc::clone(&peer_locks);
@@ -507,98 +688,144 @@ pub async fn start_im_bot<R: Runtime>(
     let process_handle = tokio::spawn(async move {
         let mut in_flight: JoinSet<()> = JoinSet::new();
 
-        // Media group buffering (Telegram albums)
-        struct MediaGroupEntry {
-            messages: Vec<ImMessage>,
-            first_received: Instant,
-        }
-        let mut media_groups: HashMap<String, MediaGroupEntry> = HashMap::new();
-        const MEDIA_GROUP_TIMEOUT: Duration = Duration::from_millis(500);
-        const MEDIA_GROUP_CHECK_INTERVAL: Duration = Duration::from_millis(100);
-
-        /// Merge buffered media group messages into one combined message
-        fn merge_media_group(mut messages: Vec<ImMessage>) -> ImMessage {
-            messages.sort_by_key(|m| m.message_id.parse::<i64>().unwrap_or(0));
-            let mut base = messages.remove(0);
-            // Use first non-empty text as caption
-            if base.text.is_empty() {
-                if let Some(msg_with_text) = messages.iter().find(|m| !m.text.is_empty()) {
-                    base.text = msg_with_text.text.clone();
+        /// Save an attachment into today's inbox directory in the workspace.
+        /// Returns its workspace-relative path.
+        async fn save_to_workspace(
+            inbox: &Inbox,
+            workspace_path: &std::path::Path,
+            file_name: &str,
+            data: &[u8],
+        ) -> Option<String> {
+            let inbox = inbox.clone();
+            let workspace_path = workspace_path.to_path_buf();
+            let file_name_owned = file_name.to_string();
+            let data = data.to_vec();
+            let date = chrono::Local::now().format("%Y-%m-%d").to_string();
+            let saved = tokio::task::spawn_blocking(move || {
+                inbox.save(&workspace_path, &date, &file_name_owned, &data)
+            })
+            .await
+            .unwrap_or_else(|e| Err(std::io::Error::other(e.to_string())));
+            match saved {
+                Ok(relative) => Some(relative),
+                Err(e) => {
+                    ulog_error!("[im] Failed to save file {}: {}", file_name, e);
+                    None
                 }
             }
-            // Merge all attachments
-            for msg in messages {
-                base.attachments.extend(msg.attachments);
-            }
-            base.media_group_id = None; // Already merged
-            base
         }
 
-        /// Process attachments: save File types to workspace, encode Image types to base64.
+        /// Process attachments: save File types to the workspace inbox (transcribing audio when a
+        /// transcriber is configured), encode Image types to base64 (downscaled first when
+        /// too large — the original is then saved to the workspace).
         /// This is async to use non-blocking file I/O.
-        async fn process_attachments(
+        async fn process_attachments<T: Transcriber>(
             msg: &mut ImMessage,
             workspace_path: &std::path::Path,
+            inbox: &Inbox,
+            transcriber: Option<&T>,
         ) -> Vec<serde_json::Value> {
-            /// Maximum image size for base64 encoding (10 MB)
-            const MAX_IMAGE_ENCODE_SIZE: usize = 10 * 1024 * 1024;
-
             let mut file_refs: Vec<String> = Vec::new();
+            let mut transcripts: Vec<String> = Vec::new();
             let mut image_payloads: Vec<serde_json::Value> = Vec::new();
 
             for attachment in &msg.attachments {
                 match attachment.attachment_type {
                     ImAttachmentType::File => {
-                        let target_dir = workspace_path.join("myagents_files");
-                        if let Err(e) = tokio::fs::create_dir_all(&target_dir).await {
-                            ulog_error!("[im] Failed to create myagents_files dir: {}", e);
+                        let Some(relative) =
+                            save_to_workspace(inbox, workspace_path, &attachment.file_name, &attachment.data).await
+                        else {
                             continue;
-                        }
-                        let target_path = target_dir.join(&attachment.file_name);
-                        let final_path = auto_rename_path(&target_path);
-                        if let Err(e) = tokio::fs::write(&final_path, &attachment.data).await {
-                            ulog_error!("[im] Failed to save file: {}", e);
-                            continue;
-                        }
-                        let relative = format!(
-                            "myagents_files/{}",
-                            final_path.file_name().unwrap().to_string_lossy()
-                        );
-                        file_refs.push(format!("@{}", relative));
+                        };
                         ulog_info!(
                             "[im] Saved file attachment: {} ({} bytes)",
                             relative,
                             attachment.data.len()
                         );
+                        file_refs.push(format!("@{}", relative));
+
+                        let Some(transcriber) = transcriber.filter(|_| {
+                            transcribe::is_transcribable(&attachment.mime_type, attachment.data.len())
+                        }) else {
+                            continue;
+                        };
+                        match transcriber
+                            .transcribe(&attachment.file_name, &attachment.mime_type, &attachment.data)
+                            .await
+                        {
+                            Ok(text) if !text.is_empty() => {
+                                ulog_info!("[im] Transcribed {} ({} chars)", relative, text.chars().count());
+                                transcripts.push(format!("[语音转写] {}", text));
+                            }
+                            Ok(_) => ulog_info!("[im] Transcription of {} is empty", relative),
+                            Err(e) => ulog_warn!("[im] Transcription of {} failed: {}", relative, e),
+                        }
                     }
                     ImAttachmentType::Image => {
-                        if attachment.data.len() > MAX_IMAGE_ENCODE_SIZE {
-                            ulog_warn!(
-                                "[im] Image too large for base64 encoding: {} ({} bytes, max {})",
-                                attachment.file_name,
-                                attachment.data.len(),
-                                MAX_IMAGE_ENCODE_SIZE
-                            );
-                            continue;
+                        // Decode / resize is CPU-bound → blocking pool
+                        let data = attachment.data.clone();
+                        let mime_type = attachment.mime_type.clone();
+                        let prepared = tokio::task::spawn_blocking(move || {
+                            attachments::prepare_for_vision(&data, &mime_type)
+                        })
+                        .await
+                        .unwrap_or_else(|e| Err(format!("resize task failed: {}", e)));
+                        let prepared = match prepared {
+                            Ok(prepared) => prepared,
+                            Err(e) => {
+                                // Not usable for vision — still hand it to tools as a file
+                                ulog_warn!("[im] Image {} not sent for vision: {}", attachment.file_name, e);
+                                if let Some(relative) =
+                                    save_to_workspace(inbox, workspace_path, &attachment.file_name, &attachment.data).await
+                                {
+                                    file_refs.push(format!("@{}", relative));
+                                }
+                                continue;
+                            }
+                        };
+                        let mut name = attachment.file_name.clone();
+                        if prepared.resized {
+                            // Full-resolution original stays available to tools
+                            if let Some(relative) =
+                                save_to_workspace(inbox, workspace_path, &attachment.file_name, &attachment.data).await
+                            {
+                                file_refs.push(format!("@{}", relative));
+                            }
+                            name = std::path::Path::new(&name)
+                                .with_extension("jpg")
+                                .to_string_lossy()
+                                .into_owned();
                         }
                         use base64::Engine;
-                        let b64 =
-                            base64::engine::general_purpose::STANDARD.encode(&attachment.data);
+                        let b64 = base64::engine::general_purpose::STANDARD.encode(&prepared.data);
+                        ulog_info!(
+                            "[im] Encoded image attachment: {} ({} bytes{})",
+                            name,
+                            prepared.data.len(),
+                            if prepared.resized {
+                                format!(", downscaled from {} bytes", attachment.data.len())
+                            } else {
+                                String::new()
+                            },
+                        );
                         image_payloads.push(json!({
-                            "name": attachment.file_name,
-                            "mimeType": attachment.mime_type,
+                            "name": name,
+                            "mimeType": prepared.mime_type,
                             "data": b64,
                         }));
-                        ulog_info!(
-                            "[im] Encoded image attachment: {} ({} bytes)",
-                            attachment.file_name,
-                            attachment.data.len()
-                        );
                     }
                 }
             }
 
-            // Append @path references to message text
+            // Append transcripts, then @path references, to message text
+            if !transcripts.is_empty() {
+                let transcript_text = transcripts.join("\n");
+                if msg.text.is_empty() {
+                    msg.text = transcript_text;
+                } else {
+                    msg.text = format!("{}\n{}", msg.text, transcript_text);
+                }
+            }
             if !file_refs.is_empty() {
                 let refs_text = file_refs.join(" ");
                 if msg.text.is_empty() {
@@ -612,28 +839,35 @@ pub async fn start_im_bot<R: Runtime>(
         }
 
         loop {
-            // Determine flush timeout for media groups
-            let flush_timeout = if media_groups.is_empty() {
-                Duration::from_secs(3600)
-            } else {
-                MEDIA_GROUP_CHECK_INTERVAL
-            };
-
             tokio::select! {
                 Some(msg) = msg_rx.recv() => {
-                    // Buffer media group messages
-                    if let Some(ref group_id) = msg.media_group_id {
-                        media_groups
-                            .entry(group_id.clone())
-                            .or_insert_with(|| MediaGroupEntry {
-                                messages: Vec::new(),
-                                first_received: Instant::now(),
-                            })
-                            .messages
-                            .push(msg);
-                        continue;
-                    }
                     let session_key = SessionRouter::session_key(&msg);
+
+                    // ── Re-runs: /retry repeats the peer's last turn, an edit of that turn's
+                    //    message re-runs it with the corrected text (other edits are ignored) ──
+                    let is_retry = msg.text.trim() == "/retry";
+                    let rerun = is_retry || msg.edited;
+                    let msg = if rerun {
+                        let last = last_turns
+                            .lock()
+                            .await
+                            .get(&session_key)
+                            .map(|t| t.message.clone());
+                        let (reply_chat_id, edited_id) = (msg.chat_id.clone(), msg.message_id.clone());
+                        match rerun_message(msg, last) {
+                            Some(msg) => msg,
+                            None if is_retry => {
+                                let _ = adapter_for_reply.send_message(&reply_chat_id, "❌ 没有可重试的消息").await;
+                                continue;
+                            }
+                            None => {
+                                ulog_debug!("[im] Ignoring edit of earlier message {} in {}", edited_id, session_key);
+                                continue;
+                            }
+                        }
+                    } else {
+                        msg
+                    };
                     let chat_id = msg.chat_id.clone();
                     let message_id = msg.message_id.clone();
                     let text = msg.text.trim().to_string();
@@ -674,6 +908,15 @@ pub async fn start_im_bot<R: Runtime>(
                                 }
                             }
 
+                            audit_for_loop.record(
+                                AuditAction::Bind,
+                                &user_id_str,
+                                msg.sender_name.as_deref(),
+                                Some(&chat_id),
+                                Some(&session_key),
+                                json!({}),
+                            );
+
                             // Persist to config.json directly (doesn't rely on frontend being mounted)
                             {
                                 let bid = bot_id_for_loop.clone();
@@ -712,6 +955,9 @@ pub async fn start_im_bot<R: Runtime>(
                              可用命令：\n\
                              /help — 查看所有命令\n\
                              /new — 开始新对话\n\
+                             /retry — 重新生成上一条回复\n\
+                             /sessions — 查看或恢复历史对话\n\
+                             /export — 导出对话记录\n\
                              /workspace <路径> — 切换工作区\n\
                              /model — 查看或切换 AI 模型\n\
                              /provider — 查看或切换 AI 供应商\n\
@@ -723,22 +969,30 @@ pub async fn start_im_bot<R: Runtime>(
                     }
 
                     if text == "/help" {
+                        let usage_line = if usage_command { "/usage — 查看今日与本月用量\n" } else { "" };
                         let _ = adapter_for_reply.send_message(
                             &chat_id,
-                            "📖 可用命令\n\n\
+                            &format!("📖 可用命令\n\n\
                              /new — 开始新对话（清空当前上下文）\n\
+                             /retry — 重新生成上一条回复（编辑上一条消息也会重新生成）\n\
+                             /sessions — 查看本会话的历史对话\n\
+                             /resume <序号> — 恢复历史对话\n\
+                             /export — 导出本会话的对话记录（Markdown；/export jsonl 导出 JSONL）\n\
                              /workspace — 查看当前工作区\n\
                              /workspace <路径> — 切换工作区目录\n\
                              /model — 查看当前 AI 模型\n\
-                             /model <名称> — 切换模型（sonnet / opus / haiku）\n\
+                             /model <名称> — 切换本会话模型（模型 ID、序号或简称）\n\
                              /provider — 查看可用 AI 供应商\n\
-                             /provider <序号或ID> — 切换供应商\n\
+                             /provider <序号或ID> — 切换本会话供应商\n\
                              /mode — 查看当前权限模式\n\
-                             /mode <模式> — 切换模式（plan / auto / full）\n\
-                             /status — 查看会话状态\n\
-                             /help — 显示本帮助\n\n\
+                             /mode <模式> — 切换本会话模式（plan / auto / full）\n\
+                             /status — 查看会话状态与设置\n\
+                             {}/help — 显示本帮助\n\n\
+                             ⚙️ /model、/provider、/mode 只影响当前会话，加参数 default 恢复 Bot 默认设置。\n\
                              💬 直接发送文字即可与 AI 对话。\n\
                              🔒 工具审批：收到权限请求时，回复「允许」「始终允许」或「拒绝」。",
+                                usage_line,
+                            ),
                         ).await;
                         continue;
                     }
@@ -753,6 +1007,7 @@ pub async fn start_im_bot<R: Runtime>(
                         adapter_for_reply.ack_clear(&chat_id, &message_id).await;
                         match result {
                             Ok(new_id) => {
+                                last_turns.lock().await.remove(&session_key);
                                 let reply = format!("✅ 已创建新对话 ({})", &new_id[..8.min(new_id.len())]);
                                 let _ = adapter_for_reply.send_message(&chat_id, &reply).await;
                             }
@@ -763,6 +1018,122 @@ pub async fn start_im_bot<R: Runtime>(
                         continue;
                     }
 
+                    // /sessions — list this chat's earlier sessions (as a menu of /resume targets)
+                    if text == "/sessions" || text == "/resume" {
+                        let (current, earlier) = session_summaries(&router_clone, &session_key).await;
+                        let describe = |s: &SessionSummary| {
+                            format!(
+                                "{}\n   创建 {} · 最后活跃 {}",
+                                s.preview.as_deref().unwrap_or("(无消息)"),
+                                sessions::format_timestamp(&s.created_at),
+                                sessions::format_timestamp(&s.last_active_at),
+                            )
+                        };
+                        let mut reply = String::from("📚 历史会话");
+                        if let Some(ref s) = current {
+                            reply.push_str(&format!("\n\n当前: {}", describe(s)));
+                        }
+                        if earlier.is_empty() {
+                            reply.push_str("\n\n暂无更早的会话（/new 开始新对话后，之前的会话会出现在这里）");
+                            let _ = adapter_for_reply.send_message(&chat_id, &reply).await;
+                            continue;
+                        }
+                        for (i, s) in earlier.iter().enumerate() {
+                            reply.push_str(&format!("\n\n{}. {}", i + 1, describe(s)));
+                        }
+                        reply.push_str("\n\n选择要恢复的会话，或发送 /resume <序号>");
+                        let options: Vec<MenuOption> = earlier
+                            .iter()
+                            .enumerate()
+                            .map(|(i, s)| {
+                                let label = format!(
+                                    "{}. {} ({})",
+                                    i + 1,
+                                    s.preview.as_deref().unwrap_or("(无消息)"),
+                                    sessions::format_timestamp(&s.last_active_at)
+                                );
+                                MenuOption::new(label, format!("/resume {}", i + 1))
+                            })
+                            .collect();
+                        send_command_menu(adapter_for_reply.as_ref(), &chat_id, &reply, &options).await;
+                        continue;
+                    }
+
+                    // /export — this chat's conversation (all of its sessions) as a document attachment
+                    if text == "/export" || text.starts_with("/export ") {
+                        let format = match text.strip_prefix("/export").unwrap_or("").trim() {
+                            "json" | "jsonl" => types::TranscriptFormat::Jsonl,
+                            _ => types::TranscriptFormat::Markdown,
+                        };
+                        adapter_for_reply.ack_processing(&chat_id, &message_id).await;
+                        let history = router_clone.lock().await.session_history(&session_key);
+                        let result = export_conversation(history, &bot_id_for_loop, &session_key, format).await;
+                        let error = match result {
+                            Ok(export) if export.message_count == 0 => Some("📭 暂无可导出的对话".to_string()),
+                            Ok(export) => {
+                                let caption = format!("📄 对话记录（{} 条消息）", export.message_count);
+                                adapter_for_reply
+                                    .send_document(&chat_id, &export.file_name, export.content.as_bytes(), Some(&caption))
+                                    .await
+                                    .err()
+                                    .map(|e| format!("❌ 导出失败: {}", e))
+                            }
+                            Err(e) => Some(format!("❌ 导出失败: {}", e)),
+                        };
+                        adapter_for_reply.ack_clear(&chat_id, &message_id).await;
+                        if let Some(reply) = error {
+                            let _ = adapter_for_reply.send_message(&chat_id, &reply).await;
+                        }
+                        continue;
+                    }
+
+                    // /resume <n> — rebind this chat to an earlier session (number from /sessions or ID prefix)
+                    if let Some(arg) = text.strip_prefix("/resume ") {
+                        let arg = arg.trim();
+                        let (_, earlier) = session_summaries(&router_clone, &session_key).await;
+                        let target = match arg.parse::<usize>() {
+                            Ok(n) => n.checked_sub(1).and_then(|i| earlier.get(i)),
+                            Err(_) if arg.len() >= 8 => earlier.iter().find(|s| s.id.starts_with(arg)),
+                            Err(_) => None,
+                        };
+                        let Some(target) = target else {
+                            reply_to_command(
+                                adapter_for_reply.as_ref(),
+                                &msg,
+                                "❌ 未找到该会话，请使用 /sessions 查看列表",
+                            ).await;
+                            continue;
+                        };
+                        // Only move to the session's workspace if it still exists
+                        let workspace = target.workspace.clone().filter(|p| p.is_dir());
+                        let result = router_clone
+                            .lock()
+                            .await
+                            .resume_session(&session_key, &target.id, workspace.clone(), &manager_clone);
+                        let reply = match result {
+                            Ok(()) => {
+                                if let Some(ref ws) = workspace {
+                                    audit_for_loop.record(
+                                        AuditAction::Workspace,
+                                        &msg.sender_id,
+                                        msg.sender_name.as_deref(),
+                                        Some(&chat_id),
+                                        Some(&session_key),
+                                        json!({ "workspace": ws.display().to_string(), "resumed_session": target.id }),
+                                    );
+                                }
+                                apply_chat_override(&router_clone, &health_clone, &session_key, None).await;
+                                format!(
+                                    "✅ 已恢复会话: {}\n下一条消息将在该会话中继续",
+                                    target.preview.as_deref().unwrap_or(&target.id)
+                                )
+                            }
+                            Err(e) => format!("❌ 恢复失败: {}", e),
+                        };
+                        reply_to_command(adapter_for_reply.as_ref(), &msg, &reply).await;
+                        continue;
+                    }
+
                     if text.starts_with("/workspace") {
                         adapter_for_reply.ack_processing(&chat_id, &message_id).await;
                         let path_arg = text.strip_prefix("/workspace").unwrap_or("").trim();
@@ -783,10 +1154,23 @@ pub async fn start_im_bot<R: Runtime>(
                                 .switch_workspace(&session_key, path_arg, &app_clone, &manager_clone)
                                 .await
                             {
-                                Ok(_) => format!("✅ 已切换工作区: {}", path_arg),
+                                Ok(_) => {
+                                    audit_for_loop.record(
+                                        AuditAction::Workspace,
+                                        &msg.sender_id,
+                                        msg.sender_name.as_deref(),
+                                        Some(&chat_id),
+                                        Some(&session_key),
+                                        json!({ "workspace": path_arg }),
+                                    );
+                                    format!("✅ 已切换工作区: {}", path_arg)
+                                }
                                 Err(e) => format!("❌ 切换失败: {}", e),
                             }
                         };
+                        if !path_arg.is_empty() {
+                            apply_chat_override(&router_clone, &health_clone, &session_key, None).await;
+                        }
                         adapter_for_reply.ack_clear(&chat_id, &message_id).await;
                         let _ = adapter_for_reply.send_message(&chat_id, &reply).await;
                         continue;
@@ -794,17 +1178,47 @@ pub async fn start_im_bot<R: Runtime>(
 
                     if text == "/status" {
                         adapter_for_reply.ack_processing(&chat_id, &message_id).await;
+                        let settings = load_chat_settings(
+                            &router_clone,
+                            &session_key,
+                            &available_providers_for_loop,
+                            &current_provider_env_for_loop,
+                            &current_model_for_loop,
+                            &permission_mode_for_loop,
+                        )
+                        .await;
+                        let o = &settings.overrides;
+                        // Values this chat set for itself (instead of following the bot config)
+                        let mark = |overridden: bool| if overridden { " (本会话)" } else { "" };
+                        let provider_name = match settings.provider() {
+                            Some(p) => p.name.clone(),
+                            None if settings.provider_env.is_none() => "Anthropic (订阅)".to_string(),
+                            None => "自定义".to_string(),
+                        };
+                        let ai_settings = format!(
+                            "供应商: {}{}\n模型: {}{}\n权限模式: {}{}",
+                            provider_name,
+                            mark(o.provider_id.is_some()),
+                            settings.model.as_deref().unwrap_or("默认"),
+                            mark(o.model.is_some()),
+                            settings.permission_mode,
+                            mark(o.permission_mode.is_some()),
+                        );
                         let router = router_clone.lock().await;
                         let sessions = router.active_sessions();
                         let current = sessions.iter().find(|s| s.session_key == session_key);
                         let reply = match current {
                             Some(s) => format!(
-                                "📊 Session 状态\n\n工作区: {}\n消息数: {}\n会话: {}",
-                                s.workspace_path, s.message_count, &session_key
+                                "📊 Session 状态\n\n工作区: {}{}\n消息数: {}\n会话: {}\n\n{}",
+                                s.workspace_path,
+                                mark(o.workspace_path.is_some()),
+                                s.message_count,
+                                &session_key,
+                                ai_settings
                             ),
                             None => format!(
-                                "📊 Session 状态\n\n当前无活跃 Session\n会话键: {}",
-                                session_key
+                                "📊 Session 状态\n\n当前无活跃 Session\n会话键: {}\n\n{}",
+                                session_key, ai_settings
                             ),
                         };
                         adapter_for_reply.ack_clear(&chat_id, &message_id).await;
@@ -812,144 +1226,240 @@ pub async fn start_im_bot<R: Runtime>(
                         continue;
                     }
 
-                    // /model — show or switch AI model
+                    // /usage — today's and this month's token usage (this chat and the whole bot)
+                    if text == "/usage" && usage_command {
+                        let path = usage_for_loop.path().to_path_buf();
+                        let now = chrono::Local::now();
+                        let filter = usage::UsageFilter {
+                            since: Some(usage::month_start(now).with_timezone(&chrono::Utc)),
+                            ..Default::default()
+                        };
+                        let records = tokio::task::spawn_blocking(move || usage::read_records(&path, &filter))
+                            .await
+                            .map_err(|e| e.to_string())
+                            .and_then(|r| r);
+                        let reply = match records {
+                            Ok(records) => usage::format_report(&records, &session_key, now),
+                            Err(e) => format!("❌ 读取用量失败: {}", e),
+                        };
+                        let _ = adapter_for_reply.send_message(&chat_id, &reply).await;
+                        continue;
+                    }
+
+                    // /model — show this chat's provider models (as a menu) or switch this chat's model
                     if text.starts_with("/model") {
                         let arg = text.strip_prefix("/model").unwrap_or("").trim().to_string();
+                        let settings = load_chat_settings(
+                            &router_clone,
+                            &session_key,
+                            &available_providers_for_loop,
+                            &current_provider_env_for_loop,
+                            &current_model_for_loop,
+                            &permission_mode_for_loop,
+                        )
+                        .await;
+                        let provider = settings.provider().cloned();
+                        let overridden = settings.overrides.model.is_some();
+
                         if arg.is_empty() {
-                            let current = current_model_for_loop.read().await;
-                            let display = current.as_deref().unwrap_or("claude-sonnet-4-6 (默认)");
-                            let help = format!(
-                                "📊 当前模型: {}\n\n可用快捷名:\n\
-                                 • sonnet → claude-sonnet-4-6\n\
-                                 • opus → claude-opus-4-6\n\
-                                 • haiku → claude-haiku-4-5\n\n\
-                                 用法: /model <名称>",
-                                display,
-                            );
-                            let _ = adapter_for_reply.send_message(&chat_id, &help).await;
-                        } else {
-                            let model_id = match arg.to_lowercase().as_str() {
-                                "sonnet" => "claude-sonnet-4-6".to_string(),
-                                "opus" => "claude-opus-4-6".to_string(),
-                                "haiku" => "claude-haiku-4-5".to_string(),
-                                other => other.to_string(),
+                            let display = match (settings.model.clone(), provider.as_ref()) {
+                                (Some(model), _) if overridden => format!("{} (本会话)", model),
+                                (Some(model), _) => model,
+                                (None, Some(p)) if !p.primary_model.is_empty() => format!("{} (默认)", p.primary_model),
+                                _ => "默认".to_string(),
                             };
-                            // Update shared model state
-                            {
-                                let mut model_guard = current_model_for_loop.write().await;
-                                *model_guard = Some(model_id.clone());
+                            let mut options: Vec<MenuOption> = provider
+                                .as_ref()
+                                .map(|p| {
+                                    p.models
+                                        .iter()
+                                        .map(|m| {
+                                            let aliases = p.aliases_for(&m.model);
+                                            let label = if aliases.is_empty() {
+                                                format!("{} ({})", m.display_name(), m.model)
+                                            } else {
+                                                format!("{} ({}) — {}", m.display_name(), m.model, aliases.join(" / "))
+                                            };
+                                            MenuOption::new(label, format!("/model {}", m.model))
+                                        })
+                                        .collect()
+                                })
+                                .unwrap_or_default();
+                            if overridden {
+                                options.push(MenuOption::new("↩ 恢复 Bot 默认模型", "/model default"));
                             }
-                            // If peer has an active Sidecar, sync model via API
-                            let router = router_clone.lock().await;
-                            let sessions = router.active_sessions();
-                            if let Some(s) = sessions.iter().find(|s| s.session_key == session_key) {
-                                // Parse port from peer sessions (need to check via ensure_sidecar route)
-                                // Active sessions don't expose port directly, so use the http client
-                                // We'll sync on next message via ensure_sidecar + sync_ai_config pattern
-                                drop(router);
-                                // Attempt to sync if we can find the port
-                                // For now, the model will be picked up when session restarts
-                                ulog_info!("[im] /model: set to {} (session={})", model_id, s.session_key);
-                            }
-                            let _ = adapter_for_reply.send_message(
-                                &chat_id,
-                                &format!("✅ 模型已切换为: {}", model_id),
-                            ).await;
+                            let header = match provider.as_ref() {
+                                Some(p) => format!(
+                                    "📊 当前模型: {}\n供应商: {}\n\n选择模型，或发送 /model <名称或序号>",
+                                    display, p.name
+                                ),
+                                None => format!("📊 当前模型: {}\n\n用法: /model <模型 ID>", display),
+                            };
+                            send_command_menu(adapter_for_reply.as_ref(), &chat_id, &header, &options).await;
+                        } else {
+                            // "default" drops the chat override. Otherwise validate against the
+                            // provider's model list when it is known; without provider info the
+                            // argument is taken as a raw model ID.
+                            let model_id = if arg == "default" {
+                                None
+                            } else {
+                                match provider.as_ref().filter(|p| !p.models.is_empty()) {
+                                    Some(p) => match p.resolve_model(&arg) {
+                                        Some(m) => Some(m.model.clone()),
+                                        None => {
+                                            reply_to_command(
+                                                adapter_for_reply.as_ref(),
+                                                &msg,
+                                                &format!("❌ {} 没有模型「{}」，请使用 /model 查看可用列表", p.name, arg),
+                                            ).await;
+                                            continue;
+                                        }
+                                    },
+                                    None => Some(arg.clone()),
+                                }
+                            };
+                            router_clone
+                                .lock()
+                                .await
+                                .update_overrides(&session_key, |o| o.model = model_id.clone());
+                            let effective = load_chat_settings(
+                                &router_clone,
+                                &session_key,
+                                &available_providers_for_loop,
+                                &current_provider_env_for_loop,
+                                &current_model_for_loop,
+                                &permission_mode_for_loop,
+                            )
+                            .await
+                            .model;
+                            apply_chat_override(&router_clone, &health_clone, &session_key, effective.as_deref()).await;
+                            ulog_info!("[im] /model: {:?} (session={})", model_id, session_key);
+                            let reply = match model_id {
+                                Some(id) => format!("✅ 本会话模型已切换为: {}", id),
+                                None => format!("✅ 已恢复 Bot 默认模型: {}", effective.as_deref().unwrap_or("默认")),
+                            };
+                            reply_to_command(adapter_for_reply.as_ref(), &msg, &reply).await;
                         }
                         continue;
                     }
 
-                    // /provider — show or switch AI provider
+                    // /provider — show or switch this chat's AI provider
                     if text.starts_with("/provider") {
                         let arg = text.strip_prefix("/provider").unwrap_or("").trim().to_string();
-
-                        // Parse available providers from config (hot-reloadable)
-                        let providers: Vec<serde_json::Value> = {
-                            let ap = available_providers_for_loop.read().await;
-                            ap.as_ref()
-                                .and_then(|json| serde_json::from_str(json).ok())
-                                .unwrap_or_default()
-                        };
+                        let settings = load_chat_settings(
+                            &router_clone,
+                            &session_key,
+                            &available_providers_for_loop,
+                            &current_provider_env_for_loop,
+                            &current_model_for_loop,
+                            &permission_mode_for_loop,
+                        )
+                        .await;
+                        let overridden = settings.overrides.provider_id.is_some();
+                        let available = &settings.available;
 
                         if arg.is_empty() {
                             // Show current provider + available list
-                            let current_env = current_provider_env_for_loop.read().await;
-                            let current_name = if current_env.is_none() {
-                                "Anthropic (订阅) [默认]".to_string()
-                            } else {
-                                // Find name by matching baseUrl
-                                let base_url = current_env.as_ref()
-                                    .and_then(|v| v["baseUrl"].as_str());
-                                providers.iter()
-                                    .find(|p| p["baseUrl"].as_str() == base_url)
-                                    .and_then(|p| p["name"].as_str())
-                                    .unwrap_or("自定义")
-                                    .to_string()
+                            let current_name = match settings.provider() {
+                                Some(p) => p.name.clone(),
+                                None if settings.provider_env.is_none() => "Anthropic (订阅) [默认]".to_string(),
+                                None => "自定义".to_string(),
                             };
 
-                            let mut menu = format!("📡 当前供应商: {}\n\n可用供应商:\n", current_name);
-                            for (i, p) in providers.iter().enumerate() {
-                                let name = p["name"].as_str().unwrap_or("?");
-                                let id = p["id"].as_str().unwrap_or("?");
-                                menu.push_str(&format!("{}. {} ({})\n", i + 1, name, id));
+                            let header = format!(
+                                "📡 当前供应商: {}{}\n\n选择供应商，或发送 /provider <序号或ID>",
+                                current_name,
+                                if overridden { " (本会话)" } else { "" }
+                            );
+                            let mut options: Vec<MenuOption> = available
+                                .iter()
+                                .enumerate()
+                                .map(|(i, p)| {
+                                    MenuOption::new(format!("{}. {} ({})", i + 1, p.name, p.id), format!("/provider {}", i + 1))
+                                })
+                                .collect();
+                            if overridden {
+                                options.push(MenuOption::new("↩ 恢复 Bot 默认供应商", "/provider default"));
                             }
-                            menu.push_str("\n用法: /provider <序号或ID>");
-
-                            let _ = adapter_for_reply.send_message(&chat_id, &menu).await;
+                            send_command_menu(adapter_for_reply.as_ref(), &chat_id, &header, &options).await;
                         } else {
-                            // Switch provider by index (1-based) or ID
-                            let target = if let Ok(idx) = arg.parse::<usize>() {
-                                providers.get(idx.saturating_sub(1)).cloned()
+                            // Switch provider by index (1-based) or ID; "default" drops the override
+                            let target = if arg == "default" {
+                                None
                             } else {
-                                providers.iter()
-                                    .find(|p| p["id"].as_str().map(|s| s == arg).unwrap_or(false))
-                                    .cloned()
-                            };
-
-                            match target {
-                                Some(provider) => {
-                                    let name = provider["name"].as_str().unwrap_or("?");
-                                    let primary_model = provider["primaryModel"].as_str().unwrap_or("");
-                                    let provider_id = provider["id"].as_str().unwrap_or("");
-
-                                    // Subscription provider → clear provider env
-                                    if provider_id.contains("sub") {
-                                        *current_provider_env_for_loop.write().await = None;
-                                    } else {
-                                        // Build new provider env from stored info
-                                        let new_env = serde_json::json!({
-                                            "baseUrl": provider["baseUrl"],
-                                            "apiKey": provider["apiKey"],
-                                            "authType": provider["authType"],
-                                        });
-                                        *current_provider_env_for_loop.write().await = Some(new_env);
+                                let found = if let Ok(idx) = arg.parse::<usize>() {
+                                    idx.checked_sub(1).and_then(|i| available.get(i))
+                                } else {
+                                    available.iter().find(|p| p.id == arg)
+                                };
+                                match found {
+                                    Some(provider) => Some(provider.clone()),
+                                    None => {
+                                        reply_to_command(
+                                            adapter_for_reply.as_ref(),
+                                            &msg,
+                                            "❌ 未找到该供应商，请使用 /provider 查看可用列表",
+                                        ).await;
+                                        continue;
                                     }
+                                }
+                            };
 
-                                    // Also switch model to the provider's primary model
-                                    if !primary_model.is_empty() {
-                                        *current_model_for_loop.write().await = Some(primary_model.to_string());
-                                    }
+                            // A new provider brings its primary model, so any model override goes
+                            router_clone.lock().await.update_overrides(&session_key, |o| {
+                                o.provider_id = target.as_ref().map(|p| p.id.clone());
+                                o.model = None;
+                            });
+                            let effective = load_chat_settings(
+                                &router_clone,
+                                &session_key,
+                                &available_providers_for_loop,
+                                &current_provider_env_for_loop,
+                                &current_model_for_loop,
+                                &permission_mode_for_loop,
+                            )
+                            .await;
+                            apply_chat_override(&router_clone, &health_clone, &session_key, effective.model.as_deref()).await;
+
+                            let model_display = effective.model.as_deref().unwrap_or("默认");
+                            audit_for_loop.record(
+                                AuditAction::Provider,
+                                &msg.sender_id,
+                                msg.sender_name.as_deref(),
+                                Some(&chat_id),
+                                Some(&session_key),
+                                json!({
+                                    "providerId": target.as_ref().map(|p| p.id.as_str()),
+                                    "model": effective.model,
+                                }),
+                            );
 
-                                    let _ = adapter_for_reply.send_message(
-                                        &chat_id,
-                                        &format!("✅ 已切换供应商: {}\n模型: {}", name, primary_model),
-                                    ).await;
-                                }
-                                None => {
-                                    let _ = adapter_for_reply.send_message(
-                                        &chat_id,
-                                        "❌ 未找到该供应商，请使用 /provider 查看可用列表",
-                                    ).await;
-                                }
-                            }
+                            let reply = match target {
+                                Some(provider) => format!(
+                                    "✅ 本会话已切换供应商: {}\n模型: {}",
+                                    provider.name, model_display
+                                ),
+                                None => format!("✅ 已恢复 Bot 默认供应商\n模型: {}", model_display),
+                            };
+                            reply_to_command(adapter_for_reply.as_ref(), &msg, &reply).await;
                         }
                         continue;
                     }
 
-                    // /mode — show or switch permission mode
+                    // /mode — show or switch this chat's permission mode
                     if text.starts_with("/mode") {
                         let arg = text.strip_prefix("/mode").unwrap_or("").trim().to_lowercase();
-                        let current = permission_mode_for_loop.read().await.clone();
+                        let settings = load_chat_settings(
+                            &router_clone,
+                            &session_key,
+                            &available_providers_for_loop,
+                            &current_provider_env_for_loop,
+                            &current_model_for_loop,
+                            &permission_mode_for_loop,
+                        )
+                        .await;
+                        let current = settings.permission_mode;
+                        let overridden = settings.overrides.permission_mode.is_some();
 
                         if arg.is_empty() {
                             let display = match current.as_str() {
@@ -958,43 +1468,65 @@ pub async fn start_im_bot<R: Runtime>(
                                 "fullAgency" => "🚀 全自主模式 (fullAgency) — 所有操作自动执行",
                                 _ => "❓ 未知模式",
                             };
-                            let _ = adapter_for_reply.send_message(
-                                &chat_id,
-                                &format!(
-                                    "🔐 当前权限模式\n\n{}\n\n\
-                                     可选模式：\n\
-                                     • plan — 计划模式（最安全）\n\
-                                     • auto — 自动模式（推荐）\n\
-                                     • full — 全自主模式\n\n\
-                                     用法: /mode <模式>",
-                                    display,
-                                ),
-                            ).await;
+                            let header = format!(
+                                "🔐 当前权限模式{}\n\n{}\n\n选择模式，或发送 /mode <模式>",
+                                if overridden { " (本会话)" } else { "" },
+                                display
+                            );
+                            let mut options = vec![
+                                MenuOption::new("🛡 plan — 计划模式（最安全）", "/mode plan"),
+                                MenuOption::new("⚡ auto — 自动模式（推荐）", "/mode auto"),
+                                MenuOption::new("🚀 full — 全自主模式", "/mode full"),
+                            ];
+                            if overridden {
+                                options.push(MenuOption::new("↩ 恢复 Bot 默认模式", "/mode default"));
+                            }
+                            send_command_menu(adapter_for_reply.as_ref(), &chat_id, &header, &options).await;
                         } else {
                             let new_mode = match arg.as_str() {
-                                "plan" => "plan",
-                                "auto" => "auto",
-                                "full" | "fullagency" => "fullAgency",
+                                "plan" => Some("plan"),
+                                "auto" => Some("auto"),
+                                "full" | "fullagency" => Some("fullAgency"),
+                                "default" => None,
                                 _ => {
-                                    let _ = adapter_for_reply.send_message(
-                                        &chat_id,
-                                        "❌ 无效模式，可选: plan / auto / full",
+                                    reply_to_command(
+                                        adapter_for_reply.as_ref(),
+                                        &msg,
+                                        "❌ 无效模式，可选: plan / auto / full / default",
                                     ).await;
                                     continue;
                                 }
                             };
-                            *permission_mode_for_loop.write().await = new_mode.to_string();
+                            router_clone
+                                .lock()
+                                .await
+                                .update_overrides(&session_key, |o| o.permission_mode = new_mode.map(String::from));
+                            apply_chat_override(&router_clone, &health_clone, &session_key, None).await;
+                            let effective = match new_mode {
+                                Some(mode) => mode.to_string(),
+                                None => permission_mode_for_loop.read().await.clone(),
+                            };
+                            audit_for_loop.record(
+                                AuditAction::Mode,
+                                &msg.sender_id,
+                                msg.sender_name.as_deref(),
+                                Some(&chat_id),
+                                Some(&session_key),
+                                json!({ "from": current, "to": effective }),
+                            );
 
-                            let display = match new_mode {
+                            let display = match effective.as_str() {
                                 "plan" => "🛡 计划模式 — AI 执行操作前需要审批",
                                 "auto" => "⚡ 自动模式 — 安全操作自动执行",
                                 "fullAgency" => "🚀 全自主模式 — 所有操作自动执行",
-                                _ => unreachable!(),
+                                _ => "❓ 未知模式",
                             };
-                            ulog_info!("[im] /mode: switched to {} (session={})", new_mode, session_key);
-                            let _ = adapter_for_reply.send_message(
-                                &chat_id,
-                                &format!("✅ 权限模式已切换\n\n{}", display),
+                            ulog_info!("[im] /mode: switched to {} (session={})", effective, session_key);
+                            let title = if new_mode.is_some() { "本会话权限模式已切换" } else { "已恢复 Bot 默认权限模式" };
+                            reply_to_command(
+                                adapter_for_reply.as_ref(),
+                                &msg,
+                                &format!("✅ {}\n\n{}", title, display),
                             ).await;
                         }
                         continue;
@@ -1027,14 +1559,44 @@ pub async fn start_im_bot<R: Runtime>(
                         // No pending approval — fall through to regular message handling
                     }
 
+                    // ── Rate limits (checked before the per-peer lock, so rejected
+                    //    messages never queue behind an in-flight turn) ──
+                    let turn_permit = match rate_limiter_for_loop.try_acquire(&msg.sender_id, &session_key) {
+                        Ok(permit) => permit,
+                        Err(exceeded) => {
+                            ulog_info!(
+                                "[im] Rate limited sender {} in {}: {:?}",
+                                msg.sender_id,
+                                session_key,
+                                exceeded.kind,
+                            );
+                            if exceeded.should_notify {
+                                let _ = adapter_for_reply.send_message(&chat_id, &exceeded.reply_text()).await;
+                            }
+                            continue;
+                        }
+                    };
+
                     // ── Regular message → spawn concurrent task ──────────
                     ulog_info!(
-                        "[im] Routing message from {} to Sidecar (session_key={}, {} chars)",
+                        "[im] Routing {} from {} to Sidecar (session_key={}, {} chars)",
+                        if rerun { "re-run" } else { "message" },
                         msg.sender_name.as_deref().unwrap_or("?"),
                         session_key,
                         text.len(),
                     );
 
+                    // Remember it as the peer's last turn. A re-run keeps the replaced turn's
+                    // reply IDs (possibly still being filled in) for the task to delete.
+                    {
+                        let mut turns = last_turns.lock().await;
+                        let reply_ids = match turns.remove(&session_key) {
+                            Some(t) if rerun => t.reply_ids,
+                            _ => Vec::new(),
+                        };
+                        turns.insert(session_key.clone(), LastTurn { message: msg.clone(), reply_ids });
+                    }
+
                     // Clone shared state for the spawned task
                     let task_router = Arc::clone(&router_clone);
                     let task_adapter = Arc::clone(&adapter_for_reply);
@@ -1042,17 +1604,26 @@ pub async fn start_im_bot<R: Runtime>(
                     let task_manager = Arc::clone(&manager_clone);
                     let task_buffer = Arc::clone(&buffer_clone);
                     let task_health = Arc::clone(&health_clone);
-                    let task_perm = permission_mode_for_loop.read().await.clone();
+                    let task_permission_mode = Arc::clone(&permission_mode_for_loop);
                     let task_provider_env = Arc::clone(&current_provider_env_for_loop);
                     let task_model = Arc::clone(&current_model_for_loop);
                     let task_mcp_json = mcp_servers_json_for_loop.read().await.clone();
                     let task_stream_client = stream_client.clone();
-                    let task_sem = Arc::clone(&global_semaphore);
                     let task_locks = Arc::clone(&peer_locks_for_loop);
                     let task_pending_approvals = Arc::clone(&pending_approvals_for_loop);
                     let task_bot_id = bot_id_for_loop.clone();
+                    let task_providers_json = Arc::clone(&available_providers_for_loop);
+                    let task_fallback_ids = Arc::clone(&fallback_provider_ids);
+                    let task_cooldowns = Arc::clone(&failover_cooldowns);
+                    let task_last_turns = Arc::clone(&last_turns);
+                    let task_transcriber = transcriber.clone();
+                    let task_inbox = Arc::clone(&inbox_for_loop);
+                    let task_usage = Arc::clone(&usage_for_loop);
 
                     in_flight.spawn(async move {
+                        // Released on drop (end of turn, including early returns)
+                        let _turn_permit = turn_permit;
+
                         // 1. Acquire per-peer lock FIRST (serialize requests to same Sidecar).
                         let peer_lock = {
                             let mut locks = task_locks.lock().await;
@@ -1063,18 +1634,14 @@ pub async fn start_im_bot<R: Runtime>(
                         };
                         let _peer_guard = peer_lock.lock().await;
 
-                        // 2. Acquire global semaphore (rate limit across all peers)
-                        let _permit = match task_sem.clone().acquire_owned().await {
-                            Ok(p) => p,
-                            Err(_) => {
-                                ulog_error!("[im] Semaphore closed");
-                                return;
-                            }
-                        };
+                        // 2. Acquire a turn slot from the app-wide scheduler
+                        let _permit = get_turn_scheduler().acquire(&task_bot_id, &session_key).await;
 
-                        // 3. ACK + typing indicator
-                        task_adapter.ack_processing(&chat_id, &message_id).await;
-                        task_adapter.send_typing(&chat_id).await;
+                        // 3. ACK + typing indicator (kept alive until the turn ends). A coalesced
+                        //    message acknowledges each of its parts.
+                        let ack_ids = msg.part_ids();
+                        ack_parts_processing(task_adapter.as_ref(), &chat_id, &ack_ids).await;
+                        let typing = TypingKeepalive::start(Arc::clone(&task_adapter), chat_id.clone());
 
                         // 4. Ensure Sidecar is running (brief router lock)
                         let (port, is_new_sidecar) = match task_router
@@ -1085,7 +1652,7 @@ pub async fn start_im_bot<R: Runtime>(
                         {
                             Ok(result) => result,
                             Err(e) => {
-                                task_adapter.ack_clear(&chat_id, &message_id).await;
+                                ack_parts_clear(task_adapter.as_ref(), &chat_id, &ack_ids).await;
                                 let _ = task_adapter
                                     .send_message(&chat_id, &format!("⚠️ {}", e))
                                     .await;
@@ -1093,15 +1660,49 @@ pub async fn start_im_bot<R: Runtime>(
                             }
                         };
 
-                        // 4b. Sync AI config to newly created Sidecar
+                        // 4a. Re-run: drop the replaced turn from the Sidecar history and its
+                        //     reply from the chat (turns to this peer have finished by now)
+                        if rerun {
+                            let old_replies = task_last_turns
+                                .lock()
+                                .await
+                                .get_mut(&session_key)
+                                .filter(|t| t.message.message_id == message_id)
+                                .map(|t| std::mem::take(&mut t.reply_ids))
+                                .unwrap_or_default();
+                            for id in &old_replies {
+                                let _ = task_adapter.delete_message(&chat_id, id).await;
+                            }
+                            match task_router.lock().await.rewind_turn(port, &message_id).await {
+                                Ok(rewound) => ulog_info!(
+                                    "[im] Re-running turn {} for {} (rewound={}, {} reply message(s) removed)",
+                                    message_id,
+                                    session_key,
+                                    rewound,
+                                    old_replies.len(),
+                                ),
+                                Err(e) => ulog_warn!("[im] Rewind before re-run failed for {}: {}", session_key, e),
+                            }
+                        }
+
+                        // 4b. This chat's settings (its overrides on top of the bot config);
+                        //     sync AI config to a newly created Sidecar
+                        let settings = load_chat_settings(
+                            &task_router,
+                            &session_key,
+                            &task_providers_json,
+                            &task_provider_env,
+                            &task_model,
+                            &task_permission_mode,
+                        )
+                        .await;
                         if is_new_sidecar {
-                            let model = task_model.read().await.clone();
                             task_router
                                 .lock()
                                 .await
                                 .sync_ai_config(
                                     port,
-                                    model.as_deref(),
+                                    settings.model.as_deref(),
                                     task_mcp_json.as_deref(),
                                 )
                                 .await;
@@ -1116,39 +1717,87 @@ pub async fn start_im_bot<R: Runtime>(
                                 .unwrap_or_else(|| router.default_workspace().clone())
                         };
                         let image_payloads = if !msg.attachments.is_empty() {
-                            process_attachments(&mut msg, &workspace_path).await
+                            process_attachments(&mut msg, &workspace_path, &task_inbox, task_transcriber.as_deref()).await
                         } else {
                             Vec::new()
                         };
 
-                        // 5. SSE stream: route message + stream response to Telegram
-                        let penv = task_provider_env.read().await.clone();
+                        // 5. SSE stream: route message + stream response to the IM chat,
+                        //    failing over to the next provider on auth / quota / 5xx errors
+                        let task_perm = settings.permission_mode.clone();
+                        let penv = settings.provider_env.clone();
+                        let stream_model = settings.model.clone();
                         let images = if image_payloads.is_empty() {
                             None
                         } else {
                             Some(&image_payloads)
                         };
-                        let session_id = match stream_to_im(
-                            &task_stream_client,
-                            port,
-                            &msg,
-                            task_adapter.as_ref(),
-                            &chat_id,
-                            &task_perm,
+                        let plan = plan_turn(
                             penv.as_ref(),
-                            images,
-                            &task_pending_approvals,
-                            Some(&task_bot_id),
+                            stream_model.as_deref(),
+                            &settings.available,
+                            &task_fallback_ids,
+                            &task_cooldowns,
                         )
-                        .await
-                        {
-                            Ok(sid) => {
+                        .await;
+                        let mut failure = plan.skipped.clone();
+                        let mut model_switched = false;
+                        let mut result = Ok(StreamedReply { session_id: None, message_ids: Vec::new(), usage: None });
+                        for (i, attempt) in plan.attempts.iter().enumerate() {
+                            if !attempt.is_own {
+                                task_router.lock().await.sync_ai_config(port, attempt.model.as_deref(), None).await;
+                                model_switched = true;
+                            }
+                            let notice = failure.as_ref().map(|f| format!("{}，已切换至 {}", f, attempt.name));
+                            result = stream_to_im(
+                                &task_stream_client,
+                                port,
+                                &msg,
+                                task_adapter.as_ref(),
+                                &chat_id,
+                                &workspace_path,
+                                &task_perm,
+                                attempt.provider_env.as_ref(),
+                                images,
+                                &task_pending_approvals,
+                                Some(&task_bot_id),
+                                attempt.model.as_deref(),
+                                document_threshold,
+                                tool_status,
+                                usage_footer,
+                                notice.as_deref(),
+                                &typing,
+                            )
+                            .await;
+                            let (Err(RouteError::Provider(kind, e)), Some(pid)) = (&result, &attempt.provider_id) else {
+                                break;
+                            };
+                            task_cooldowns.lock().await.mark_failed(pid, Instant::now());
+                            failure = Some(format!("{} {}", attempt.name, kind.label()));
+                            if let Some(next) = plan.attempts.get(i + 1) {
+                                ulog_warn!("[im] Provider {} failed ({}), failing over to {}", pid, e, next.name);
+                            }
+                        }
+                        if model_switched {
+                            // Leave the Sidecar on the chat's own model for the next turn
+                            task_router.lock().await.sync_ai_config(port, plan.own_model.as_deref(), None).await;
+                        }
+                        let session_id = match result {
+                            Ok(reply) => {
                                 ulog_info!(
                                     "[im] Stream complete for {} (session={})",
                                     session_key,
-                                    sid.as_deref().unwrap_or("?"),
+                                    reply.session_id.as_deref().unwrap_or("?"),
                                 );
-                                sid
+                                if let Some(ref u) = reply.usage {
+                                    task_usage.record(UsageSource::Chat, Some(&session_key), Some(&msg.sender_id), None, u);
+                                }
+                                if let Some(turn) = task_last_turns.lock().await.get_mut(&session_key) {
+                                    if turn.message.message_id == message_id {
+                                        turn.reply_ids = reply.message_ids;
+                                    }
+                                }
+                                reply.session_id
                             }
                             Err(e) => {
                                 ulog_error!("[im] Stream error for {}: {}", session_key, e);
@@ -1170,13 +1819,13 @@ pub async fn start_im_bot<R: Runtime>(
                                 let _ = task_adapter
                                     .send_message(&chat_id, &user_msg)
                                     .await;
-                                task_adapter.ack_clear(&chat_id, &message_id).await;
+                                ack_parts_clear(task_adapter.as_ref(), &chat_id, &ack_ids).await;
                                 return;
                             }
                         };
 
                         // 6. Clear ACK reaction
-                        task_adapter.ack_clear(&chat_id, &message_id).await;
+                        ack_parts_clear(task_adapter.as_ref(), &chat_id, &ack_ids).await;
 
                         // 7. Update session state
                         task_router
@@ -1208,21 +1857,37 @@ pub async fn start_im_bot<R: Runtime>(
                                         &buf_msg,
                                         task_adapter.as_ref(),
                                         &buf_chat_id,
+                                        &workspace_path,
                                         &task_perm,
                                         penv.as_ref(),
                                         None, // buffered messages don't preserve attachments
                                         &task_pending_approvals,
                                         Some(&task_bot_id),
+                                        stream_model.as_deref(),
+                                        document_threshold,
+                                        tool_status,
+                                        usage_footer,
+                                        None,
+                                        &typing,
                                     )
                                     .await
                                     {
-                                        Ok(buf_sid) => {
+                                        Ok(buf_reply) => {
+                                            if let Some(ref u) = buf_reply.usage {
+                                                task_usage.record(
+                                                    UsageSource::Chat,
+                                                    Some(&session_key),
+                                                    Some(&buf_msg.sender_id),
+                                                    None,
+                                                    u,
+                                                );
+                                            }
                                             task_router
                                                 .lock()
                                                 .await
                                                 .record_response(
                                                     &session_key,
-                                                    buf_sid.as_deref(),
+                                                    buf_reply.session_id.as_deref(),
                                                 );
                                             replayed += 1;
                                         }
@@ -1266,29 +1931,6 @@ pub async fn start_im_bot<R: Runtime>(
                         ulog_error!("[im] Message task panicked: {}", e);
                     }
                 }
-                // Flush expired media groups
-                _ = tokio::time::sleep(flush_timeout) => {
-                    let expired_keys: Vec<String> = media_groups
-                        .iter()
-                        .filter(|(_, entry)| entry.first_received.elapsed() >= MEDIA_GROUP_TIMEOUT)
-                        .map(|(k, _)| k.clone())
-                        .collect();
-
-                    for group_id in expired_keys {
-                        if let Some(entry) = media_groups.remove(&group_id) {
-                            let merged = merge_media_group(entry.messages);
-                            ulog_info!(
-                                "[im] Flushed media group {} ({} attachments)",
-                                group_id,
-                                merged.attachments.len(),
-                            );
-                            // Re-inject merged message into the channel
-                            if msg_tx_for_reinjection.send(merged).await.is_err() {
-                                ulog_error!("[im] Failed to re-inject merged media group");
-                            }
-                        }
-                    }
-                }
                 _ = process_shutdown_rx.changed() => {
                     if *process_shutdown_rx.borrow() {
                         ulog_info!(
@@ -1329,6 +1971,43 @@ pub async fn start_im_bot<R: Runtime>(
         }
     });
 
+    // Start inbox janitor (prunes expired attachments in every workspace the bot uses)
+    let router_for_janitor = Arc::clone(&router);
+    let inbox_for_janitor = Arc::clone(&inbox);
+    let mut janitor_shutdown_rx = shutdown_rx.clone();
+
+    let _janitor_handle = tokio::spawn(async move {
+        let mut interval = tokio::time::interval(inbox::JANITOR_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    let workspaces = router_for_janitor.lock().await.workspaces();
+                    let inbox = Arc::clone(&inbox_for_janitor);
+                    let _ = tokio::task::spawn_blocking(move || {
+                        let now = std::time::SystemTime::now();
+                        for workspace in workspaces {
+                            let (files, bytes) = inbox.prune_expired(&workspace, now);
+                            if files > 0 {
+                                ulog_info!(
+                                    "[im-inbox] Pruned {} expired files ({} bytes) in {}",
+                                    files,
+                                    bytes,
+                                    workspace.display()
+                                );
+                            }
+                        }
+                    })
+                    .await;
+                }
+                _ = janitor_shutdown_rx.changed() => {
+                    if *janitor_shutdown_rx.borrow() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+
     let started_at = Instant::now();
 
     // Build status (include bind URL for QR code flow / bind code for text bind)
@@ -1354,6 +2033,8 @@ pub async fn start_im_bot<R: Runtime>(
         buffered_messages: buffer.lock().await.len(),
         bind_url,
         bind_code: bind_code_for_status,
+        rate_limit: rate_limiter.counters(),
+        queued_turns: 0,
     };
 
     // ===== Heartbeat Runner (v0.1.21) =====
@@ -1362,9 +2043,11 @@ pub async fn start_im_bot<R: Runtime>(
         let hb_bot_label = bot_username_for_url.clone().unwrap_or_else(|| bot_id.to_string());
         let (runner, config_arc) = heartbeat::HeartbeatRunner::new(
             hb_config,
+            bot_id.clone(),
             hb_bot_label,
             Arc::clone(&current_model),
             Arc::clone(&mcp_servers_json),
+            Arc::clone(&usage),
         );
         let (wake_tx, wake_rx) = mpsc::channel::<types::WakeReason>(64);
 
@@ -1411,6 +2094,10 @@ pub async fn start_im_bot<R: Runtime>(
         heartbeat_wake_tx,
         heartbeat_config: heartbeat_config_arc,
         adapter: Arc::clone(&adapter),
+        audit,
+        rate_limiter,
+        inbox,
+        usage,
         // Hot-reloadable config (Arc clones shared with processing loop)
         current_model,
         current_provider_env,
@@ -1434,6 +2121,9 @@ pub async fn stop_im_bot(
     if let Some(instance) = im_guard.remove(bot_id) {
         ulog_info!("[im] Stopping IM Bot {}...", bot_id);
 
+        // Drop the bot's fairness weight so stopped/deleted bots don't linger in the scheduler
+        get_turn_scheduler().clear_weight(bot_id);
+
         // Signal shutdown to all loops
         let _ = instance.shutdown_tx.send(true);
 
@@ -1526,6 +2216,8 @@ pub async fn get_im_bot_status(im_state: &ManagedImBots, bot_id: &str) -> ImBotS
             buffered_messages: status.buffered_messages,
             bind_url,
             bind_code: bind_code_opt,
+            rate_limit: instance.rate_limiter.counters(),
+            queued_turns: get_turn_scheduler().queue_depth(bot_id),
         }
     } else {
         ImBotStatus::default()
@@ -1563,41 +2255,358 @@ pub async fn get_all_bots_status(im_state: &ManagedImBots) -> HashMap<String, Im
             buffered_messages: status.buffered_messages,
             bind_url,
             bind_code: bind_code_opt,
+            rate_limit: instance.rate_limiter.counters(),
+            queued_turns: get_turn_scheduler().queue_depth(bot_id),
         });
     }
 
     result
 }
 
+// ===== Command menus =====
+
+/// Show a command menu as buttons. If the platform rejects the menu, fall back to a
+/// text list of the commands, which still work when typed.
+async fn send_command_menu<A: adapter::ImStreamAdapter>(
+    adapter: &A,
+    chat_id: &str,
+    text: &str,
+    options: &[MenuOption],
+) {
+    if options.is_empty() {
+        let _ = adapter.send_message(chat_id, text).await;
+        return;
+    }
+    if let Err(e) = adapter.send_menu(chat_id, text, options).await {
+        ulog_warn!("[im] Menu send failed: {}, falling back to text", e);
+        let mut fallback = format!("{}\n", text);
+        for opt in options {
+            fallback.push_str(&format!("\n• {} — {}", opt.label, opt.command));
+        }
+        let _ = adapter.send_message(chat_id, &fallback).await;
+    }
+}
+
+/// Reply to a command. When it came from a menu button, show the result in the menu
+/// message itself (a new message if that edit fails).
+async fn reply_to_command<A: adapter::ImStreamAdapter>(adapter: &A, msg: &ImMessage, text: &str) {
+    if let Some(ref menu_id) = msg.menu_message_id {
+        match adapter.update_menu(&msg.chat_id, menu_id, text).await {
+            Ok(()) => return,
+            Err(e) => ulog_warn!("[im] Menu update failed: {}", e),
+        }
+    }
+    let _ = adapter.send_message(&msg.chat_id, text).await;
+}
+
+// ===== Per-chat settings =====
+
+/// Settings a chat runs with: its overrides on top of the bot config
+struct ChatSettings {
+    overrides: ChatOverrides,
+    /// Parsed `available_providers_json`
+    available: Vec<ProviderInfo>,
+    provider_env: Option<serde_json::Value>,
+    model: Option<String>,
+    permission_mode: String,
+}
+
+impl ChatSettings {
+    /// Provider the chat is using, when it is in `available_providers_json`
+    fn provider(&self) -> Option<&ProviderInfo> {
+        providers::current_provider(&self.available, self.provider_env.as_ref())
+    }
+}
+
+/// Resolve a chat's settings. The model is the chat's own pick, else its provider's default
+/// (primary model, or the first one listed), else the bot's — so dropping an override always
+/// resolves a model to switch the Sidecar back to. An override whose provider was removed
+/// falls back to the bot's provider.
+async fn load_chat_settings(
+    router: &Mutex<SessionRouter>,
+    session_key: &str,
+    available_providers_json: &tokio::sync::RwLock<Option<String>>,
+    bot_provider_env: &tokio::sync::RwLock<Option<serde_json::Value>>,
+    bot_model: &tokio::sync::RwLock<Option<String>>,
+    bot_permission_mode: &tokio::sync::RwLock<String>,
+) -> ChatSettings {
+    let overrides = router.lock().await.overrides(session_key);
+    let available = providers::load_providers(available_providers_json.read().await.as_deref());
+    let provider = overrides
+        .provider_id
+        .as_deref()
+        .and_then(|id| available.iter().find(|p| p.id == id));
+    let provider_model = provider.and_then(|p| {
+        if p.primary_model.is_empty() {
+            p.models.first().map(|m| m.model.clone())
+        } else {
+            Some(p.primary_model.clone())
+        }
+    });
+    let provider_env = match provider {
+        Some(p) => p.provider_env(),
+        None => bot_provider_env.read().await.clone(),
+    };
+    let default_model = match provider_model {
+        Some(model) => Some(model),
+        None => bot_model.read().await.clone(),
+    };
+    let permission_mode = match overrides.permission_mode {
+        Some(ref mode) => mode.clone(),
+        None => bot_permission_mode.read().await.clone(),
+    };
+    ChatSettings {
+        model: overrides.model.clone().or(default_model),
+        overrides,
+        available,
+        provider_env,
+        permission_mode,
+    }
+}
+
+/// After a chat override changed: hot-switch the chat's running Sidecar to `model` (the
+/// effective model from `load_chat_settings`; None when the change didn't touch the model)
+/// and refresh the session list the health state persists.
+async fn apply_chat_override(
+    router: &Mutex<SessionRouter>,
+    health: &HealthManager,
+    session_key: &str,
+    model: Option<&str>,
+) {
+    let router = router.lock().await;
+    if let (Some(port), Some(_)) = (router.peer_port(session_key), model) {
+        router.sync_ai_config(port, model, None).await;
+    }
+    health.set_active_sessions(router.active_sessions()).await;
+}
+
+// ===== Session history =====
+
+/// This chat's current session and its earlier ones (most recent first, numbered from 1 by
+/// /sessions and /resume). Sessions the store has no record of are left out.
+async fn session_summaries(
+    router: &Mutex<SessionRouter>,
+    session_key: &str,
+) -> (Option<SessionSummary>, Vec<SessionSummary>) {
+    let Some((current_id, previous)) = router.lock().await.session_history(session_key) else {
+        return (None, Vec::new());
+    };
+    let mut ids = vec![current_id.clone()];
+    ids.extend(previous);
+    let mut summaries = sessions::load_summaries(&ids);
+    let current = summaries
+        .iter()
+        .position(|s| s.id == current_id)
+        .map(|i| summaries.remove(i));
+    (current, summaries)
+}
+
+/// A peer's conversation — its earlier sessions (oldest first), then the current one — with
+/// the tool approvals made in its chat, rendered for export. `history` is the peer's
+/// `(current, previous)` session ids, from the router or the persisted session list.
+async fn export_conversation(
+    history: Option<(String, Vec<String>)>,
+    bot_id: &str,
+    session_key: &str,
+    format: types::TranscriptFormat,
+) -> Result<types::TranscriptExport, String> {
+    let ids: Vec<String> = match history {
+        Some((current_id, previous)) => previous.into_iter().rev().chain(std::iter::once(current_id)).collect(),
+        None => Vec::new(),
+    };
+    let (_, chat_id) = router::parse_session_key(session_key);
+    let audit_path = health::bot_audit_path(bot_id);
+    tokio::task::spawn_blocking(move || {
+        let approvals = audit::read_entries(&audit_path, &audit::AuditFilter::default())?
+            .into_iter()
+            .filter(|e| e.chat_id.as_deref() == Some(chat_id.as_str()))
+            .collect();
+        let items = transcript::load(&sessions::store_dir(), &ids, approvals);
+        Ok(transcript::export(&chat_id, &items, format))
+    })
+    .await
+    .map_err(|e| format!("Export task failed: {}", e))?
+}
+
+// ===== Provider failover =====
+
+/// One provider to try for a turn
+struct TurnAttempt {
+    /// None when failover isn't configured (the chat's own env is used as-is)
+    provider_id: Option<String>,
+    name: String,
+    provider_env: Option<serde_json::Value>,
+    model: Option<String>,
+    /// The chat's own provider (model is already synced to the Sidecar)
+    is_own: bool,
+}
+
+/// Providers to try for a turn, in order
+struct TurnPlan {
+    attempts: Vec<TurnAttempt>,
+    /// Why the chat's own provider is not tried first, e.g. "DeepSeek 暂不可用"
+    skipped: Option<String>,
+    /// Model to put back on the Sidecar after a fallback attempt switched it
+    own_model: Option<String>,
+}
+
+/// Build the provider chain for a turn: the chat's own provider, then its fallbacks, minus
+/// providers still cooling down. Without fallbacks, or when the own provider isn't in
+/// `available_providers_json`, this is just the chat's own settings.
+async fn plan_turn(
+    provider_env: Option<&serde_json::Value>,
+    model: Option<&str>,
+    available: &[ProviderInfo],
+    fallback_ids: &[String],
+    cooldowns: &Mutex<ProviderCooldowns>,
+) -> TurnPlan {
+    let own = |provider_id: Option<String>, name: String| TurnAttempt {
+        provider_id,
+        name,
+        provider_env: provider_env.cloned(),
+        model: model.map(String::from),
+        is_own: true,
+    };
+    let single = |attempt| TurnPlan { attempts: vec![attempt], skipped: None, own_model: model.map(String::from) };
+    if fallback_ids.is_empty() {
+        return single(own(None, String::new()));
+    }
+    let Some(current) = providers::current_provider(available, provider_env) else {
+        return single(own(None, String::new()));
+    };
+
+    let chain = failover::failover_chain(available, current, fallback_ids);
+    let usable = cooldowns.lock().await.filter(chain, Instant::now());
+    let skipped = (usable[0].id != current.id).then(|| format!("{} 暂不可用", current.name));
+    let own_model = model
+        .map(String::from)
+        .or_else(|| (!current.primary_model.is_empty()).then(|| current.primary_model.clone()));
+    let attempts = usable
+        .into_iter()
+        .map(|p| {
+            if p.id == current.id {
+                own(Some(p.id.clone()), p.name.clone())
+            } else {
+                TurnAttempt {
+                    provider_id: Some(p.id.clone()),
+                    name: p.name.clone(),
+                    provider_env: p.provider_env(),
+                    model: (!p.primary_model.is_empty()).then(|| p.primary_model.clone()),
+                    is_own: false,
+                }
+            }
+        })
+        .collect();
+    TurnPlan { attempts, skipped, own_model }
+}
+
 // ===== SSE Stream → IM Draft ====
 
+/// Telegram's chat action expires after ~5s — refresh a little sooner
+const TYPING_KEEPALIVE_INTERVAL: Duration = Duration::from_secs(4);
+
+/// Keeps the typing indicator (Feishu: the processing reaction) alive during a turn.
+/// Refreshes go through the adapter's outbound queue as best-effort calls, so a spent
+/// budget skips one instead of delaying replies. `stream_to_im` pauses it while waiting
+/// for an approval and once the turn completes or fails; dropping it stops the task.
+struct TypingKeepalive {
+    active: Arc<AtomicBool>,
+    handle: tokio::task::JoinHandle<()>,
+}
+
+impl TypingKeepalive {
+    fn start(adapter: Arc<AnyAdapter>, chat_id: String) -> Self {
+        let active = Arc::new(AtomicBool::new(true));
+        let task_active = Arc::clone(&active);
+        let handle = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(TYPING_KEEPALIVE_INTERVAL);
+            loop {
+                interval.tick().await; // First tick fires immediately
+                if task_active.load(Ordering::Relaxed) {
+                    adapter::ImAdapter::send_typing(adapter.as_ref(), &chat_id).await;
+                }
+            }
+        });
+        Self { active, handle }
+    }
+
+    fn set_active(&self, active: bool) {
+        self.active.store(active, Ordering::Relaxed);
+    }
+}
+
+impl Drop for TypingKeepalive {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+/// Placeholder shown until a reply's first text block (and between steps of the status line)
+const PLACEHOLDER_TEXT: &str = "🤖 生成中...";
+/// Processing reaction on every message a turn answers (coalesced parts included)
+async fn ack_parts_processing(adapter: &AnyAdapter, chat_id: &str, message_ids: &[String]) {
+    for id in message_ids {
+        adapter::ImAdapter::ack_processing(adapter, chat_id, id).await;
+    }
+}
+
+/// Clear the reactions of every message a turn answered
+async fn ack_parts_clear(adapter: &AnyAdapter, chat_id: &str, message_ids: &[String]) {
+    for id in message_ids {
+        adapter::ImAdapter::ack_clear(adapter, chat_id, id).await;
+    }
+}
+
+/// Minimum interval between edits of the tool-activity status line
+const STATUS_THROTTLE: Duration = Duration::from_secs(2);
+
+/// Outcome of a streamed turn
+struct StreamedReply {
+    session_id: Option<String>,
+    /// Reply messages finalized in place (split or document sends aren't tracked)
+    message_ids: Vec<String>,
+    /// Token usage reported with `complete` (absent for stopped or disconnected turns)
+    usage: Option<TurnUsage>,
+}
+
 /// Consume Sidecar SSE stream, managing draft message lifecycle for any IM platform.
 /// Each text block → independent IM message (streamed draft edits).
-/// Returns sessionId on success.
 async fn stream_to_im<A: adapter::ImStreamAdapter>(
     client: &Client,
     port: u16,
     msg: &ImMessage,
     adapter: &A,
     chat_id: &str,
+    workspace: &std::path::Path,
     permission_mode: &str,
     provider_env: Option<&serde_json::Value>,
     images: Option<&Vec<serde_json::Value>>,
     pending_approvals: &PendingApprovals,
     bot_id: Option<&str>,
-) -> Result<Option<String>, RouteError> {
+    model: Option<&str>,
+    document_threshold: usize,
+    status_mode: ActivityMode,
+    usage_footer: bool,
+    notice: Option<&str>,
+    typing: &TypingKeepalive,
+) -> Result<StreamedReply, RouteError> {
+    let started = Instant::now();
+    adapter.set_chat_workspace(chat_id, workspace);
     // Build request body (same as original route_to_sidecar)
     let source = match (&msg.platform, &msg.source_type) {
         (ImPlatform::Telegram, ImSourceType::Private) => "telegram_private",
         (ImPlatform::Telegram, ImSourceType::Group) => "telegram_group",
+        (ImPlatform::Telegram, ImSourceType::Channel) => "telegram_channel",
         (ImPlatform::Feishu, ImSourceType::Private) => "feishu_private",
         (ImPlatform::Feishu, ImSourceType::Group) => "feishu_group",
+        (ImPlatform::Feishu, ImSourceType::Channel) => "feishu_channel",
     };
     let mut body = json!({
         "message": msg.text,
         "source": source,
         "sourceId": msg.chat_id,
         "senderName": msg.sender_name,
+        "messageId": msg.message_id,
         "permissionMode": permission_mode,
     });
     if let Some(env) = provider_env {
@@ -1639,14 +2648,44 @@ async fn stream_to_im<A: adapter::ImStreamAdapter>(
 
     // Response-level placeholder state:
     // - placeholder_id: message ID of "🤖 生成中..." sent when first block is non-text
+    //   (or of the tool-activity status line, which is re-sent after each text block)
     // - first_content_sent: true once user has seen any content (placeholder or real text)
     let mut placeholder_id: Option<String> = None;
     let mut first_content_sent = false;
+    let mut status = ActivityStatus::new(status_mode);
+    let mut status_shown = String::new();
+    let mut last_status_edit = Instant::now();
+    // Refreshes the status line's elapsed time while a step runs
+    let mut status_tick = tokio::time::interval(STATUS_THROTTLE);
+    status_tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let mut awaiting_approval = false;
 
     let mut session_id: Option<String> = None;
     const THROTTLE: Duration = Duration::from_millis(1000);
 
-    while let Some(chunk_result) = byte_stream.next().await {
+    // Response metadata for adapter.finish_response():
+    // tools used in the turn + the last finalized message (id, text)
+    let mut tools: Vec<String> = Vec::new();
+    let mut last_final: Option<(String, String)> = None;
+    let mut message_ids: Vec<String> = Vec::new();
+
+    loop {
+        let chunk_result = tokio::select! {
+            chunk = byte_stream.next() => match chunk {
+                Some(chunk) => chunk,
+                None => break,
+            },
+            // Also catches up on status changes whose edit was throttled
+            _ = status_tick.tick(), if status.enabled() && placeholder_id.is_some() && !awaiting_approval => {
+                if let Some(ref pid) = placeholder_id {
+                    if last_status_edit.elapsed() >= STATUS_THROTTLE {
+                        let text = status.render(Instant::now()).unwrap_or_else(|| PLACEHOLDER_TEXT.to_string());
+                        show_status(adapter, chat_id, pid, &text, &mut status_shown, &mut last_status_edit).await;
+                    }
+                }
+                continue;
+            }
+        };
         let chunk = chunk_result
             .map_err(|e| RouteError::Unavailable(format!("SSE stream error: {}", e)))?;
         buffer.push_str(&String::from_utf8_lossy(&chunk));
@@ -1670,7 +2709,11 @@ async fn stream_to_im<A: adapter::ImStreamAdapter>(
                 Err(_) => continue,
             };
 
-            match json_val["type"].as_str().unwrap_or("") {
+            let event_type = json_val["type"].as_str().unwrap_or("");
+            // Nothing to type about while waiting on the user, or after the turn ended
+            typing.set_active(!matches!(event_type, "permission-request" | "complete" | "error"));
+            awaiting_approval = event_type == "permission-request";
+            match event_type {
                 "partial" => {
                     if let Some(text) = json_val["text"].as_str() {
                         block_text = text.to_string();
@@ -1681,14 +2724,21 @@ async fn stream_to_im<A: adapter::ImStreamAdapter>(
                             if let Some(pid) = placeholder_id.take() {
                                 // Adopt the placeholder as draft → edit with real content
                                 draft_id = Some(pid);
-                                let display = format_draft_text(&block_text, adapter.max_message_length());
-                                if let Err(e) = adapter.edit_message(chat_id, draft_id.as_ref().unwrap(), &display).await {
-                                    ulog_warn!("[im] Placeholder→draft edit failed: {}", e);
+                                let display = format_draft_text(&block_text, adapter.max_message_length(), adapter.length_unit());
+                                match adapter.edit_draft(chat_id, draft_id.as_ref().unwrap(), &display).await {
+                                    Ok(true) => last_edit = Instant::now(),
+                                    Ok(false) => {} // send budget spent — next partial retries
+                                    Err(e) => {
+                                        ulog_warn!("[im] Placeholder→draft edit failed: {}", e);
+                                        last_edit = Instant::now();
+                                    }
                                 }
-                                last_edit = Instant::now();
+                                // The status line is gone — the next one starts fresh
+                                status.reset();
+                                status_shown.clear();
                             } else {
                                 // No placeholder — send real content directly as draft
-                                let display = format_draft_text(&block_text, adapter.max_message_length());
+                                let display = format_draft_text(&block_text, adapter.max_message_length(), adapter.length_unit());
                                 match adapter.send_message_returning_id(chat_id, &display).await {
                                     Ok(Some(id)) => {
                                         draft_id = Some(id);
@@ -1700,23 +2750,59 @@ async fn stream_to_im<A: adapter::ImStreamAdapter>(
                             first_content_sent = true;
                         }
 
-                        // Throttled edit (≥1s interval)
+                        // Throttled edit (≥1s interval). Skipped edits (outbound budget spent)
+                        // leave last_edit untouched so the next partial retries with newer text.
                         if let Some(ref did) = draft_id {
                             if last_edit.elapsed() >= THROTTLE {
-                                let display = format_draft_text(&block_text, adapter.max_message_length());
-                                if let Err(e) = adapter.edit_message(chat_id, did, &display).await {
-                                    ulog_warn!("[im] Draft edit failed: {}", e);
+                                let display = format_draft_text(&block_text, adapter.max_message_length(), adapter.length_unit());
+                                match adapter.edit_draft(chat_id, did, &display).await {
+                                    Ok(true) => last_edit = Instant::now(),
+                                    Ok(false) => {}
+                                    Err(e) => {
+                                        ulog_warn!("[im] Draft edit failed: {}", e);
+                                        last_edit = Instant::now();
+                                    }
                                 }
-                                last_edit = Instant::now();
                             }
                         }
                     }
                 }
                 "activity" => {
-                    // Non-text block started (thinking, tool_use).
-                    // If user hasn't seen any content yet, send a placeholder.
-                    if !first_content_sent {
-                        match adapter.send_message_returning_id(chat_id, "🤖 生成中...").await {
+                    let kind = json_val["kind"].as_str().unwrap_or("");
+                    if let Some(name) = json_val["toolName"].as_str() {
+                        // tool_input / tool_result follow the tool's own start event
+                        if !matches!(kind, "tool_input" | "tool_result") {
+                            tools.push(name.to_string());
+                        }
+                    }
+                    status.on_event(&json_val, Instant::now());
+                    if status.enabled() {
+                        // Status line in the placeholder: sent before the first text block and
+                        // again after each one, edited (throttled) as tools run
+                        if draft_id.is_none() {
+                            let text = status
+                                .render(Instant::now())
+                                .unwrap_or_else(|| PLACEHOLDER_TEXT.to_string());
+                            match placeholder_id {
+                                Some(ref pid) => {
+                                    if last_status_edit.elapsed() >= STATUS_THROTTLE {
+                                        show_status(adapter, chat_id, pid, &text, &mut status_shown, &mut last_status_edit).await;
+                                    }
+                                }
+                                None => {
+                                    if let Ok(Some(id)) = adapter.send_message_returning_id(chat_id, &text).await {
+                                        placeholder_id = Some(id);
+                                        status_shown = text;
+                                        last_status_edit = Instant::now();
+                                    }
+                                    first_content_sent = true;
+                                }
+                            }
+                        }
+                    } else if !first_content_sent {
+                        // Non-text block started (thinking, tool_use).
+                        // If user hasn't seen any content yet, send a placeholder.
+                        match adapter.send_message_returning_id(chat_id, PLACEHOLDER_TEXT).await {
                             Ok(Some(id)) => {
                                 placeholder_id = Some(id);
                             }
@@ -1737,7 +2823,10 @@ async fn stream_to_im<A: adapter::ImStreamAdapter>(
                             let _ = adapter.delete_message(chat_id, did).await;
                         }
                     } else {
-                        finalize_block(adapter, chat_id, draft_id.clone(), &final_text).await;
+                        last_final = finalize_block(adapter, chat_id, draft_id.clone(), &final_text, document_threshold)
+                            .await
+                            .map(|id| (id, final_text));
+                        message_ids.extend(last_final.as_ref().map(|(id, _)| id.clone()));
                         any_text_sent = true;
                     }
                     // Reset current block state
@@ -1746,21 +2835,40 @@ async fn stream_to_im<A: adapter::ImStreamAdapter>(
                 }
                 "complete" => {
                     session_id = json_val["sessionId"].as_str().map(String::from);
+                    let usage = serde_json::from_value::<TurnUsage>(json_val["usage"].clone()).ok();
                     // Flush any remaining block text (skip whitespace-only)
                     if !block_text.trim().is_empty() {
-                        finalize_block(adapter, chat_id, draft_id.clone(), &block_text).await;
+                        last_final = finalize_block(adapter, chat_id, draft_id.clone(), &block_text, document_threshold)
+                            .await
+                            .map(|id| (id, block_text.clone()));
+                        message_ids.extend(last_final.as_ref().map(|(id, _)| id.clone()));
                         any_text_sent = true;
                     } else if let Some(ref did) = draft_id {
                         let _ = adapter.delete_message(chat_id, did).await;
                     }
+                    // Clean up orphaned placeholder (e.g. only thinking/tool_use, no text output,
+                    // or a status line after the last text block)
+                    if let Some(ref pid) = placeholder_id {
+                        let _ = adapter.delete_message(chat_id, pid).await;
+                    }
                     if !any_text_sent {
-                        // Clean up orphaned placeholder (e.g. only thinking/tool_use, no text output)
-                        if let Some(ref pid) = placeholder_id {
-                            let _ = adapter.delete_message(chat_id, pid).await;
-                        }
                         let _ = adapter.send_message(chat_id, "(No response)").await;
                     }
-                    return Ok(session_id);
+                    if let Some((ref mid, ref text)) = last_final {
+                        let meta = adapter::ResponseMeta {
+                            model,
+                            duration: started.elapsed(),
+                            tools: &tools,
+                            notice,
+                            usage: usage.as_ref().filter(|_| usage_footer),
+                        };
+                        if let Err(e) = adapter.finish_response(chat_id, mid, text, &meta).await {
+                            ulog_warn!("[im-stream] finish_response failed: {}", e);
+                        }
+                    } else if let Some(notice) = notice {
+                        let _ = adapter.send_message(chat_id, &format!("⚠️ {}", notice)).await;
+                    }
+                    return Ok(StreamedReply { session_id, message_ids, usage });
                 }
                 "permission-request" => {
                     let request_id = json_val["requestId"].as_str().unwrap_or("").to_string();
@@ -1796,6 +2904,8 @@ async fn stream_to_im<A: adapter::ImStreamAdapter>(
                             chat_id: chat_id.to_string(),
                             card_message_id: card_msg_id,
                             created_at: now,
+                            input_digest: audit::input_digest(&tool_input),
+                            tool_name,
                         });
                     }
                     // SSE stream naturally pauses here — canUseTool Promise is blocking
@@ -1804,6 +2914,8 @@ async fn stream_to_im<A: adapter::ImStreamAdapter>(
                     let error = json_val["error"]
                         .as_str()
                         .unwrap_or("Unknown error");
+                    // HTTP status of the underlying API error, when the Sidecar could read one
+                    let status = json_val["status"].as_u64().and_then(|s| u16::try_from(s).ok());
                     // Delete current draft and placeholder if they exist
                     if let Some(ref did) = draft_id {
                         let _ = adapter.delete_message(chat_id, did).await;
@@ -1812,6 +2924,12 @@ async fn stream_to_im<A: adapter::ImStreamAdapter>(
                         let _ = adapter.delete_message(chat_id, pid).await;
                     }
                     // Don't send_message here — outer handler will do it
+                    // Provider failures before any output or tool call can be retried elsewhere
+                    if !any_text_sent && tools.is_empty() {
+                        if let Some(kind) = failover::classify(error, status) {
+                            return Err(RouteError::Provider(kind, error.to_string()));
+                        }
+                    }
                     return Err(RouteError::Response(500, error.to_string()));
                 }
                 _ => {} // Ignore unknown types
@@ -1820,62 +2938,131 @@ async fn stream_to_im<A: adapter::ImStreamAdapter>(
     }
 
     // Stream disconnected unexpectedly → flush any remaining text (skip whitespace-only)
+    typing.set_active(false);
     if !block_text.trim().is_empty() {
-        finalize_block(adapter, chat_id, draft_id.clone(), &block_text).await;
+        message_ids.extend(finalize_block(adapter, chat_id, draft_id.clone(), &block_text, document_threshold).await);
         any_text_sent = true;
     } else if let Some(ref did) = draft_id {
         let _ = adapter.delete_message(chat_id, did).await;
     }
+    if let Some(ref pid) = placeholder_id {
+        let _ = adapter.delete_message(chat_id, pid).await;
+    }
     if !any_text_sent {
-        if let Some(ref pid) = placeholder_id {
-            let _ = adapter.delete_message(chat_id, pid).await;
-        }
         let _ = adapter.send_message(chat_id, "(No response)").await;
     }
-    Ok(session_id)
+    Ok(StreamedReply { session_id, message_ids, usage: None })
+}
+
+/// Edit the placeholder to show `text` unless it already does. Best-effort: a skipped or
+/// failed edit is retried by the next activity event or tick.
+async fn show_status<A: adapter::ImStreamAdapter>(
+    adapter: &A,
+    chat_id: &str,
+    placeholder_id: &str,
+    text: &str,
+    shown: &mut String,
+    last_edit: &mut Instant,
+) {
+    if shown == text {
+        return;
+    }
+    match adapter.edit_draft(chat_id, placeholder_id, text).await {
+        Ok(true) => {
+            *shown = text.to_string();
+            *last_edit = Instant::now();
+        }
+        Ok(false) => {} // send budget spent
+        Err(e) => {
+            ulog_debug!("[im-stream] Status line edit failed: {}", e);
+            *last_edit = Instant::now();
+        }
+    }
+}
+
+/// Default reply length (chars) above which the reply is sent as a document
+const DEFAULT_DOCUMENT_REPLY_THRESHOLD: usize = 12_000;
+/// Length (chars) of the preview sent alongside a document reply
+const DOCUMENT_SUMMARY_LEN: usize = 600;
+
+/// Send a long reply as a short preview plus the full text as a `.md` document.
+async fn send_as_document<A: adapter::ImStreamAdapter>(
+    adapter: &A,
+    chat_id: &str,
+    text: &str,
+) -> adapter::AdapterResult<()> {
+    let preview = split::split_markdown(text, DOCUMENT_SUMMARY_LEN, split::LengthUnit::Chars)
+        .into_iter()
+        .next()
+        .unwrap_or_default();
+    let caption = format!(
+        "{}\n\n📄 回复较长（{} 字），完整内容见附件。",
+        preview,
+        text.chars().count()
+    );
+    let file_name = format!("reply-{}.md", chrono::Local::now().format("%Y%m%d-%H%M%S"));
+    adapter
+        .send_document(chat_id, &file_name, text.as_bytes(), Some(&caption))
+        .await
 }
 
 /// Finalize a text block's draft message.
-/// Uses adapter.max_message_length() to determine the platform's limit.
+/// Uses adapter.max_message_length() to determine the platform's limit. Text longer than
+/// `document_threshold` chars (0 = never) goes out as a preview plus a `.md` document.
+/// Returns the draft's message ID when the final text landed in it (edited in place).
 async fn finalize_block<A: adapter::ImStreamAdapter>(
     adapter: &A,
     chat_id: &str,
     draft_id: Option<String>,
     text: &str,
-) {
+    document_threshold: usize,
+) -> Option<String> {
     if text.is_empty() {
-        return;
+        return None;
+    }
+    if document_threshold > 0 && text.chars().count() > document_threshold {
+        match send_as_document(adapter, chat_id, text).await {
+            Ok(()) => {
+                if let Some(did) = draft_id {
+                    let _ = adapter.delete_message(chat_id, &did).await;
+                }
+                return None;
+            }
+            Err(e) => ulog_warn!("[im] Document reply failed: {}, sending as split messages", e),
+        }
     }
     let max_len = adapter.max_message_length();
-    if let Some(ref did) = draft_id {
-        if text.chars().count() <= max_len {
-            if let Err(e) = adapter.edit_message(chat_id, did, text).await {
-                ulog_warn!("[im] Finalize edit failed: {}, sending as new message", e);
-                let _ = adapter.send_message(chat_id, text).await;
+    if let Some(did) = draft_id {
+        if adapter.length_unit().measure(text) <= max_len {
+            match adapter.edit_message(chat_id, &did, text).await {
+                Ok(()) => return Some(did),
+                Err(e) => {
+                    // e.g. rendered markup outgrew the limit — replace the draft with a fresh (split) send
+                    ulog_warn!("[im] Finalize edit failed: {}, sending as new message", e);
+                    let _ = adapter.delete_message(chat_id, &did).await;
+                    let _ = adapter.send_message(chat_id, text).await;
+                }
             }
         } else {
             // Too long for edit: delete draft → send_message (auto-splits)
-            let _ = adapter.delete_message(chat_id, did).await;
+            let _ = adapter.delete_message(chat_id, &did).await;
             let _ = adapter.send_message(chat_id, text).await;
         }
     } else {
         // No draft created (very fast response) → send directly
         let _ = adapter.send_message(chat_id, text).await;
     }
+    None
 }
 
 /// Format draft display text (truncate if needed for platform limit).
-/// `max_len` is the platform's message limit (e.g. 4096 for Telegram, 30000 for Feishu).
-fn format_draft_text(text: &str, max_len: usize) -> String {
+/// `max_len` is the platform's message limit in `unit` (e.g. 4096 chars for Telegram,
+/// 20000 bytes for Feishu).
+fn format_draft_text(text: &str, max_len: usize, unit: split::LengthUnit) -> String {
     // Reserve a small margin for the "..." truncation indicator
     let limit = max_len.saturating_sub(10);
-    if text.chars().count() > limit {
-        let truncate_at = text
-            .char_indices()
-            .nth(limit)
-            .map(|(i, _)| i)
-            .unwrap_or(text.len());
-        format!("{}...", &text[..truncate_at])
+    if unit.measure(text) > limit {
+        format!("{}...", split::truncate(text, limit, unit))
     } else {
         text.to_string()
     }
@@ -1895,31 +3082,6 @@ fn extract_sse_data(event_str: &str) -> String {
         .join("\n")
 }
 
-/// Generate a non-conflicting file path by appending _1, _2, etc.
-fn auto_rename_path(path: &std::path::Path) -> PathBuf {
-    if !path.exists() {
-        return path.to_path_buf();
-    }
-    let stem = path
-        .file_stem()
-        .unwrap_or_default()
-        .to_string_lossy()
-        .to_string();
-    let ext = path
-        .extension()
-        .map(|e| format!(".{}", e.to_string_lossy()))
-        .unwrap_or_default();
-    let parent = path.parent().unwrap_or(path);
-    for i in 1..100 {
-        let new_name = format!("{}_{}{}", stem, i, ext);
-        let new_path = parent.join(new_name);
-        if !new_path.exists() {
-            return new_path;
-        }
-    }
-    path.to_path_buf()
-}
-
 // ===== Auto-start on app launch =====
 
 /// Config shape from ~/.myagents/config.json (only what we need)
@@ -2220,6 +3382,17 @@ pub async fn cmd_start_im_bot(
     feishuAppId: Option<String>,
     feishuAppSecret: Option<String>,
     heartbeatConfigJson: Option<String>,
+    rateLimitJson: Option<String>,
+    schedulerWeight: Option<u32>,
+    feishuCardStreaming: Option<bool>,
+    feishuProcessingCard: Option<bool>,
+    documentReplyThreshold: Option<u32>,
+    fallbackProviderIds: Option<Vec<String>>,
+    transcriptionJson: Option<String>,
+    inboxJson: Option<String>,
+    toolStatus: Option<types::ActivityMode>,
+    usageFooter: Option<bool>,
+    usageCommand: Option<bool>,
 ) -> Result<ImBotStatus, String> {
     let im_platform = match platform.as_deref() {
         Some("feishu") => ImPlatform::Feishu,
@@ -2229,6 +3402,18 @@ pub async fn cmd_start_im_bot(
         .as_deref()
         .filter(|s| !s.is_empty() && *s != "null")
         .and_then(|s| serde_json::from_str::<types::HeartbeatConfig>(s).ok());
+    let rate_limit = rateLimitJson
+        .as_deref()
+        .filter(|s| !s.is_empty() && *s != "null")
+        .and_then(|s| serde_json::from_str::<types::RateLimitConfig>(s).ok());
+    let transcription = transcriptionJson
+        .as_deref()
+        .filter(|s| !s.is_empty() && *s != "null")
+        .and_then(|s| serde_json::from_str::<types::TranscriptionConfig>(s).ok());
+    let inbox = inboxJson
+        .as_deref()
+        .filter(|s| !s.is_empty() && *s != "null")
+        .and_then(|s| serde_json::from_str::<types::InboxConfig>(s).ok());
     let config = ImConfig {
         platform: im_platform,
         bot_token: botToken,
@@ -2244,6 +3429,17 @@ pub async fn cmd_start_im_bot(
         mcp_servers_json: mcpServersJson,
         available_providers_json: availableProvidersJson,
         heartbeat_config,
+        rate_limit,
+        scheduler_weight: schedulerWeight,
+        feishu_card_streaming: feishuCardStreaming.unwrap_or(false),
+        feishu_processing_card: feishuProcessingCard.unwrap_or(false),
+        document_reply_threshold: documentReplyThreshold,
+        fallback_provider_ids: fallbackProviderIds.unwrap_or_default(),
+        transcription,
+        inbox,
+        tool_status: toolStatus.unwrap_or_default(),
+        usage_footer: usageFooter.unwrap_or(false),
+        usage_command: usageCommand.unwrap_or(false),
     };
 
     start_im_bot(
@@ -2341,7 +3537,8 @@ pub async fn cmd_update_heartbeat_config(
 }
 
 /// Hot-update AI config (model + provider env + available providers) for a running bot.
-/// Model is synced to all active Sidecars via POST /api/model/set (SDK hot-switch).
+/// Model is synced to the active Sidecars of chats without their own model/provider override
+/// via POST /api/model/set (SDK hot-switch).
 /// Provider env is updated in memory — next message automatically uses the new value.
 #[tauri::command]
 #[allow(non_snake_case)]
@@ -2384,10 +3581,11 @@ pub async fn cmd_update_im_bot_ai_config(
         }
     }
 
-    // Sync model to all active Sidecars (SDK hot-switch, no session restart needed)
+    // Sync model to active Sidecars (SDK hot-switch, no session restart needed),
+    // skipping chats that picked their own model or provider
     if model.is_some() {
         let router = router.lock().await;
-        for port in router.active_sidecar_ports() {
+        for port in router.bot_model_sidecar_ports() {
             router.sync_ai_config(port, model.as_deref(), None).await;
         }
     }
@@ -2452,12 +3650,24 @@ pub async fn cmd_update_im_bot_allowed_users(
     botId: String,
     allowedUsers: Vec<String>,
 ) -> Result<(), String> {
-    let users = {
+    let (users, audit) = {
         let bots = imState.lock().await;
         let inst = bots.get(&botId).ok_or("Bot not found or not running")?;
-        Arc::clone(&inst.allowed_users)
+        (Arc::clone(&inst.allowed_users), Arc::clone(&inst.audit))
     };
-    *users.write().await = allowedUsers;
+    let previous = std::mem::replace(&mut *users.write().await, allowedUsers.clone());
+    let added: Vec<&String> = allowedUsers.iter().filter(|u| !previous.contains(u)).collect();
+    let removed: Vec<&String> = previous.iter().filter(|u| !allowedUsers.contains(u)).collect();
+    if !added.is_empty() || !removed.is_empty() {
+        audit.record(
+            AuditAction::Allowlist,
+            "desktop",
+            None,
+            None,
+            None,
+            json!({ "added": added, "removed": removed }),
+        );
+    }
     ulog_info!("[im] Allowed users hot-updated for bot {}", botId);
     Ok(())
 }
@@ -2480,3 +3690,121 @@ pub async fn cmd_update_im_bot_workspace(
     ulog_info!("[im] Workspace hot-updated for bot {}: {}", botId, workspacePath);
     Ok(())
 }
+
+/// Inbox disk usage of a running bot, per workspace it uses (empty when not running).
+#[tauri::command]
+#[allow(non_snake_case)]
+pub async fn cmd_im_inbox_usage(
+    imState: tauri::State<'_, ManagedImBots>,
+    botId: String,
+) -> Result<Vec<types::InboxUsage>, String> {
+    let (inbox, workspaces) = {
+        let im_guard = imState.lock().await;
+        let Some(instance) = im_guard.get(&botId) else {
+            return Ok(Vec::new());
+        };
+        let workspaces = instance.router.lock().await.workspaces();
+        (Arc::clone(&instance.inbox), workspaces)
+    };
+    tokio::task::spawn_blocking(move || workspaces.iter().map(|w| inbox.usage(w)).collect())
+        .await
+        .map_err(|e| format!("Inbox usage task failed: {}", e))
+}
+
+/// Read the audit log for a bot (works whether or not the bot is running).
+/// `since` / `until` are RFC3339 timestamps; `limit` keeps the most recent N entries.
+#[tauri::command]
+#[allow(non_snake_case)]
+pub async fn cmd_im_audit_log(
+    botId: String,
+    since: Option<String>,
+    until: Option<String>,
+    userId: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<types::AuditEntry>, String> {
+    let parse_ts = |s: Option<String>, label: &str| -> Result<Option<chrono::DateTime<chrono::Utc>>, String> {
+        match s.as_deref().filter(|s| !s.is_empty()) {
+            Some(v) => chrono::DateTime::parse_from_rfc3339(v)
+                .map(|t| Some(t.with_timezone(&chrono::Utc)))
+                .map_err(|e| format!("Invalid {} timestamp: {}", label, e)),
+            None => Ok(None),
+        }
+    };
+    let filter = audit::AuditFilter {
+        since: parse_ts(since, "since")?,
+        until: parse_ts(until, "until")?,
+        user_id: userId.filter(|u| !u.is_empty()),
+        limit,
+    };
+    let path = health::bot_audit_path(&botId);
+    tokio::task::spawn_blocking(move || audit::read_entries(&path, &filter))
+        .await
+        .map_err(|e| format!("Audit log read task failed: {}", e))?
+}
+
+/// Export a peer's conversation (user messages, bot replies, tool approvals, attachment list)
+/// as Markdown or JSONL. Works whether or not the bot is running: a stopped bot's peer
+/// sessions come from its persisted session list.
+#[tauri::command]
+#[allow(non_snake_case)]
+pub async fn cmd_im_export_conversation(
+    imState: tauri::State<'_, ManagedImBots>,
+    botId: String,
+    sessionKey: String,
+    format: Option<types::TranscriptFormat>,
+) -> Result<types::TranscriptExport, String> {
+    let router = imState.lock().await.get(&botId).map(|instance| Arc::clone(&instance.router));
+    let history = match router {
+        Some(router) => router.lock().await.session_history(&sessionKey),
+        None => health::load_state(&health::bot_health_path(&botId))
+            .active_sessions
+            .into_iter()
+            .find(|s| s.session_key == sessionKey)
+            .map(|s| (s.session_id, s.previous_session_ids)),
+    };
+    export_conversation(history, &botId, &sessionKey, format.unwrap_or_default()).await
+}
+
+/// Usage totals for a bot per local day or month (works whether or not the bot is running).
+/// `groupBy` breaks each period down by peer, user, cron task, source or model;
+/// `since` / `until` are RFC3339 timestamps.
+#[tauri::command]
+#[allow(non_snake_case)]
+pub async fn cmd_im_usage(
+    botId: String,
+    period: Option<types::UsagePeriod>,
+    groupBy: Option<types::UsageGroup>,
+    since: Option<String>,
+    until: Option<String>,
+) -> Result<Vec<types::UsageBucket>, String> {
+    let parse_ts = |s: Option<String>, label: &str| -> Result<Option<chrono::DateTime<chrono::Utc>>, String> {
+        match s.as_deref().filter(|s| !s.is_empty()) {
+            Some(v) => chrono::DateTime::parse_from_rfc3339(v)
+                .map(|t| Some(t.with_timezone(&chrono::Utc)))
+                .map_err(|e| format!("Invalid {} timestamp: {}", label, e)),
+            None => Ok(None),
+        }
+    };
+    let filter = usage::UsageFilter {
+        since: parse_ts(since, "since")?,
+        until: parse_ts(until, "until")?,
+        ..Default::default()
+    };
+    let path = health::bot_usage_path(&botId);
+    let records = tokio::task::spawn_blocking(move || usage::read_records(&path, &filter))
+        .await
+        .map_err(|e| format!("Usage ledger read task failed: {}", e))??;
+    Ok(usage::summarize(&records, period.unwrap_or_default(), groupBy))
+}
+
+/// Record a bot-owned cron run in the bot's usage ledger (the running instance's ledger
+/// when the bot is up, so appends stay serialized).
+pub async fn record_cron_usage<R: Runtime>(app_handle: &AppHandle<R>, bot_id: &str, task_id: &str, turn: &TurnUsage) {
+    use tauri::Manager;
+    let running = match app_handle.try_state::<ManagedImBots>() {
+        Some(state) => state.lock().await.get(bot_id).map(|i| Arc::clone(&i.usage)),
+        None => None,
+    };
+    let ledger = running.unwrap_or_else(|| Arc::new(UsageLedger::new(health::bot_usage_path(bot_id))));
+    ledger.record(UsageSource::Cron, None, None, Some(task_id), turn);
+}
@@ -0,0 +1,309 @@
+// Outbound send pacing for IM adapters.
+// Each adapter owns one OutboundQueue. Every chat-bound API call takes a token from the
+// adapter-wide bucket and from the chat's bucket before it goes out:
+//   - Final sends (replies, heartbeat/cron pushes, deletes, approval cards) wait for a token.
+//   - Best-effort calls (draft edits, typing, reactions) never wait: when the budget is
+//     spent, or a final send is queued for the same chat, they are skipped. A skipped
+//     draft edit is superseded by the next one, which carries newer text anyway.
+// A platform 429 blocks the affected bucket for `retry_after`.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Shortest sleep between admission attempts (avoids spinning on float rounding)
+const MIN_WAIT: Duration = Duration::from_millis(5);
+/// Prune idle chat buckets once the map grows past this size
+const MAX_IDLE_CHATS: usize = 1024;
+
+/// Token bucket rate: `per_sec` sustained, up to `burst` back-to-back
+#[derive(Debug, Clone, Copy)]
+pub struct Rate {
+    pub per_sec: f64,
+    pub burst: f64,
+}
+
+/// Documented platform send limits
+#[derive(Debug, Clone, Copy)]
+pub struct OutboundLimits {
+    pub global: Rate,
+    pub per_chat: Rate,
+    pub per_group: Rate,
+    is_group: fn(&str) -> bool,
+}
+
+impl OutboundLimits {
+    /// Telegram Bot API: ~30 msg/s per bot, ~1 msg/s per chat, 20 msg/min per group.
+    /// Group and channel chat IDs are negative.
+    pub fn telegram() -> Self {
+        Self {
+            global: Rate { per_sec: 30.0, burst: 30.0 },
+            per_chat: Rate { per_sec: 1.0, burst: 3.0 },
+            per_group: Rate { per_sec: 20.0 / 60.0, burst: 3.0 },
+            is_group: |chat_id| chat_id.starts_with('-'),
+        }
+    }
+
+    /// Feishu: 50 req/s per app, 5 req/s per chat (groups and p2p alike).
+    pub fn feishu() -> Self {
+        Self {
+            global: Rate { per_sec: 50.0, burst: 50.0 },
+            per_chat: Rate { per_sec: 5.0, burst: 5.0 },
+            per_group: Rate { per_sec: 5.0, burst: 5.0 },
+            is_group: |_| false,
+        }
+    }
+
+    fn chat_rate(&self, chat_id: &str) -> Rate {
+        if (self.is_group)(chat_id) {
+            self.per_group
+        } else {
+            self.per_chat
+        }
+    }
+}
+
+/// Admission priority
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Priority {
+    /// Must be delivered — waits for a token
+    Final,
+    /// May be dropped — draft edits, typing, reactions
+    BestEffort,
+}
+
+struct Bucket {
+    tokens: f64,
+    last: Instant,
+    blocked_until: Option<Instant>,
+}
+
+impl Bucket {
+    fn new(rate: Rate, now: Instant) -> Self {
+        Self { tokens: rate.burst, last: now, blocked_until: None }
+    }
+
+    fn refill(&mut self, rate: Rate, now: Instant) {
+        if self.blocked_until.is_some_and(|t| t > now) {
+            // No credit accrues while the platform has us blocked
+            self.last = now;
+            return;
+        }
+        self.blocked_until = None;
+        let elapsed = now.saturating_duration_since(self.last).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rate.per_sec).min(rate.burst);
+        self.last = now;
+    }
+
+    /// Time until one token is available (zero = available now)
+    fn wait(&self, rate: Rate, now: Instant) -> Duration {
+        let blocked = self
+            .blocked_until
+            .map_or(Duration::ZERO, |t| t.saturating_duration_since(now));
+        let refill = if self.tokens >= 1.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64((1.0 - self.tokens) / rate.per_sec)
+        };
+        blocked.max(refill)
+    }
+
+    fn block(&mut self, until: Instant) {
+        self.tokens = 0.0;
+        self.blocked_until = Some(self.blocked_until.map_or(until, |t| t.max(until)));
+    }
+
+    fn is_idle(&self, rate: Rate, now: Instant) -> bool {
+        self.blocked_until.is_none()
+            && self.tokens + now.saturating_duration_since(self.last).as_secs_f64() * rate.per_sec >= rate.burst
+    }
+}
+
+struct QueueState {
+    global: Bucket,
+    chats: HashMap<String, Bucket>,
+    /// Final sends currently waiting, per chat (drafts yield to them)
+    finals_waiting: HashMap<String, usize>,
+}
+
+/// Per-adapter outbound pacing queue
+pub struct OutboundQueue {
+    limits: OutboundLimits,
+    state: Mutex<QueueState>,
+}
+
+/// Registers a waiting final send; deregisters on drop (including cancellation).
+struct FinalWaiter<'a> {
+    queue: &'a OutboundQueue,
+    chat_id: String,
+}
+
+impl Drop for FinalWaiter<'_> {
+    fn drop(&mut self) {
+        let mut state = self.queue.state.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(n) = state.finals_waiting.get_mut(&self.chat_id) {
+            *n -= 1;
+            if *n == 0 {
+                state.finals_waiting.remove(&self.chat_id);
+            }
+        }
+    }
+}
+
+impl OutboundQueue {
+    pub fn new(limits: OutboundLimits) -> Self {
+        let now = Instant::now();
+        Self {
+            limits,
+            state: Mutex::new(QueueState {
+                global: Bucket::new(limits.global, now),
+                chats: HashMap::new(),
+                finals_waiting: HashMap::new(),
+            }),
+        }
+    }
+
+    /// Admit one call to `chat_id`. Final waits until a token is free and returns Ok;
+    /// BestEffort returns `Err(wait)` immediately if it would have to wait.
+    pub async fn admit(&self, chat_id: &str, priority: Priority) -> Result<(), Duration> {
+        if priority == Priority::BestEffort {
+            return self.try_admit_at(chat_id, Instant::now());
+        }
+
+        let _waiter = self.enter_final(chat_id);
+        loop {
+            match self.take_at(chat_id, Instant::now()) {
+                Ok(()) => return Ok(()),
+                Err(wait) => tokio::time::sleep(wait.max(MIN_WAIT)).await,
+            }
+        }
+    }
+
+    /// Platform told us to back off. `None` blocks the whole adapter.
+    pub fn backoff(&self, chat_id: Option<&str>, retry_after: Duration) {
+        let now = Instant::now();
+        let until = now + retry_after;
+        let mut guard = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let state = &mut *guard;
+        match chat_id {
+            Some(id) => {
+                let rate = self.limits.chat_rate(id);
+                state
+                    .chats
+                    .entry(id.to_string())
+                    .or_insert_with(|| Bucket::new(rate, now))
+                    .block(until);
+            }
+            None => state.global.block(until),
+        }
+    }
+
+    fn enter_final(&self, chat_id: &str) -> FinalWaiter<'_> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        *state.finals_waiting.entry(chat_id.to_string()).or_insert(0) += 1;
+        FinalWaiter { queue: self, chat_id: chat_id.to_string() }
+    }
+
+    fn try_admit_at(&self, chat_id: &str, now: Instant) -> Result<(), Duration> {
+        {
+            let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            if state.finals_waiting.contains_key(chat_id) {
+                return Err(MIN_WAIT);
+            }
+        }
+        self.take_at(chat_id, now)
+    }
+
+    /// Take a token from both buckets, or report how long until both have one.
+    fn take_at(&self, chat_id: &str, now: Instant) -> Result<(), Duration> {
+        let mut guard = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let state = &mut *guard;
+        let rate = self.limits.chat_rate(chat_id);
+
+        if state.chats.len() > MAX_IDLE_CHATS {
+            let limits = self.limits;
+            state.chats.retain(|id, b| !b.is_idle(limits.chat_rate(id), now));
+        }
+
+        state.global.refill(self.limits.global, now);
+        let chat = state
+            .chats
+            .entry(chat_id.to_string())
+            .or_insert_with(|| Bucket::new(rate, now));
+        chat.refill(rate, now);
+
+        let wait = state.global.wait(self.limits.global, now).max(chat.wait(rate, now));
+        if !wait.is_zero() {
+            return Err(wait);
+        }
+        state.global.tokens -= 1.0;
+        chat.tokens -= 1.0;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits() -> OutboundLimits {
+        OutboundLimits {
+            global: Rate { per_sec: 10.0, burst: 10.0 },
+            per_chat: Rate { per_sec: 1.0, burst: 2.0 },
+            per_group: Rate { per_sec: 0.5, burst: 1.0 },
+            is_group: |id| id.starts_with('-'),
+        }
+    }
+
+    #[test]
+    fn test_chat_bucket_burst_and_refill() {
+        let queue = OutboundQueue::new(limits());
+        let t0 = Instant::now();
+        assert!(queue.take_at("1", t0).is_ok());
+        assert!(queue.take_at("1", t0).is_ok());
+        let wait = queue.take_at("1", t0).unwrap_err();
+        assert!(wait > Duration::from_millis(900) && wait <= Duration::from_secs(1));
+        // Other chats are unaffected; groups use the stricter rate
+        assert!(queue.take_at("2", t0).is_ok());
+        assert!(queue.take_at("-5", t0).is_ok());
+        assert!(queue.take_at("-5", t0).is_err());
+        assert!(queue.take_at("1", t0 + Duration::from_secs(1)).is_ok());
+    }
+
+    #[test]
+    fn test_best_effort_yields_to_waiting_final() {
+        let queue = OutboundQueue::new(limits());
+        let t0 = Instant::now();
+        let waiter = queue.enter_final("1");
+        assert!(queue.try_admit_at("1", t0).is_err());
+        assert!(queue.try_admit_at("2", t0).is_ok());
+        drop(waiter);
+        assert!(queue.try_admit_at("1", t0).is_ok());
+    }
+
+    #[test]
+    fn test_backoff_blocks_until_retry_after() {
+        let queue = OutboundQueue::new(limits());
+        queue.backoff(Some("1"), Duration::from_secs(3));
+        let now = Instant::now();
+        let wait = queue.take_at("1", now).unwrap_err();
+        assert!(wait > Duration::from_secs(2));
+        assert!(queue.take_at("2", now).is_ok());
+
+        queue.backoff(None, Duration::from_secs(3));
+        assert!(queue.take_at("2", Instant::now()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_final_waits_for_token() {
+        let queue = OutboundQueue::new(OutboundLimits {
+            per_chat: Rate { per_sec: 20.0, burst: 1.0 },
+            ..limits()
+        });
+        let start = Instant::now();
+        queue.admit("1", Priority::Final).await.unwrap();
+        queue.admit("1", Priority::Final).await.unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(40));
+        assert!(queue.admit("1", Priority::BestEffort).await.is_err());
+    }
+}
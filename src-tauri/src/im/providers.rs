@@ -0,0 +1,235 @@
+// Provider / model catalogue for the /provider and /model commands.
+// Parsed from `available_providers_json` (built by the frontend from the provider list):
+//   [{ id, name, primaryModel, baseUrl?, authType?, apiKey?,
+//      models?: [{ model, modelName }], modelAliases?: { alias: modelId } }]
+// `/model <name>` is resolved against the current provider's models only.
+// Aliases the user edited in Settings live in config.json (`providerModelAliases`) and
+// are re-read on every load, so edits apply to running bots.
+
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderModel {
+    /// API model ID, e.g. "claude-sonnet-4-6"
+    pub model: String,
+    /// Display name, e.g. "Claude Sonnet 4.6"
+    #[serde(default)]
+    pub model_name: Option<String>,
+}
+
+impl ProviderModel {
+    pub fn display_name(&self) -> &str {
+        self.model_name.as_deref().unwrap_or(&self.model)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderInfo {
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub primary_model: String,
+    #[serde(default)]
+    pub base_url: Option<String>,
+    #[serde(default)]
+    pub auth_type: Option<String>,
+    #[serde(default)]
+    pub api_key: Option<String>,
+    #[serde(default)]
+    pub models: Vec<ProviderModel>,
+    /// Short names for models, e.g. { "sonnet": "claude-sonnet-4-6" }
+    #[serde(default)]
+    pub model_aliases: HashMap<String, String>,
+}
+
+impl ProviderInfo {
+    /// Subscription providers run without a provider env (SDK default auth)
+    pub fn is_subscription(&self) -> bool {
+        self.id.contains("sub")
+    }
+
+    /// Provider env sent to the Sidecar; None for subscription providers
+    pub fn provider_env(&self) -> Option<serde_json::Value> {
+        if self.is_subscription() {
+            return None;
+        }
+        Some(serde_json::json!({
+            "baseUrl": self.base_url,
+            "apiKey": self.api_key,
+            "authType": self.auth_type,
+        }))
+    }
+
+    /// Resolve user input to one of this provider's models. Tries, in order: exact model ID,
+    /// configured alias, 1-based index into the model list, then a unique match on a
+    /// word of the model ID or display name ("sonnet" → "claude-sonnet-4-6").
+    pub fn resolve_model(&self, input: &str) -> Option<&ProviderModel> {
+        let needle = input.trim().to_lowercase();
+        if needle.is_empty() {
+            return None;
+        }
+        if let Some(m) = self.models.iter().find(|m| m.model.to_lowercase() == needle) {
+            return Some(m);
+        }
+        if let Some(target) = self
+            .model_aliases
+            .iter()
+            .find(|(alias, _)| alias.to_lowercase() == needle)
+            .map(|(_, target)| target)
+        {
+            return self.models.iter().find(|m| &m.model == target);
+        }
+        if let Ok(idx) = needle.parse::<usize>() {
+            return idx.checked_sub(1).and_then(|i| self.models.get(i));
+        }
+
+        let words = |s: &str| -> Vec<String> {
+            s.to_lowercase()
+                .split(|c: char| !c.is_alphanumeric() && c != '.')
+                .map(str::to_string)
+                .collect()
+        };
+        let mut matches = self.models.iter().filter(|m| {
+            words(&m.model).contains(&needle) || words(m.display_name()).contains(&needle)
+        });
+        match (matches.next(), matches.next()) {
+            (Some(m), None) => Some(m),
+            _ => None,
+        }
+    }
+
+    /// Aliases pointing at `model`, for display
+    pub fn aliases_for(&self, model: &str) -> Vec<&str> {
+        let mut aliases: Vec<&str> = self
+            .model_aliases
+            .iter()
+            .filter(|(_, target)| target.as_str() == model)
+            .map(|(alias, _)| alias.as_str())
+            .collect();
+        aliases.sort_unstable();
+        aliases
+    }
+}
+
+/// Parse `available_providers_json`. Malformed input yields an empty list.
+pub fn parse_providers(json: Option<&str>) -> Vec<ProviderInfo> {
+    json.and_then(|s| serde_json::from_str(s).ok()).unwrap_or_default()
+}
+
+/// Partial app config for reading user-edited model aliases
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct PartialAppConfig {
+    /// provider ID → { alias: modelId }, replacing that provider's default aliases
+    #[serde(default)]
+    provider_model_aliases: HashMap<String, HashMap<String, String>>,
+}
+
+/// Read `providerModelAliases` from ~/.myagents/config.json (empty if unset or unreadable)
+fn read_alias_overrides() -> HashMap<String, HashMap<String, String>> {
+    dirs::home_dir()
+        .map(|home| home.join(".myagents").join("config.json"))
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str::<PartialAppConfig>(&content).ok())
+        .map(|config| config.provider_model_aliases)
+        .unwrap_or_default()
+}
+
+/// Replace each provider's aliases with the user's, where the user edited them
+fn apply_alias_overrides(providers: &mut [ProviderInfo], overrides: &HashMap<String, HashMap<String, String>>) {
+    for provider in providers {
+        if let Some(aliases) = overrides.get(&provider.id) {
+            provider.model_aliases = aliases.clone();
+        }
+    }
+}
+
+/// `parse_providers` with the aliases from config.json applied
+pub fn load_providers(json: Option<&str>) -> Vec<ProviderInfo> {
+    let mut providers = parse_providers(json);
+    apply_alias_overrides(&mut providers, &read_alias_overrides());
+    providers
+}
+
+/// Provider the bot is currently using: the subscription provider when no provider env
+/// is set, otherwise the one whose baseUrl matches the env.
+pub fn current_provider<'a>(
+    providers: &'a [ProviderInfo],
+    provider_env: Option<&serde_json::Value>,
+) -> Option<&'a ProviderInfo> {
+    match provider_env {
+        None => providers.iter().find(|p| p.is_subscription()),
+        Some(env) => {
+            let base_url = env["baseUrl"].as_str();
+            providers.iter().find(|p| p.base_url.as_deref() == base_url)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn providers() -> Vec<ProviderInfo> {
+        parse_providers(Some(
+            r#"[
+                {"id": "anthropic-sub", "name": "Anthropic (订阅)", "primaryModel": "claude-sonnet-4-6",
+                 "models": [
+                    {"model": "claude-sonnet-4-6", "modelName": "Claude Sonnet 4.6"},
+                    {"model": "claude-opus-4-6", "modelName": "Claude Opus 4.6"},
+                    {"model": "claude-haiku-4-5", "modelName": "Claude Haiku 4.5"}
+                 ],
+                 "modelAliases": {"fast": "claude-haiku-4-5"}},
+                {"id": "deepseek", "name": "DeepSeek", "primaryModel": "deepseek-chat",
+                 "baseUrl": "https://api.deepseek.com/anthropic", "apiKey": "sk-x",
+                 "models": [{"model": "deepseek-chat"}, {"model": "deepseek-reasoner"}]}
+            ]"#,
+        ))
+    }
+
+    #[test]
+    fn test_resolve_model() {
+        let list = providers();
+        let sub = &list[0];
+        assert_eq!(sub.resolve_model("claude-opus-4-6").unwrap().model, "claude-opus-4-6");
+        assert_eq!(sub.resolve_model("Sonnet").unwrap().model, "claude-sonnet-4-6");
+        assert_eq!(sub.resolve_model("fast").unwrap().model, "claude-haiku-4-5");
+        assert_eq!(sub.resolve_model("2").unwrap().model, "claude-opus-4-6");
+        // Ambiguous and unknown names are rejected
+        assert!(sub.resolve_model("claude").is_none());
+        assert!(sub.resolve_model("gpt-4o").is_none());
+        assert!(sub.resolve_model("9").is_none());
+        // Other providers' models don't resolve
+        assert!(list[1].resolve_model("sonnet").is_none());
+        assert_eq!(list[1].resolve_model("reasoner").unwrap().model, "deepseek-reasoner");
+        assert_eq!(sub.aliases_for("claude-haiku-4-5"), vec!["fast"]);
+    }
+
+    #[test]
+    fn test_alias_overrides_replace_defaults() {
+        let mut list = providers();
+        let overrides: HashMap<String, HashMap<String, String>> = serde_json::from_str(
+            r#"{"anthropic-sub": {"big": "claude-opus-4-6"}, "deepseek": {"r1": "deepseek-reasoner"}}"#,
+        )
+        .unwrap();
+        apply_alias_overrides(&mut list, &overrides);
+        assert_eq!(list[0].resolve_model("big").unwrap().model, "claude-opus-4-6");
+        assert!(list[0].aliases_for("claude-haiku-4-5").is_empty());
+        assert_eq!(list[1].resolve_model("r1").unwrap().model, "deepseek-reasoner");
+    }
+
+    #[test]
+    fn test_current_provider_and_env() {
+        let list = providers();
+        assert_eq!(current_provider(&list, None).unwrap().id, "anthropic-sub");
+        let env = list[1].provider_env().unwrap();
+        assert_eq!(current_provider(&list, Some(&env)).unwrap().id, "deepseek");
+        assert!(list[0].provider_env().is_none());
+        assert!(parse_providers(Some("not json")).is_empty());
+    }
+}
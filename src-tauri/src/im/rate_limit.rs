@@ -0,0 +1,287 @@
+// Per-sender / per-chat rate limiting for IM bots.
+// Checked in the processing loop before a turn is spawned (i.e. before the per-peer lock),
+// so over-limit messages never queue behind the peer lock or consume a concurrency slot.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::types::{RateLimitConfig, RateLimitCounters, RateLimitRule};
+
+/// Rolling window for the messages-per-minute limit
+const MINUTE_WINDOW: Duration = Duration::from_secs(60);
+/// Minimum interval between over-limit replies to the same sender/chat
+const NOTICE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Which limit was hit
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LimitKind {
+    MessagesPerMinute(u32),
+    ConcurrentTurns(u32),
+    DailyTurns(u32),
+}
+
+/// Over-limit verdict
+#[derive(Debug)]
+pub struct LimitExceeded {
+    pub kind: LimitKind,
+    /// False when the sender was already told recently — caller should stay silent
+    pub should_notify: bool,
+}
+
+impl LimitExceeded {
+    /// Polite user-facing reply
+    pub fn reply_text(&self) -> String {
+        match self.kind {
+            LimitKind::MessagesPerMinute(n) => format!(
+                "⏳ 消息发送得有点快，请稍等片刻再发（每分钟最多 {} 条）。",
+                n
+            ),
+            LimitKind::ConcurrentTurns(n) => format!(
+                "⏳ 前面的消息还在处理中，请等它完成后再发送（最多同时处理 {} 条）。",
+                n
+            ),
+            LimitKind::DailyTurns(n) => format!(
+                "⏳ 今日对话次数已达上限（{} 次），请明天再来。",
+                n
+            ),
+        }
+    }
+}
+
+/// Usage tracking for one sender or chat
+#[derive(Default)]
+struct Bucket {
+    recent: VecDeque<Instant>,
+    active: u32,
+    day: Option<chrono::NaiveDate>,
+    daily: u32,
+    last_notice: Option<Instant>,
+}
+
+impl Bucket {
+    fn check(&mut self, rule: &RateLimitRule, now: Instant, today: chrono::NaiveDate) -> Option<LimitKind> {
+        while self.recent.front().is_some_and(|t| now.duration_since(*t) >= MINUTE_WINDOW) {
+            self.recent.pop_front();
+        }
+        if self.day != Some(today) {
+            self.day = Some(today);
+            self.daily = 0;
+        }
+        if let Some(n) = rule.messages_per_minute.filter(|n| *n > 0) {
+            if self.recent.len() as u32 >= n {
+                return Some(LimitKind::MessagesPerMinute(n));
+            }
+        }
+        if let Some(n) = rule.concurrent_turns.filter(|n| *n > 0) {
+            if self.active >= n {
+                return Some(LimitKind::ConcurrentTurns(n));
+            }
+        }
+        if let Some(n) = rule.daily_turns.filter(|n| *n > 0) {
+            if self.daily >= n {
+                return Some(LimitKind::DailyTurns(n));
+            }
+        }
+        None
+    }
+
+    fn admit(&mut self, now: Instant) {
+        self.recent.push_back(now);
+        self.active += 1;
+        self.daily += 1;
+    }
+
+    fn take_notice(&mut self, now: Instant) -> bool {
+        let due = self
+            .last_notice
+            .map_or(true, |t| now.duration_since(t) >= NOTICE_INTERVAL);
+        if due {
+            self.last_notice = Some(now);
+        }
+        due
+    }
+
+    fn is_idle(&self, now: Instant, today: chrono::NaiveDate) -> bool {
+        self.active == 0
+            && self.recent.back().map_or(true, |t| now.duration_since(*t) >= MINUTE_WINDOW)
+            && (self.day != Some(today) || self.daily == 0)
+    }
+}
+
+#[derive(Default)]
+struct LimiterState {
+    users: HashMap<String, Bucket>,
+    chats: HashMap<String, Bucket>,
+    counters: RateLimitCounters,
+}
+
+/// Per-bot rate limiter (sender + chat scopes)
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    state: Arc<Mutex<LimiterState>>,
+}
+
+/// Admission for one turn. Dropping it releases the concurrent-turn slots.
+pub struct TurnPermit {
+    state: Arc<Mutex<LimiterState>>,
+    user_key: String,
+    chat_key: String,
+}
+
+impl Drop for TurnPermit {
+    fn drop(&mut self) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(b) = state.users.get_mut(&self.user_key) {
+            b.active = b.active.saturating_sub(1);
+        }
+        if let Some(b) = state.chats.get_mut(&self.chat_key) {
+            b.active = b.active.saturating_sub(1);
+        }
+        state.counters.active_turns = state.counters.active_turns.saturating_sub(1);
+    }
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            state: Arc::new(Mutex::new(LimiterState::default())),
+        }
+    }
+
+    /// Check both scopes and, if allowed, count the message as a started turn.
+    pub fn try_acquire(&self, sender_id: &str, chat_key: &str) -> Result<TurnPermit, LimitExceeded> {
+        self.try_acquire_at(sender_id, chat_key, Instant::now(), chrono::Local::now().date_naive())
+    }
+
+    fn try_acquire_at(
+        &self,
+        sender_id: &str,
+        chat_key: &str,
+        now: Instant,
+        today: chrono::NaiveDate,
+    ) -> Result<TurnPermit, LimitExceeded> {
+        let mut guard = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let state = &mut *guard;
+
+        // Opportunistic cleanup so long-running bots don't accumulate idle buckets
+        if state.users.len() + state.chats.len() > 1024 {
+            state.users.retain(|_, b| !b.is_idle(now, today));
+            state.chats.retain(|_, b| !b.is_idle(now, today));
+        }
+
+        let user = state.users.entry(sender_id.to_string()).or_default();
+        let user_hit = self.config.per_user.as_ref().and_then(|r| user.check(r, now, today));
+        if let Some(kind) = user_hit {
+            let should_notify = user.take_notice(now);
+            state.counters.record_rejection(kind);
+            return Err(LimitExceeded { kind, should_notify });
+        }
+
+        let chat = state.chats.entry(chat_key.to_string()).or_default();
+        let chat_hit = self.config.per_chat.as_ref().and_then(|r| chat.check(r, now, today));
+        if let Some(kind) = chat_hit {
+            let should_notify = chat.take_notice(now);
+            state.counters.record_rejection(kind);
+            return Err(LimitExceeded { kind, should_notify });
+        }
+
+        chat.admit(now);
+        if let Some(user) = state.users.get_mut(sender_id) {
+            user.admit(now);
+        }
+        state.counters.active_turns += 1;
+
+        Ok(TurnPermit {
+            state: Arc::clone(&self.state),
+            user_key: sender_id.to_string(),
+            chat_key: chat_key.to_string(),
+        })
+    }
+
+    /// Snapshot of counters for ImBotStatus
+    pub fn counters(&self) -> RateLimitCounters {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).counters.clone()
+    }
+}
+
+impl RateLimitCounters {
+    fn record_rejection(&mut self, kind: LimitKind) {
+        match kind {
+            LimitKind::MessagesPerMinute(_) => self.rejected_per_minute += 1,
+            LimitKind::ConcurrentTurns(_) => self.rejected_concurrent += 1,
+            LimitKind::DailyTurns(_) => self.rejected_daily += 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(per_minute: Option<u32>, concurrent: Option<u32>, daily: Option<u32>) -> RateLimitRule {
+        RateLimitRule {
+            messages_per_minute: per_minute,
+            concurrent_turns: concurrent,
+            daily_turns: daily,
+        }
+    }
+
+    fn today() -> chrono::NaiveDate {
+        chrono::NaiveDate::from_ymd_opt(2026, 1, 1).unwrap()
+    }
+
+    #[test]
+    fn test_messages_per_minute_window() {
+        let limiter = RateLimiter::new(RateLimitConfig {
+            per_user: Some(rule(Some(2), None, None)),
+            per_chat: None,
+        });
+        let t0 = Instant::now();
+        drop(limiter.try_acquire_at("u", "c", t0, today()).unwrap());
+        drop(limiter.try_acquire_at("u", "c", t0, today()).unwrap());
+        let err = limiter.try_acquire_at("u", "c", t0, today()).err().unwrap();
+        assert_eq!(err.kind, LimitKind::MessagesPerMinute(2));
+        assert!(err.should_notify);
+        // Second rejection within the notice interval stays silent
+        assert!(!limiter.try_acquire_at("u", "c", t0, today()).err().unwrap().should_notify);
+        // Window slides after a minute
+        assert!(limiter.try_acquire_at("u", "c", t0 + MINUTE_WINDOW, today()).is_ok());
+        assert_eq!(limiter.counters().rejected_per_minute, 2);
+    }
+
+    #[test]
+    fn test_concurrent_turns_released_on_drop() {
+        let limiter = RateLimiter::new(RateLimitConfig {
+            per_user: None,
+            per_chat: Some(rule(None, Some(1), None)),
+        });
+        let t0 = Instant::now();
+        let permit = limiter.try_acquire_at("u1", "chat", t0, today()).unwrap();
+        assert_eq!(
+            limiter.try_acquire_at("u2", "chat", t0, today()).err().unwrap().kind,
+            LimitKind::ConcurrentTurns(1)
+        );
+        assert_eq!(limiter.counters().active_turns, 1);
+        drop(permit);
+        assert_eq!(limiter.counters().active_turns, 0);
+        assert!(limiter.try_acquire_at("u2", "chat", t0, today()).is_ok());
+    }
+
+    #[test]
+    fn test_daily_cap_resets_next_day() {
+        let limiter = RateLimiter::new(RateLimitConfig {
+            per_user: Some(rule(None, None, Some(1))),
+            per_chat: None,
+        });
+        let t0 = Instant::now();
+        assert!(limiter.try_acquire_at("u", "c", t0, today()).is_ok());
+        assert_eq!(
+            limiter.try_acquire_at("u", "c", t0, today()).err().unwrap().kind,
+            LimitKind::DailyTurns(1)
+        );
+        let tomorrow = today().succ_opt().unwrap();
+        assert!(limiter.try_acquire_at("u", "c", t0, tomorrow).is_ok());
+    }
+}
@@ -2,7 +2,7 @@
 // Handles: peer→Sidecar mapping, crash recovery, idle session collection, and HTTP client factory.
 //
 // Concurrency model:
-//   Global semaphore + per-peer locks live OUTSIDE the router (in the processing loop).
+//   Per-peer locks + the app-wide turn scheduler live OUTSIDE the router (processing loop).
 //   The router lock is only held briefly for data operations (ensure_sidecar, record_response).
 //   SSE streaming to Sidecars happens WITHOUT the router lock, enabling true per-peer parallelism.
 
@@ -20,10 +20,9 @@ use crate::sidecar::{
     ensure_session_sidecar, release_session_sidecar, ManagedSidecarManager, SidecarOwner,
 };
 
-use super::types::{ImMessage, ImSourceType, PeerSession};
+use super::failover::FailureKind;
+use super::types::{ChatOverrides, ImMessage, ImSourceType, PeerSession};
 
-/// Max concurrent AI requests across all peers
-pub const GLOBAL_CONCURRENCY: usize = 8;
 /// Idle session timeout (30 minutes)
 const IDLE_TIMEOUT_SECS: u64 = 1800;
 /// Max Sidecar restart attempts (reserved for future reconnect logic)
@@ -47,6 +46,8 @@ pub enum RouteError {
     Unavailable(String),
     /// Sidecar returned non-success HTTP status
     Response(u16, String),
+    /// Provider failure (auth, quota, 5xx) reported before any output — eligible for failover
+    Provider(FailureKind, String),
 }
 
 impl RouteError {
@@ -61,6 +62,7 @@ impl std::fmt::Display for RouteError {
             Self::Setup(e) => write!(f, "{}", e),
             Self::Unavailable(e) => write!(f, "Sidecar unavailable: {}", e),
             Self::Response(status, body) => write!(f, "Sidecar returned {}: {}", status, body),
+            Self::Provider(_, e) => write!(f, "{}", e),
         }
     }
 }
@@ -133,12 +135,12 @@ impl SessionRouter {
             }
         }
 
-        // Preserve message_count from existing session (P2 fix)
-        let prev_count = self
+        // Preserve message_count, chat overrides and session history from existing session (P2 fix)
+        let (prev_count, overrides, previous_session_ids) = self
             .peer_sessions
             .get(session_key)
-            .map(|ps| ps.message_count)
-            .unwrap_or(0);
+            .map(|ps| (ps.message_count, ps.overrides.clone(), ps.previous_session_ids.clone()))
+            .unwrap_or_default();
 
         // Need to create or restart Sidecar
         let workspace = self
@@ -194,6 +196,8 @@ impl SessionRouter {
                 source_id,
                 message_count: prev_count,
                 last_active: Instant::now(),
+                overrides,
+                previous_session_ids,
             },
         );
 
@@ -242,6 +246,7 @@ impl SessionRouter {
             if ps.sidecar_port == 0 {
                 let new_session_id = uuid::Uuid::new_v4().to_string();
                 if let Some(ps) = self.peer_sessions.get_mut(session_key) {
+                    remember_session(&mut ps.previous_session_ids, &old_session_id);
                     ps.session_id = new_session_id.clone();
                     ps.message_count = 0;
                     ps.last_active = Instant::now();
@@ -274,6 +279,7 @@ impl SessionRouter {
 
                 // Update peer session
                 if let Some(ps) = self.peer_sessions.get_mut(session_key) {
+                    remember_session(&mut ps.previous_session_ids, &old_session_id);
                     ps.session_id = new_session_id.clone();
                     ps.message_count = 0;
                     ps.last_active = Instant::now();
@@ -295,11 +301,17 @@ impl SessionRouter {
         _app_handle: &AppHandle<R>,
         manager: &ManagedSidecarManager,
     ) -> Result<String, String> {
-        // Release current Sidecar
+        // Release current Sidecar (chat overrides and session history carry over)
+        let mut overrides = ChatOverrides::default();
+        let mut previous_session_ids = Vec::new();
         if let Some(ps) = self.peer_sessions.remove(session_key) {
             let owner = SidecarOwner::ImBot(session_key.to_string());
             let _ = release_session_sidecar(manager, &ps.session_id, &owner);
+            overrides = ps.overrides;
+            previous_session_ids = ps.previous_session_ids;
+            remember_session(&mut previous_session_ids, &ps.session_id);
         }
+        overrides.workspace_path = Some(workspace_path.to_string());
 
         // The next message will auto-create a new Sidecar with the new workspace
         // For now, update the default workspace for this peer
@@ -321,6 +333,8 @@ impl SessionRouter {
                 source_id,
                 message_count: 0,
                 last_active: Instant::now(),
+                overrides,
+                previous_session_ids,
             },
         );
 
@@ -369,6 +383,18 @@ impl SessionRouter {
         &self.default_workspace
     }
 
+    /// Every workspace the bot saves attachments into: the default one and each peer's
+    /// (for the inbox janitor and usage report).
+    pub fn workspaces(&self) -> Vec<PathBuf> {
+        let mut workspaces = vec![self.default_workspace.clone()];
+        for ps in self.peer_sessions.values() {
+            if !workspaces.contains(&ps.workspace_path) {
+                workspaces.push(ps.workspace_path.clone());
+            }
+        }
+        workspaces
+    }
+
     /// Find any active session with a running Sidecar.
     /// Returns (port, source_string, source_id) for cron tasks etc.
     /// Picks the most recently active session for deterministic behavior.
@@ -403,6 +429,94 @@ impl SessionRouter {
         }
     }
 
+    /// Chat overrides for a peer (empty when it has none or no session yet)
+    pub fn overrides(&self, session_key: &str) -> ChatOverrides {
+        self.peer_sessions
+            .get(session_key)
+            .map(|ps| ps.overrides.clone())
+            .unwrap_or_default()
+    }
+
+    /// Change a peer's chat overrides. Creates the peer session (without a Sidecar) if the
+    /// chat hasn't sent a message yet, so the setting applies to its first turn.
+    pub fn update_overrides(&mut self, session_key: &str, update: impl FnOnce(&mut ChatOverrides)) {
+        let default_workspace = self.default_workspace.clone();
+        let ps = self.peer_sessions.entry(session_key.to_string()).or_insert_with(|| {
+            let (source_type, source_id) = parse_session_key(session_key);
+            PeerSession {
+                session_key: session_key.to_string(),
+                session_id: uuid::Uuid::new_v4().to_string(),
+                sidecar_port: 0,
+                workspace_path: default_workspace,
+                source_type,
+                source_id,
+                message_count: 0,
+                last_active: Instant::now(),
+                overrides: ChatOverrides::default(),
+                previous_session_ids: Vec::new(),
+            }
+        });
+        update(&mut ps.overrides);
+    }
+
+    /// Port of the peer's running Sidecar, if any
+    pub fn peer_port(&self, session_key: &str) -> Option<u16> {
+        self.peer_sessions
+            .get(session_key)
+            .map(|ps| ps.sidecar_port)
+            .filter(|port| *port > 0)
+    }
+
+    /// Current session ID and earlier ones (most recent first) of a peer
+    pub fn session_history(&self, session_key: &str) -> Option<(String, Vec<String>)> {
+        self.peer_sessions
+            .get(session_key)
+            .map(|ps| (ps.session_id.clone(), ps.previous_session_ids.clone()))
+    }
+
+    /// Handle /resume — rebind a peer to one of its earlier sessions.
+    /// Releases the running Sidecar; the next message starts one with `session_id`, which
+    /// Bun resumes via SDK resume. `workspace` is where that session ran (SDK history is
+    /// stored per workspace); it becomes the chat's workspace.
+    pub fn resume_session(
+        &mut self,
+        session_key: &str,
+        session_id: &str,
+        workspace: Option<PathBuf>,
+        manager: &ManagedSidecarManager,
+    ) -> Result<(), String> {
+        let default_workspace = self.default_workspace.clone();
+        let ps = self
+            .peer_sessions
+            .get_mut(session_key)
+            .ok_or("No session history for this chat")?;
+        if ps.session_id == session_id {
+            return Ok(());
+        }
+
+        if ps.sidecar_port > 0 {
+            let owner = SidecarOwner::ImBot(session_key.to_string());
+            let _ = release_session_sidecar(manager, &ps.session_id, &owner);
+            ps.sidecar_port = 0;
+        }
+        let old_session_id = std::mem::replace(&mut ps.session_id, session_id.to_string());
+        ps.previous_session_ids.retain(|id| id != session_id);
+        remember_session(&mut ps.previous_session_ids, &old_session_id);
+        ps.message_count = 0;
+        ps.last_active = Instant::now();
+        if let Some(ws) = workspace {
+            ps.overrides.workspace_path = (ws != default_workspace).then(|| ws.display().to_string());
+            ps.workspace_path = ws;
+        }
+        ulog_info!(
+            "[im-router] Resumed session {} for {} (was {})",
+            session_id,
+            session_key,
+            old_session_id,
+        );
+        Ok(())
+    }
+
     /// Get active peer session info (for health state)
     pub fn active_sessions(&self) -> Vec<super::types::ImActiveSession> {
         self.peer_sessions
@@ -414,6 +528,8 @@ impl SessionRouter {
                 workspace_path: ps.workspace_path.display().to_string(),
                 message_count: ps.message_count,
                 last_active: chrono::Utc::now().to_rfc3339(), // Approximate
+                overrides: ps.overrides.clone(),
+                previous_session_ids: ps.previous_session_ids.clone(),
             })
             .collect()
     }
@@ -424,22 +540,31 @@ impl SessionRouter {
     /// Session IDs are restored from persisted state so Bun can resume the conversation
     /// via --session-id → SDK resume. This preserves IM conversation history across app restarts.
     ///
-    /// Workspace is always set to the current `default_workspace` (from settings),
-    /// NOT the persisted value. This ensures workspace changes take effect on restart.
+    /// Workspace is set to the current `default_workspace` (from settings), NOT the persisted
+    /// value, so settings changes take effect on restart — unless the chat picked its own
+    /// workspace with /workspace, which is kept along with its other overrides.
     pub fn restore_sessions(&mut self, sessions: &[super::types::ImActiveSession]) {
         for s in sessions {
             let (source_type, source_id) = parse_session_key(&s.session_key);
+            let workspace_path = s
+                .overrides
+                .workspace_path
+                .as_ref()
+                .map(PathBuf::from)
+                .unwrap_or_else(|| self.default_workspace.clone());
             self.peer_sessions.insert(
                 s.session_key.clone(),
                 PeerSession {
                     session_key: s.session_key.clone(),
                     session_id: s.session_id.clone(), // Restore original session_id for resume
                     sidecar_port: 0, // Sidecar not running yet; ensure_sidecar will start it
-                    workspace_path: self.default_workspace.clone(),
+                    workspace_path,
                     source_type,
                     source_id,
                     message_count: s.message_count,
                     last_active: Instant::now(),
+                    overrides: s.overrides.clone(),
+                    previous_session_ids: s.previous_session_ids.clone(),
                 },
             );
         }
@@ -468,6 +593,20 @@ impl SessionRouter {
             .collect()
     }
 
+    /// Running Sidecars of chats that follow the bot's model (no /model or /provider override).
+    pub fn bot_model_sidecar_ports(&self) -> Vec<u16> {
+        let mut ports: Vec<u16> = self
+            .peer_sessions
+            .values()
+            .filter(|ps| ps.sidecar_port > 0)
+            .filter(|ps| ps.overrides.model.is_none() && ps.overrides.provider_id.is_none())
+            .map(|ps| ps.sidecar_port)
+            .collect();
+        ports.sort_unstable();
+        ports.dedup();
+        ports
+    }
+
     /// Update default workspace path (hot-reload, only affects new sessions).
     pub fn set_default_workspace(&mut self, path: PathBuf) {
         self.default_workspace = path;
@@ -502,6 +641,24 @@ impl SessionRouter {
         }
     }
 
+    /// Drop the turn started by IM message `message_id` (and everything after it) from the
+    /// Sidecar's history, so it can be re-run. Ok(false) if the Sidecar never saw that message.
+    pub async fn rewind_turn(&self, port: u16, message_id: &str) -> Result<bool, String> {
+        let url = format!("http://127.0.0.1:{}/api/im/rewind", port);
+        let resp = self
+            .http_client
+            .post(&url)
+            .json(&json!({ "messageId": message_id }))
+            .send()
+            .await
+            .map_err(|e| format!("Rewind error: {}", e))?;
+        let body: serde_json::Value = resp.json().await.unwrap_or_default();
+        if body["success"].as_bool() != Some(true) {
+            return Err(body["error"].as_str().unwrap_or("Rewind failed").to_string());
+        }
+        Ok(body["rewound"].as_bool().unwrap_or(false))
+    }
+
     /// Release all sessions (shutdown)
     pub fn release_all(&mut self, manager: &ManagedSidecarManager) {
         let keys: Vec<String> = self.peer_sessions.keys().cloned().collect();
@@ -514,16 +671,27 @@ impl SessionRouter {
     }
 }
 
+/// Record `session_id` as the most recent earlier session, keeping the list bounded.
+fn remember_session(previous: &mut Vec<String>, session_id: &str) {
+    previous.retain(|id| id != session_id);
+    previous.insert(0, session_id.to_string());
+    previous.truncate(super::sessions::MAX_PREVIOUS_SESSIONS);
+}
+
 /// Derive source string (e.g. "telegram_private") from session key.
 fn session_key_to_source_str(session_key: &str) -> String {
     if session_key.contains("telegram") && session_key.contains("private") {
         "telegram_private".to_string()
     } else if session_key.contains("telegram") && session_key.contains("group") {
         "telegram_group".to_string()
+    } else if session_key.contains("telegram") && session_key.contains("channel") {
+        "telegram_channel".to_string()
     } else if session_key.contains("feishu") && session_key.contains("private") {
         "feishu_private".to_string()
     } else if session_key.contains("feishu") && session_key.contains("group") {
         "feishu_group".to_string()
+    } else if session_key.contains("feishu") && session_key.contains("channel") {
+        "feishu_channel".to_string()
     } else {
         "telegram_private".to_string()
     }
@@ -531,11 +699,12 @@ fn session_key_to_source_str(session_key: &str) -> String {
 
 /// Parse session key into (source_type, source_id)
 pub fn parse_session_key(session_key: &str) -> (ImSourceType, String) {
-    // Format: im:{platform}:{private|group}:{id}
+    // Format: im:{platform}:{private|group|channel}:{id}
     let parts: Vec<&str> = session_key.split(':').collect();
     if parts.len() >= 4 {
         let source_type = match parts[2] {
             "group" => ImSourceType::Group,
+            "channel" => ImSourceType::Channel,
             _ => ImSourceType::Private,
         };
         let source_id = parts[3..].join(":");
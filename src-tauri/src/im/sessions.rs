@@ -0,0 +1,143 @@
+// Past sessions of an IM peer, for /sessions and /resume.
+// The router keeps the IDs a peer used before (/new, /workspace, /resume push the outgoing
+// one); titles, timestamps and previews come from the Sidecar's session store:
+//   ~/.myagents/sessions.json          [{ id, agentDir, title, createdAt, lastActiveAt, ... }]
+//   ~/.myagents/sessions/{id}.jsonl    one message per line: { role, content, timestamp, ... }
+
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+/// Previous session IDs kept per peer
+pub const MAX_PREVIOUS_SESSIONS: usize = 20;
+/// Preview length (chars) of a session's first message
+const PREVIEW_CHARS: usize = 40;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SessionMetadata {
+    id: String,
+    #[serde(default)]
+    agent_dir: String,
+    #[serde(default)]
+    created_at: String,
+    #[serde(default)]
+    last_active_at: String,
+}
+
+#[derive(Deserialize)]
+struct SessionLine {
+    #[serde(default)]
+    role: String,
+    #[serde(default)]
+    content: String,
+}
+
+/// One past session as listed by /sessions
+#[derive(Debug, Clone)]
+pub struct SessionSummary {
+    pub id: String,
+    /// Workspace the session ran in (SDK history is stored per workspace)
+    pub workspace: Option<PathBuf>,
+    pub created_at: String,
+    pub last_active_at: String,
+    /// Start of the first user message
+    pub preview: Option<String>,
+}
+
+pub(super) fn store_dir() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".myagents")
+}
+
+/// Summaries for `ids` (in the given order) from the default session store.
+/// IDs the store doesn't know (e.g. a session that never got a message) are left out.
+pub fn load_summaries(ids: &[String]) -> Vec<SessionSummary> {
+    load_summaries_from(&store_dir(), ids)
+}
+
+fn load_summaries_from(dir: &Path, ids: &[String]) -> Vec<SessionSummary> {
+    let index: Vec<SessionMetadata> = std::fs::read_to_string(dir.join("sessions.json"))
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default();
+
+    ids.iter()
+        .filter_map(|id| index.iter().find(|m| &m.id == id))
+        .map(|meta| {
+            let preview = std::fs::read_to_string(dir.join("sessions").join(format!("{}.jsonl", meta.id)))
+                .ok()
+                .and_then(|jsonl| first_user_preview(&jsonl));
+            SessionSummary {
+                id: meta.id.clone(),
+                workspace: (!meta.agent_dir.is_empty()).then(|| PathBuf::from(&meta.agent_dir)),
+                created_at: meta.created_at.clone(),
+                last_active_at: meta.last_active_at.clone(),
+                preview,
+            }
+        })
+        .collect()
+}
+
+/// First user message of a session transcript, on one line and cut to `PREVIEW_CHARS`
+fn first_user_preview(jsonl: &str) -> Option<String> {
+    let content = jsonl
+        .lines()
+        .filter_map(|line| serde_json::from_str::<SessionLine>(line).ok())
+        .find(|m| m.role == "user" && !m.content.trim().is_empty())?
+        .content;
+    let flat = content.split_whitespace().collect::<Vec<_>>().join(" ");
+    if flat.chars().count() <= PREVIEW_CHARS {
+        return Some(flat);
+    }
+    Some(format!("{}…", flat.chars().take(PREVIEW_CHARS).collect::<String>()))
+}
+
+/// "2026-10-18T09:30:00.000Z" → "10-18 17:30" in local time (raw string if unparsable)
+pub fn format_timestamp(ts: &str) -> String {
+    chrono::DateTime::parse_from_rfc3339(ts)
+        .map(|t| t.with_timezone(&chrono::Local).format("%m-%d %H:%M").to_string())
+        .unwrap_or_else(|_| ts.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_user_preview() {
+        let jsonl = concat!(
+            "{\"role\":\"assistant\",\"content\":\"hi\"}\n",
+            "not json\n",
+            "{\"role\":\"user\",\"content\":\"  \"}\n",
+            "{\"role\":\"user\",\"content\":\"帮我\\n整理一下 README\"}\n",
+        );
+        assert_eq!(first_user_preview(jsonl).as_deref(), Some("帮我 整理一下 README"));
+        let long = format!("{{\"role\":\"user\",\"content\":\"{}\"}}", "x".repeat(50));
+        assert_eq!(first_user_preview(&long).unwrap().chars().count(), PREVIEW_CHARS + 1);
+        assert!(first_user_preview("").is_none());
+    }
+
+    #[test]
+    fn test_load_summaries_keeps_order_and_skips_unknown() {
+        let dir = std::env::temp_dir().join(format!("im-sessions-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("sessions")).unwrap();
+        std::fs::write(
+            dir.join("sessions.json"),
+            r#"[{"id":"a","agentDir":"/w","title":"A","createdAt":"2026-01-01T00:00:00Z","lastActiveAt":"2026-01-02T00:00:00Z"},
+                {"id":"b","agentDir":"","title":"B","createdAt":"","lastActiveAt":""}]"#,
+        )
+        .unwrap();
+        std::fs::write(dir.join("sessions").join("a.jsonl"), "{\"role\":\"user\",\"content\":\"hello\"}\n").unwrap();
+
+        let ids = vec!["b".to_string(), "missing".to_string(), "a".to_string()];
+        let list = load_summaries_from(&dir, &ids);
+        std::fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(list.iter().map(|s| s.id.as_str()).collect::<Vec<_>>(), vec!["b", "a"]);
+        assert_eq!(list[1].preview.as_deref(), Some("hello"));
+        assert_eq!(list[1].workspace, Some(PathBuf::from("/w")));
+        assert!(list[0].workspace.is_none() && list[0].preview.is_none());
+    }
+}
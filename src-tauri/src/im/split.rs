@@ -0,0 +1,251 @@
+// Markdown-aware message splitting shared by all IM adapters.
+// Long replies are cut on paragraph and line boundaries. A cut inside a fenced code block
+// closes the fence at the end of the chunk and reopens it (with the same info string) at
+// the start of the next, so every chunk renders on its own. Lengths are measured in the
+// platform's unit: Telegram counts UTF-16 code units, Feishu limits request bytes.
+
+/// Unit a platform's message length limit is expressed in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LengthUnit {
+    Chars,
+    Bytes,
+    /// UTF-16 code units (Telegram): emoji and other astral chars count as 2
+    Utf16,
+}
+
+impl LengthUnit {
+    pub fn measure(self, s: &str) -> usize {
+        match self {
+            Self::Chars => s.chars().count(),
+            Self::Bytes => s.len(),
+            Self::Utf16 => s.encode_utf16().count(),
+        }
+    }
+}
+
+/// Room to leave for a part label like "(12/13)" and its markup
+pub const PART_LABEL_RESERVE: usize = 24;
+
+/// Part counter for chunk `index` (0-based) of `total`: "(1/3)"
+pub fn part_label(index: usize, total: usize) -> String {
+    format!("({}/{})", index + 1, total)
+}
+
+/// Longest prefix of `text` that fits in `max_len` (on a char boundary)
+pub fn truncate(text: &str, max_len: usize, unit: LengthUnit) -> &str {
+    if unit.measure(text) <= max_len {
+        return text;
+    }
+    let end = match unit {
+        LengthUnit::Chars => text.char_indices().nth(max_len).map_or(text.len(), |(i, _)| i),
+        LengthUnit::Bytes => {
+            let mut end = max_len;
+            while !text.is_char_boundary(end) {
+                end -= 1;
+            }
+            end
+        }
+        LengthUnit::Utf16 => {
+            let mut units = 0;
+            text.char_indices()
+                .find(|(_, c)| {
+                    units += c.len_utf16();
+                    units > max_len
+                })
+                .map_or(text.len(), |(i, _)| i)
+        }
+    };
+    &text[..end]
+}
+
+/// A fenced code block open at the current position
+struct OpenFence {
+    /// Opening line as written (e.g. "```rust"), repeated at the top of the next chunk
+    open: String,
+    fence_char: char,
+    fence_len: usize,
+}
+
+impl OpenFence {
+    fn close(&self) -> String {
+        self.fence_char.to_string().repeat(self.fence_len)
+    }
+}
+
+/// Fence run at the start of `line`: (char, length), for ``` and ~~~ fences
+fn fence_run(line: &str) -> Option<(char, usize, &str)> {
+    let trimmed = line.trim_start_matches(' ');
+    if line.len() - trimmed.len() > 3 {
+        return None;
+    }
+    let c = trimmed.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let len = trimmed.chars().take_while(|x| *x == c).count();
+    (len >= 3).then(|| (c, len, &trimmed[len..]))
+}
+
+/// Fence state after `line`
+fn fence_after(fence: Option<&OpenFence>, line: &str) -> Option<OpenFence> {
+    match (fence, fence_run(line)) {
+        (Some(f), Some((c, len, rest))) if c == f.fence_char && len >= f.fence_len && rest.trim().is_empty() => None,
+        (Some(f), _) => Some(OpenFence { open: f.open.clone(), fence_char: f.fence_char, fence_len: f.fence_len }),
+        (None, Some((c, len, _))) => Some(OpenFence {
+            open: line.trim_end().to_string(),
+            fence_char: c,
+            fence_len: len,
+        }),
+        (None, None) => None,
+    }
+}
+
+/// Break one overlong line into pieces of at most `max_len`, preferring spaces
+fn split_long_line(line: &str, max_len: usize, unit: LengthUnit) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut rest = line;
+    while unit.measure(rest) > max_len {
+        let head = truncate(rest, max_len, unit);
+        let cut = match head.rfind(' ') {
+            Some(i) if i > head.len() / 2 => i + 1,
+            _ => head.len().max(rest.chars().next().map_or(1, char::len_utf8)),
+        };
+        pieces.push(&rest[..cut]);
+        rest = &rest[cut..];
+    }
+    if !rest.is_empty() {
+        pieces.push(rest);
+    }
+    pieces
+}
+
+/// Split Markdown into chunks of at most `max_len` (in `unit`), preferring paragraph
+/// breaks, then line breaks. Code fences cut mid-block are closed and reopened.
+pub fn split_markdown(text: &str, max_len: usize, unit: LengthUnit) -> Vec<String> {
+    if unit.measure(text) <= max_len {
+        return vec![text.to_string()];
+    }
+
+    // Lines longer than half a chunk are pre-cut so fence markers always fit around them
+    let piece_len = (max_len / 2).max(1);
+    let lines: Vec<&str> = text
+        .split_inclusive('\n')
+        .flat_map(|line| split_long_line(line, piece_len, unit))
+        .collect();
+
+    let mut chunks = Vec::new();
+    let mut chunk = String::new();
+    let mut body_lines = 0;
+    let mut fence: Option<OpenFence> = None;
+    // (index of the line after a blank line outside code, chunk length at that point)
+    let mut last_break: Option<(usize, usize)> = None;
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i];
+        // Drop blank lines at the top of a chunk
+        if body_lines == 0 && fence.is_none() && line.trim().is_empty() {
+            i += 1;
+            continue;
+        }
+
+        let next_fence = fence_after(fence.as_ref(), line);
+        let closing = next_fence.as_ref().map_or(0, |f| unit.measure(&f.close()) + 1);
+        let fits = unit.measure(&chunk) + unit.measure(line) + closing <= max_len;
+
+        if !fits && body_lines > 0 {
+            // Back up to the last paragraph break if that still fills half the chunk
+            if let Some((resume, len)) = last_break {
+                if unit.measure(&chunk[..len]) >= max_len / 2 {
+                    chunk.truncate(len);
+                    i = resume;
+                    fence = None;
+                }
+            }
+            let reopen = fence.as_ref().map(|f| {
+                if !chunk.ends_with('\n') {
+                    chunk.push('\n');
+                }
+                chunk.push_str(&f.close());
+                format!("{}\n", f.open)
+            });
+            chunks.push(chunk.trim_end().to_string());
+            chunk = reopen.unwrap_or_default();
+            body_lines = 0;
+            last_break = None;
+            continue;
+        }
+
+        chunk.push_str(line);
+        body_lines += 1;
+        fence = next_fence;
+        i += 1;
+        if fence.is_none() && line.trim().is_empty() {
+            last_break = Some((i, chunk.len()));
+        }
+    }
+
+    if body_lines > 0 {
+        chunks.push(chunk.trim_end().to_string());
+    }
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_short_text_is_one_chunk() {
+        assert_eq!(split_markdown("hello", 10, LengthUnit::Chars), vec!["hello"]);
+    }
+
+    #[test]
+    fn test_prefers_paragraph_breaks() {
+        let text = format!("{}\n\n{}\n{}", "a".repeat(30), "b".repeat(20), "c".repeat(20));
+        let chunks = split_markdown(&text, 50, LengthUnit::Chars);
+        assert_eq!(chunks, vec!["a".repeat(30), format!("{}\n{}", "b".repeat(20), "c".repeat(20))]);
+    }
+
+    #[test]
+    fn test_code_fence_closed_and_reopened() {
+        let code: Vec<String> = (0..10).map(|i| format!("let x{} = {};", i, i)).collect();
+        let text = format!("Intro\n\n```rust\n{}\n```\nAfter", code.join("\n"));
+        let chunks = split_markdown(&text, 80, LengthUnit::Chars);
+        assert!(chunks.len() > 1);
+        for chunk in &chunks {
+            assert!(chunk.chars().count() <= 80, "{:?}", chunk);
+            let fences = chunk.lines().filter(|l| l.starts_with("```")).count();
+            assert_eq!(fences % 2, 0, "unbalanced fence in {:?}", chunk);
+        }
+        assert!(chunks[1].starts_with("```rust\n"));
+        assert!(chunks.last().unwrap().ends_with("After"));
+    }
+
+    #[test]
+    fn test_bytes_unit_and_long_lines() {
+        let text = "中".repeat(100);
+        let chunks = split_markdown(&text, 90, LengthUnit::Bytes);
+        assert!(chunks.iter().all(|c| c.len() <= 90));
+        assert_eq!(chunks.concat(), text);
+        assert_eq!(split_markdown(&text, 90, LengthUnit::Chars).len(), 2);
+    }
+
+    #[test]
+    fn test_utf16_unit_counts_emoji_as_two() {
+        let text = format!("{}\n{}", "😀".repeat(30), "a😀".repeat(20));
+        assert_eq!(LengthUnit::Utf16.measure(&text), 60 + 1 + 60);
+        let chunks = split_markdown(&text, 64, LengthUnit::Utf16);
+        assert!(chunks.len() > 1);
+        assert!(chunks.iter().all(|c| LengthUnit::Utf16.measure(c) <= 64), "{:?}", chunks);
+        assert_eq!(chunks.concat().replace('\n', ""), text.replace('\n', ""));
+        // Chars would have let the whole text through as one 81-char "fit"
+        assert_eq!(split_markdown(&text, 81, LengthUnit::Chars).len(), 1);
+        assert_eq!(truncate("a😀b", 2, LengthUnit::Utf16), "a");
+        assert_eq!(truncate("a😀b", 3, LengthUnit::Utf16), "a😀");
+    }
+
+    #[test]
+    fn test_truncate_and_part_label() {
+        assert_eq!(truncate("héllo", 2, LengthUnit::Bytes), "h");
+        assert_eq!(truncate("héllo", 2, LengthUnit::Chars), "hé");
+        assert_eq!(part_label(0, 3), "(1/3)");
+    }
+}
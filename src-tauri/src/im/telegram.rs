@@ -1,6 +1,6 @@
 // Telegram Bot API adapter
-// Handles long-polling, message sending (split + markdown fallback), ACK reactions,
-// MessageCoalescer (fragment merging + debounce), and rate limit handling.
+// Handles long-polling, message sending (Markdown → HTML, split + plain-text fallback), ACK reactions,
+// and rate limit handling. Fragment merging / debounce happens in the shared stage (`coalesce.rs`).
 
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -11,168 +11,34 @@ use serde_json::{json, Value};
 use tokio::sync::{mpsc, Mutex, RwLock};
 use tokio::time::{sleep, Instant};
 
-use super::types::{ImAttachment, ImAttachmentType, ImConfig, ImMessage, ImPlatform, ImSourceType, TelegramError};
-use super::util::{mime_to_ext, sanitize_filename};
-use super::ApprovalCallback;
+use super::outbound::{OutboundLimits, OutboundQueue, Priority};
+use super::split::{part_label, split_markdown, LengthUnit};
+use super::telegram_html::{html_to_plain, markdown_to_telegram_html, split_html, CONTINUATION_RESERVE};
+use super::types::{
+    render_forwarded_from, ImAttachment, ImAttachmentType, ImConfig, ImMessage, ImPlatform, ImRichContent,
+    ImSourceType, MenuOption, TelegramError,
+};
+use super::util::{mime_to_ext, multipart_body, sanitize_filename};
+use super::{ApprovalCallback, ImCallback};
 use crate::{proxy_config, ulog_info, ulog_warn, ulog_error, ulog_debug};
 
+/// Telegram message length limit (UTF-16 code units after entity parsing)
+const MAX_MESSAGE_LEN: usize = 4096;
+/// Telegram document caption limit
+const MAX_CAPTION_LEN: usize = 1024;
+/// Telegram callback_data limit (bytes)
+const CALLBACK_DATA_MAX_LEN: usize = 64;
 /// Telegram long-poll timeout (seconds)
 const LONG_POLL_TIMEOUT: u64 = 30;
 /// Max retries for transient errors before backing off
 const MAX_TRANSIENT_RETRIES: u32 = 3;
+/// Max waits on HTTP 429 before a final-priority send gives up with `RateLimited`
+const MAX_RATE_LIMIT_RETRIES: u32 = 3;
 /// Initial backoff for reconnect (seconds)
 const INITIAL_BACKOFF_SECS: u64 = 1;
 /// Max backoff for reconnect (seconds)
 const MAX_BACKOFF_SECS: u64 = 30;
 
-// MessageCoalescer constants
-const DEFAULT_DEBOUNCE_MS: u64 = 500;
-const DEFAULT_FRAGMENT_MERGE_MS: u64 = 1500;
-const FRAGMENT_MIN_LENGTH: usize = 4000;
-const MAX_FRAGMENTS: usize = 12;
-const MAX_MERGED_LENGTH: usize = 50000;
-
-/// Pending batch of messages being coalesced (only for fragment merging)
-struct PendingBatch {
-    fragments: Vec<String>,
-    total_length: usize,
-    #[allow(dead_code)]
-    first_msg_id: i64,
-    last_msg_id: i64,
-    last_received: Instant,
-    // Preserve sender metadata from the first fragment
-    chat_id: String,
-    sender_id: String,
-    sender_name: Option<String>,
-    source_type: ImSourceType,
-    platform: ImPlatform,
-}
-
-/// Merges fragmented messages (Telegram splits >4096 char pastes)
-/// and debounces rapid consecutive messages from the same chat.
-pub struct MessageCoalescer {
-    pending: HashMap<String, PendingBatch>,
-    debounce_ms: u64,
-    fragment_merge_ms: u64,
-}
-
-impl MessageCoalescer {
-    pub fn new() -> Self {
-        Self {
-            pending: HashMap::new(),
-            debounce_ms: DEFAULT_DEBOUNCE_MS,
-            fragment_merge_ms: DEFAULT_FRAGMENT_MERGE_MS,
-        }
-    }
-
-    /// Push a message. Returns a vec of messages ready to send.
-    ///
-    /// Non-fragment messages (< 4000 chars) are returned immediately — they
-    /// bypass the pending buffer entirely. Only true fragments (Telegram's
-    /// automatic splitting of long pastes, >= 4000 chars each) are buffered
-    /// for merging.
-    ///
-    /// When a new message arrives and there's an existing pending batch,
-    /// the old batch is flushed first, then the new message is either
-    /// buffered (fragment) or returned immediately (non-fragment).
-    pub fn push(&mut self, msg: &ImMessage) -> Vec<ImMessage> {
-        let now = Instant::now();
-        let is_fragment = msg.text.len() >= FRAGMENT_MIN_LENGTH;
-        let chat_id = &msg.chat_id;
-        let msg_id_i64 = msg.message_id.parse::<i64>().unwrap_or(0);
-        let mut ready = Vec::new();
-
-        if let Some(batch) = self.pending.get_mut(chat_id) {
-            let time_since_last = now.duration_since(batch.last_received).as_millis() as u64;
-
-            // Check if this is a continuation fragment
-            let is_continuation = is_fragment
-                && msg_id_i64 == batch.last_msg_id + 1
-                && time_since_last < self.fragment_merge_ms;
-
-            if is_continuation
-                && batch.fragments.len() < MAX_FRAGMENTS
-                && batch.total_length + msg.text.len() < MAX_MERGED_LENGTH
-            {
-                // Append to existing batch
-                batch.total_length += msg.text.len();
-                batch.fragments.push(msg.text.clone());
-                batch.last_msg_id = msg_id_i64;
-                batch.last_received = now;
-                return ready; // Still waiting for more fragments
-            }
-
-            // Not a continuation — flush the old batch
-            if let Some(flushed) = self.flush_batch_to_msg(chat_id) {
-                ready.push(flushed);
-            }
-        }
-
-        if is_fragment {
-            // Buffer: wait for more fragments before sending
-            self.pending.insert(
-                chat_id.to_string(),
-                PendingBatch {
-                    fragments: vec![msg.text.clone()],
-                    total_length: msg.text.len(),
-                    first_msg_id: msg_id_i64,
-                    last_msg_id: msg_id_i64,
-                    last_received: now,
-                    chat_id: msg.chat_id.clone(),
-                    sender_id: msg.sender_id.clone(),
-                    sender_name: msg.sender_name.clone(),
-                    source_type: msg.source_type.clone(),
-                    platform: msg.platform.clone(),
-                },
-            );
-        } else {
-            // Non-fragment: return immediately, no debounce needed
-            ready.push(msg.clone());
-        }
-
-        ready
-    }
-
-    /// Flush all batches that have exceeded the debounce timeout.
-    /// Returns vec of ready-to-send ImMessages with correct sender metadata.
-    pub fn flush_expired(&mut self) -> Vec<ImMessage> {
-        let now = Instant::now();
-
-        let expired_keys: Vec<String> = self
-            .pending
-            .iter()
-            .filter(|(_, batch)| {
-                now.duration_since(batch.last_received).as_millis() as u64 >= self.debounce_ms
-            })
-            .map(|(k, _)| k.clone())
-            .collect();
-
-        let mut ready = Vec::new();
-        for key in expired_keys {
-            if let Some(flushed) = self.flush_batch_to_msg(&key) {
-                ready.push(flushed);
-            }
-        }
-        ready
-    }
-
-    /// Flush a pending batch, reconstructing a full ImMessage with stored metadata.
-    fn flush_batch_to_msg(&mut self, chat_id: &str) -> Option<ImMessage> {
-        self.pending.remove(chat_id).map(|batch| ImMessage {
-            chat_id: batch.chat_id,
-            message_id: batch.last_msg_id.to_string(),
-            text: batch.fragments.join("\n"),
-            sender_id: batch.sender_id,
-            sender_name: batch.sender_name,
-            source_type: batch.source_type,
-            platform: batch.platform,
-            timestamp: chrono::Utc::now(),
-            attachments: Vec::new(),
-            media_group_id: None,
-        })
-    }
-}
-
 /// Telegram Bot API adapter
 pub struct TelegramAdapter {
     bot_token: String,
@@ -180,12 +46,13 @@ pub struct TelegramAdapter {
     allowed_users: Arc<RwLock<Vec<String>>>,
     client: Client,
     message_tx: mpsc::Sender<ImMessage>,
-    coalescer: Arc<Mutex<MessageCoalescer>>,
     bot_username: Arc<Mutex<Option<String>>>,
     /// Channel for forwarding approval callbacks from inline keyboard button clicks
     approval_tx: mpsc::Sender<ApprovalCallback>,
     /// Short ID → (full request_id, created_at) mapping (callback_data has 64 byte limit)
     short_id_map: Arc<Mutex<HashMap<String, (String, Instant)>>>,
+    /// Outbound pacing (Telegram per-bot / per-chat / per-group limits)
+    outbound: OutboundQueue,
 }
 
 impl TelegramAdapter {
@@ -211,10 +78,10 @@ impl TelegramAdapter {
             allowed_users,
             client,
             message_tx,
-            coalescer: Arc::new(Mutex::new(MessageCoalescer::new())),
             bot_username: Arc::new(Mutex::new(None)),
             approval_tx,
             short_id_map: Arc::new(Mutex::new(HashMap::new())),
+            outbound: OutboundQueue::new(OutboundLimits::telegram()),
         }
     }
 
@@ -231,7 +98,32 @@ impl TelegramAdapter {
 
     /// Generic API call with rate limit and error handling
     async fn api_call(&self, method: &str, body: &Value) -> Result<Value, TelegramError> {
+        self.api_call_with(method, body, outbound_priority(method)).await
+    }
+
+    /// API call with explicit outbound priority. Chat-bound calls are paced through
+    /// `self.outbound`; a best-effort call that can't go out right now fails with
+    /// `RateLimited` instead of waiting.
+    async fn api_call_with(
+        &self,
+        method: &str,
+        body: &Value,
+        priority: Option<Priority>,
+    ) -> Result<Value, TelegramError> {
+        let chat_id = match &body["chat_id"] {
+            Value::String(s) => Some(s.clone()),
+            Value::Number(n) => Some(n.to_string()),
+            _ => None,
+        };
+        if let (Some(priority), Some(chat)) = (priority, chat_id.as_deref()) {
+            self.outbound
+                .admit(chat, priority)
+                .await
+                .map_err(|wait| TelegramError::RateLimited(wait.as_secs()))?;
+        }
+
         let mut retries = 0;
+        let mut rate_limit_retries = 0;
 
         loop {
             let resp = self
@@ -262,7 +154,12 @@ impl TelegramAdapter {
                     method,
                     retry_after
                 );
+                self.outbound.backoff(chat_id.as_deref(), Duration::from_secs(retry_after));
+                if priority == Some(Priority::BestEffort) || rate_limit_retries >= MAX_RATE_LIMIT_RETRIES {
+                    return Err(TelegramError::RateLimited(retry_after));
+                }
                 sleep(Duration::from_secs(retry_after)).await;
+                rate_limit_retries += 1;
                 continue;
             }
 
@@ -335,6 +232,9 @@ impl TelegramAdapter {
         let commands = json!({
             "commands": [
                 { "command": "new", "description": "开始新对话" },
+                { "command": "retry", "description": "重新生成上一条回复" },
+                { "command": "sessions", "description": "查看或恢复历史对话" },
+                { "command": "export", "description": "导出对话记录" },
                 { "command": "workspace", "description": "切换工作区 /workspace <path>" },
                 { "command": "model", "description": "查看或切换 AI 模型" },
                 { "command": "provider", "description": "查看或切换 AI 供应商" },
@@ -353,25 +253,29 @@ impl TelegramAdapter {
             "offset": offset,
             "limit": 100,
             "timeout": LONG_POLL_TIMEOUT,
-            "allowed_updates": ["message", "callback_query"]
+            "allowed_updates": ["message", "edited_message", "channel_post", "edited_channel_post", "callback_query"]
         });
         let result = self.api_call("getUpdates", &body).await?;
         Ok(result.as_array().cloned().unwrap_or_default())
     }
 
-    /// Send message with Markdown, auto-split if needed
+    /// Send Markdown as Telegram HTML, auto-split if needed.
+    /// Splits the Markdown first (fences stay balanced), then the rendered HTML of any part
+    /// that markup pushed over the limit. Parts are numbered "(1/3)".
     pub async fn send_message(&self, chat_id: &str, text: &str) -> Result<Option<i64>, TelegramError> {
-        let chunks = split_message(text, 4096);
+        let limit = MAX_MESSAGE_LEN - CONTINUATION_RESERVE;
+        let chunks: Vec<String> = split_markdown(text, limit, LengthUnit::Utf16)
+            .iter()
+            .flat_map(|part| split_html(&markdown_to_telegram_html(part), limit))
+            .collect();
         let total = chunks.len();
         let mut last_message_id = None;
 
         for (i, chunk) in chunks.iter().enumerate() {
             let decorated = if total == 1 {
                 chunk.clone()
-            } else if i < total - 1 {
-                format!("{}\n\n_(continues…)_", chunk)
             } else {
-                format!("_(continued)_\n\n{}", chunk)
+                format!("{}\n\n<i>{}</i>", chunk, part_label(i, total))
             };
 
             last_message_id = Some(self.send_single_message(chat_id, &decorated).await?);
@@ -380,16 +284,75 @@ impl TelegramAdapter {
         Ok(last_message_id)
     }
 
-    /// Send a single message, trying Markdown first then falling back to plain text
-    async fn send_single_message(&self, chat_id: &str, text: &str) -> Result<i64, TelegramError> {
-        // Try Markdown first
+    /// Upload `data` as a document (multipart sendDocument). The Markdown caption goes on
+    /// the document when it fits Telegram's caption limit, otherwise as a message before it.
+    pub async fn send_document(
+        &self,
+        chat_id: &str,
+        file_name: &str,
+        data: &[u8],
+        caption: Option<&str>,
+    ) -> Result<i64, TelegramError> {
+        let mut caption_html = caption.map(markdown_to_telegram_html);
+        if caption_html.as_ref().is_some_and(|c| c.encode_utf16().count() > MAX_CAPTION_LEN) {
+            self.send_message(chat_id, caption.unwrap_or_default()).await?;
+            caption_html = None;
+        }
+
+        let mut fields = vec![("chat_id", chat_id)];
+        if let Some(ref html) = caption_html {
+            fields.push(("caption", html.as_str()));
+            fields.push(("parse_mode", "HTML"));
+        }
+        let mime = if file_name.ends_with(".md") { "text/markdown" } else { "application/octet-stream" };
+        let (content_type, body) = multipart_body(&fields, "document", file_name, mime, data);
+
+        let _ = self.outbound.admit(chat_id, Priority::Final).await;
+        let mut rate_limit_retries = 0;
+        loop {
+            let resp = self
+                .client
+                .post(self.api_url("sendDocument"))
+                .header("Content-Type", content_type.clone())
+                .body(body.clone())
+                .send()
+                .await
+                .map_err(|e| TelegramError::Other(format!("HTTP error: {}", e)))?;
+            let json: Value = resp
+                .json()
+                .await
+                .map_err(|e| TelegramError::Other(format!("JSON parse error: {}", e)))?;
+
+            if json["ok"].as_bool() == Some(true) {
+                return Ok(json["result"]["message_id"].as_i64().unwrap_or(0));
+            }
+            if json["error_code"].as_i64() == Some(429) {
+                let retry_after = json["parameters"]["retry_after"].as_u64().unwrap_or(5);
+                ulog_warn!("[telegram] Rate limited on sendDocument, retry after {}s", retry_after);
+                self.outbound.backoff(Some(chat_id), Duration::from_secs(retry_after));
+                if rate_limit_retries >= MAX_RATE_LIMIT_RETRIES {
+                    return Err(TelegramError::RateLimited(retry_after));
+                }
+                sleep(Duration::from_secs(retry_after)).await;
+                rate_limit_retries += 1;
+                continue;
+            }
+            return Err(TelegramError::Other(format!(
+                "sendDocument failed: {}",
+                json["description"].as_str().unwrap_or("unknown error")
+            )));
+        }
+    }
+
+    /// Send a single pre-rendered HTML chunk, falling back to plain text if Telegram rejects it
+    async fn send_single_message(&self, chat_id: &str, html: &str) -> Result<i64, TelegramError> {
         match self
             .api_call(
                 "sendMessage",
                 &json!({
                     "chat_id": chat_id,
-                    "text": text,
-                    "parse_mode": "Markdown"
+                    "text": html,
+                    "parse_mode": "HTML"
                 }),
             )
             .await
@@ -398,7 +361,7 @@ impl TelegramAdapter {
                 return Ok(result["message_id"].as_i64().unwrap_or(0));
             }
             Err(TelegramError::MarkdownParseError) => {
-                ulog_debug!("[telegram] Markdown parse failed, falling back to plain text");
+                ulog_debug!("[telegram] HTML parse failed, falling back to plain text");
             }
             Err(e) => return Err(e),
         }
@@ -409,7 +372,7 @@ impl TelegramAdapter {
                 "sendMessage",
                 &json!({
                     "chat_id": chat_id,
-                    "text": text
+                    "text": html_to_plain(html)
                 }),
             )
             .await?;
@@ -423,28 +386,49 @@ impl TelegramAdapter {
         message_id: i64,
         text: &str,
     ) -> Result<(), TelegramError> {
+        self.edit_message_with(chat_id, message_id, text, Priority::Final).await
+    }
+
+    /// Render Markdown and edit. A final edit whose rendered HTML no longer fits one
+    /// message fails with `MessageTooLong` (caller re-sends split); a draft shows the
+    /// first chunk.
+    async fn edit_message_with(
+        &self,
+        chat_id: &str,
+        message_id: i64,
+        text: &str,
+        priority: Priority,
+    ) -> Result<(), TelegramError> {
+        let mut chunks = split_html(&markdown_to_telegram_html(text), MAX_MESSAGE_LEN);
+        if chunks.len() > 1 && priority == Priority::Final {
+            return Err(TelegramError::MessageTooLong);
+        }
+        let html = chunks.swap_remove(0);
+
         match self
-            .api_call(
+            .api_call_with(
                 "editMessageText",
                 &json!({
                     "chat_id": chat_id,
                     "message_id": message_id,
-                    "text": text,
-                    "parse_mode": "Markdown"
+                    "text": html,
+                    "parse_mode": "HTML"
                 }),
+                Some(priority),
             )
             .await
         {
             Ok(_) => Ok(()),
             Err(TelegramError::MarkdownParseError) => {
-                // Retry without Markdown
-                self.api_call(
+                // Retry as plain text
+                self.api_call_with(
                     "editMessageText",
                     &json!({
                         "chat_id": chat_id,
                         "message_id": message_id,
-                        "text": text
+                        "text": html_to_plain(&html)
                     }),
+                    Some(priority),
                 )
                 .await?;
                 Ok(())
@@ -628,12 +612,58 @@ impl TelegramAdapter {
         Ok(())
     }
 
+    /// Send a plain-text menu with one inline button per option (`mn:<command>`).
+    pub async fn send_menu(
+        &self,
+        chat_id: &str,
+        text: &str,
+        options: &[MenuOption],
+    ) -> Result<Option<String>, TelegramError> {
+        let keyboard: Vec<Value> = options
+            .iter()
+            .filter_map(|opt| {
+                let data = format!("mn:{}", opt.command);
+                if data.len() > CALLBACK_DATA_MAX_LEN {
+                    ulog_warn!("[telegram] Menu command too long for callback_data: {}", opt.command);
+                    return None;
+                }
+                Some(json!([{ "text": opt.label, "callback_data": data }]))
+            })
+            .collect();
+
+        let result = self.api_call("sendMessage", &json!({
+            "chat_id": chat_id,
+            "text": text,
+            "reply_markup": { "inline_keyboard": keyboard },
+        })).await?;
+        Ok(result["message_id"].as_i64().map(|id| id.to_string()))
+    }
+
+    /// Replace a menu message's text; omitting reply_markup removes the keyboard.
+    pub async fn update_menu(&self, chat_id: &str, message_id: &str, text: &str) -> Result<(), TelegramError> {
+        let mid = message_id.parse::<i64>().unwrap_or(0);
+        match self.api_call("editMessageText", &json!({
+            "chat_id": chat_id,
+            "message_id": mid,
+            "text": text,
+        })).await {
+            Ok(_) | Err(TelegramError::MessageNotModified) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
     /// Process a callback_query update (inline keyboard button click).
-    async fn process_callback_query(&self, update: &Value) -> Option<ApprovalCallback> {
+    /// `pa:<short_id>:<action>` → approval decision; `mn:<command>` → menu selection,
+    /// dispatched as a command message from the clicking user.
+    async fn process_callback_query(&self, update: &Value) -> Option<ImCallback> {
         let cq = update.get("callback_query")?;
         let cq_id = cq["id"].as_str()?;
         let data = cq["data"].as_str()?;
 
+        if let Some(command) = data.strip_prefix("mn:") {
+            return self.process_menu_callback(cq, cq_id, command).await;
+        }
+
         // Parse "pa:<short_id>:<action>"
         let parts: Vec<&str> = data.splitn(3, ':').collect();
         if parts.len() != 3 || parts[0] != "pa" {
@@ -658,7 +688,54 @@ impl TelegramAdapter {
         let user_id = cq["from"]["id"].as_i64().unwrap_or(0).to_string();
 
         ulog_info!("[telegram] Callback query: decision={}, rid={}", decision, &request_id[..request_id.len().min(16)]);
-        Some(ApprovalCallback { request_id, decision, user_id })
+        Some(ImCallback::Approval(ApprovalCallback { request_id, decision, user_id }))
+    }
+
+    /// Turn a menu button click into a command message (whitelist applies as for typed commands).
+    async fn process_menu_callback(&self, cq: &Value, cq_id: &str, command: &str) -> Option<ImCallback> {
+        let from = &cq["from"];
+        let message = &cq["message"];
+        let sender_id = from["id"].as_i64()?;
+        let sender_name = from["username"]
+            .as_str()
+            .or_else(|| from["first_name"].as_str())
+            .map(|s| s.to_string());
+
+        if !self.is_allowed(sender_id, sender_name.as_deref()).await {
+            let _ = self.api_call("answerCallbackQuery", &json!({
+                "callback_query_id": cq_id,
+                "text": "无权限",
+            })).await;
+            return None;
+        }
+        let _ = self.api_call("answerCallbackQuery", &json!({ "callback_query_id": cq_id })).await;
+
+        let chat_id = message["chat"]["id"].as_i64()?.to_string();
+        let menu_id = message["message_id"].as_i64()?.to_string();
+        let source_type = match message["chat"]["type"].as_str().unwrap_or("private") {
+            "group" | "supergroup" => ImSourceType::Group,
+            "channel" => ImSourceType::Channel,
+            _ => ImSourceType::Private,
+        };
+
+        ulog_info!("[telegram] Menu selection: {} (chat {})", command, chat_id);
+        Some(ImCallback::Command(ImMessage {
+            chat_id,
+            message_id: menu_id.clone(),
+            text: command.to_string(),
+            sender_id: sender_id.to_string(),
+            sender_name,
+            source_type,
+            platform: ImPlatform::Telegram,
+            timestamp: chrono::Utc::now(),
+            attachments: Vec::new(),
+            media_group_id: None,
+            menu_message_id: Some(menu_id),
+            edited: false,
+            rich_content: Vec::new(),
+            forwarded_from: None,
+            merged_message_ids: Vec::new(),
+        }))
     }
 
     // ===== Long-polling loop =====
@@ -698,53 +775,40 @@ impl TelegramAdapter {
                         }
 
                         // Handle callback_query (inline keyboard button clicks)
-                        if let Some(cb) = self.process_callback_query(&update).await {
-                            if self.approval_tx.send(cb).await.is_err() {
-                                ulog_error!("[telegram] Approval channel closed");
+                        match self.process_callback_query(&update).await {
+                            Some(ImCallback::Approval(cb)) => {
+                                if self.approval_tx.send(cb).await.is_err() {
+                                    ulog_error!("[telegram] Approval channel closed");
+                                }
+                                continue;
                             }
-                            continue;
-                        }
-
-                        if let Some(msg) = self.process_update(&update).await {
-                            // Push through coalescer — returns messages ready to send
-                            let ready_msgs = {
-                                let mut coalescer = self.coalescer.lock().await;
-                                coalescer.push(&msg)
-                            };
-
-                            for ready_msg in ready_msgs {
-                                ulog_info!(
-                                    "[telegram] Dispatching message from {} (chat {}): {} chars",
-                                    ready_msg.sender_name.as_deref().unwrap_or("?"),
-                                    ready_msg.chat_id,
-                                    ready_msg.text.len(),
-                                );
-                                if self.message_tx.send(ready_msg).await.is_err() {
+                            Some(ImCallback::Command(msg)) => {
+                                if self.message_tx.send(msg).await.is_err() {
                                     ulog_error!("[telegram] Message channel closed");
                                     return;
                                 }
+                                continue;
                             }
+                            None => {}
+                        }
 
-                            // ACK received
-                            if let Ok(mid) = msg.message_id.parse::<i64>() {
-                                self.ack_received(&msg.chat_id, mid).await;
+                        if let Some(msg) = self.process_update(&update).await {
+                            ulog_info!(
+                                "[telegram] Dispatching message from {} (chat {}): {} chars",
+                                msg.sender_name.as_deref().unwrap_or("?"),
+                                msg.chat_id,
+                                msg.text.len(),
+                            );
+                            let (chat_id, message_id) = (msg.chat_id.clone(), msg.message_id.clone());
+                            if self.message_tx.send(msg).await.is_err() {
+                                ulog_error!("[telegram] Message channel closed");
+                                return;
                             }
-                        }
-                    }
 
-                    // Flush any debounce-expired fragment batches
-                    let expired_msgs = {
-                        let mut coalescer = self.coalescer.lock().await;
-                        coalescer.flush_expired()
-                    };
-                    for expired_msg in expired_msgs {
-                        ulog_info!(
-                            "[telegram] Flushing expired fragment batch for chat {}",
-                            expired_msg.chat_id,
-                        );
-                        if self.message_tx.send(expired_msg).await.is_err() {
-                            ulog_error!("[telegram] Message channel closed");
-                            return;
+                            // ACK received
+                            if let Ok(mid) = message_id.parse::<i64>() {
+                                self.ack_received(&chat_id, mid).await;
+                            }
                         }
                     }
                 }
@@ -837,11 +901,22 @@ impl TelegramAdapter {
     }
 
     /// Process a single Telegram update into an ImMessage.
-    /// Handles text, photo, voice, audio, video, document, sticker, location, venue.
+    /// Handles text, photo, voice, audio, video, document, sticker, location, venue, contact
+    /// and poll, in chats and channels (`channel_post`). Edits come through as
+    /// `ImMessage { edited: true, .. }`.
     async fn process_update(&self, update: &Value) -> Option<ImMessage> {
-        let message = update.get("message")?;
+        let (message, edited) = match update.get("message").or_else(|| update.get("channel_post")) {
+            Some(message) => (message, false),
+            None => (
+                update
+                    .get("edited_message")
+                    .or_else(|| update.get("edited_channel_post"))?,
+                true,
+            ),
+        };
         let chat = &message["chat"];
-        let from = &message["from"];
+        // Channel posts have no `from` — the channel itself is the sender
+        let from = message.get("from").or_else(|| message.get("sender_chat"))?;
 
         let chat_id = chat["id"].as_i64()?.to_string();
         let message_id = message["message_id"].as_i64()?.to_string();
@@ -850,12 +925,14 @@ impl TelegramAdapter {
         let sender_name = from["username"]
             .as_str()
             .or_else(|| from["first_name"].as_str())
+            .or_else(|| from["title"].as_str())
             .map(|s| s.to_string());
 
         // Determine source type
         let chat_type = chat["type"].as_str().unwrap_or("private");
         let source_type = match chat_type {
             "group" | "supergroup" => ImSourceType::Group,
+            "channel" => ImSourceType::Channel,
             _ => ImSourceType::Private,
         };
 
@@ -865,8 +942,11 @@ impl TelegramAdapter {
             .or_else(|| message["caption"].as_str())
             .unwrap_or("");
 
-        // Media group ID (album)
-        let media_group_id = message["media_group_id"].as_str().map(String::from);
+        // Media group ID (album). An edit only carries the edited item, so it isn't regrouped.
+        let media_group_id = message["media_group_id"]
+            .as_str()
+            .filter(|_| !edited)
+            .map(String::from);
 
         // ── Collect attachments ──
         let mut attachments: Vec<ImAttachment> = Vec::new();
@@ -1022,24 +1102,16 @@ impl TelegramAdapter {
             }
         }
 
-        // 7. Location / Venue
-        if let Some(venue) = message.get("venue") {
-            let lat = venue["location"]["latitude"].as_f64().unwrap_or(0.0);
-            let lng = venue["location"]["longitude"].as_f64().unwrap_or(0.0);
-            let title = venue["title"].as_str().unwrap_or("");
-            let addr = venue["address"].as_str().unwrap_or("");
-            text_parts.push(format!(
-                "[位置: {}, {} ({:.4}, {:.4})]",
-                title, addr, lat, lng
-            ));
-        } else if let Some(loc) = message.get("location") {
-            let lat = loc["latitude"].as_f64().unwrap_or(0.0);
-            let lng = loc["longitude"].as_f64().unwrap_or(0.0);
-            text_parts.push(format!("[位置: {:.4}, {:.4}]", lat, lng));
-        }
+        // 7. Location / Venue, contact, poll
+        let rich_content = parse_rich_content(message);
+        text_parts.extend(rich_content.iter().map(ImRichContent::render));
 
-        // ── Build final text ──
+        // ── Build final text (a forwarded message is marked with its origin first) ──
+        let forwarded_from = forward_origin(message);
         let mut final_text_parts = Vec::new();
+        if let Some(ref origin) = forwarded_from {
+            final_text_parts.push(render_forwarded_from(origin));
+        }
         if !raw_text.is_empty() {
             final_text_parts.push(raw_text.to_string());
         }
@@ -1064,8 +1136,8 @@ impl TelegramAdapter {
             return None;
         }
 
-        // Group chat: only respond to @Bot, /ask, or bind requests
-        if source_type == ImSourceType::Group && !is_bind_request {
+        // Group chat / channel: only respond to @Bot, /ask, or bind requests
+        if source_type != ImSourceType::Private && !is_bind_request {
             let bot_username = self.bot_username.lock().await;
             let is_mention = bot_username
                 .as_ref()
@@ -1097,6 +1169,11 @@ impl TelegramAdapter {
             timestamp: chrono::Utc::now(),
             attachments,
             media_group_id,
+            menu_message_id: None,
+            edited,
+            rich_content,
+            forwarded_from,
+            merged_message_ids: Vec::new(),
         })
     }
 
@@ -1122,37 +1199,21 @@ impl TelegramAdapter {
     }
 }
 
-/// Split text into chunks respecting max_len, trying to break at paragraph/line boundaries
-pub fn split_message(text: &str, max_len: usize) -> Vec<String> {
-    if text.len() <= max_len {
-        return vec![text.to_string()];
-    }
-
-    let mut chunks = Vec::new();
-    let mut remaining = text;
-
-    while !remaining.is_empty() {
-        if remaining.len() <= max_len {
-            chunks.push(remaining.to_string());
-            break;
-        }
-
-        // Try to find a good break point
-        let search_range = &remaining[..max_len];
-        let break_point = search_range
-            .rfind("\n\n") // Paragraph break
-            .or_else(|| search_range.rfind('\n')) // Line break
-            .or_else(|| search_range.rfind(". ")) // Sentence
-            .or_else(|| search_range.rfind(' ')) // Word
-            .unwrap_or(max_len); // Hard cut
-
-        let break_at = if break_point == 0 { max_len } else { break_point };
-
-        chunks.push(remaining[..break_at].to_string());
-        remaining = remaining[break_at..].trim_start();
+/// Outbound pacing class for a Bot API method (None = not chat-bound, not paced).
+/// Typing and reactions are cosmetic, so they are dropped rather than queued.
+fn outbound_priority(method: &str) -> Option<Priority> {
+    match method {
+        "sendMessage" | "editMessageText" | "editMessageReplyMarkup" | "deleteMessage"
+        | "sendDocument" | "sendPhoto" => Some(Priority::Final),
+        "sendChatAction" | "setMessageReaction" => Some(Priority::BestEffort),
+        _ => None,
     }
+}
 
-    chunks
+/// Split text into chunks respecting max_len (bytes), trying to break at paragraph/line
+/// boundaries without cutting code fences
+pub fn split_message(text: &str, max_len: usize) -> Vec<String> {
+    split_markdown(text, max_len, LengthUnit::Bytes)
 }
 
 /// Clean message text: remove @mention and /ask prefix
@@ -1175,6 +1236,74 @@ fn clean_message_text(text: &str, bot_username: &Option<String>) -> String {
     cleaned.trim().to_string()
 }
 
+/// Location / venue, contact and poll of a message
+fn parse_rich_content(message: &Value) -> Vec<ImRichContent> {
+    let mut content = Vec::new();
+    let non_empty = |v: &Value| v.as_str().filter(|s| !s.is_empty()).map(String::from);
+
+    // A venue also carries a plain `location` — take the venue only
+    let venue = message.get("venue");
+    if let Some(loc) = venue.map(|v| &v["location"]).or_else(|| message.get("location")) {
+        content.push(ImRichContent::Location {
+            latitude: loc["latitude"].as_f64().unwrap_or(0.0),
+            longitude: loc["longitude"].as_f64().unwrap_or(0.0),
+            title: venue.and_then(|v| non_empty(&v["title"])),
+            address: venue.and_then(|v| non_empty(&v["address"])),
+        });
+    }
+
+    if let Some(contact) = message.get("contact") {
+        let name = [&contact["first_name"], &contact["last_name"]]
+            .into_iter()
+            .filter_map(|v| v.as_str())
+            .collect::<Vec<_>>()
+            .join(" ");
+        content.push(ImRichContent::Contact {
+            name,
+            phone: non_empty(&contact["phone_number"]),
+        });
+    }
+
+    if let Some(poll) = message.get("poll") {
+        content.push(ImRichContent::Poll {
+            question: poll["question"].as_str().unwrap_or("").to_string(),
+            options: poll["options"]
+                .as_array()
+                .map(|opts| opts.iter().filter_map(|o| o["text"].as_str().map(String::from)).collect())
+                .unwrap_or_default(),
+        });
+    }
+
+    content
+}
+
+/// Who a forwarded message originally came from: `forward_origin` (Bot API 7.0+),
+/// falling back to the older `forward_from*` fields
+fn forward_origin(message: &Value) -> Option<String> {
+    let user_name = |u: &Value| {
+        u["username"]
+            .as_str()
+            .map(|name| format!("@{}", name))
+            .or_else(|| u["first_name"].as_str().map(String::from))
+    };
+
+    if let Some(origin) = message.get("forward_origin") {
+        return match origin["type"].as_str() {
+            Some("user") => user_name(&origin["sender_user"]),
+            Some("hidden_user") => origin["sender_user_name"].as_str().map(String::from),
+            Some("chat") => origin["sender_chat"]["title"].as_str().map(String::from),
+            Some("channel") => origin["chat"]["title"].as_str().map(String::from),
+            _ => None,
+        }
+        .or_else(|| Some("未知来源".to_string()));
+    }
+    message
+        .get("forward_from")
+        .and_then(user_name)
+        .or_else(|| message["forward_from_chat"]["title"].as_str().map(String::from))
+        .or_else(|| message["forward_sender_name"].as_str().map(String::from))
+}
+
 // ── ImAdapter trait implementation ─────────────────────────
 
 impl super::adapter::ImAdapter for TelegramAdapter {
@@ -1202,6 +1331,19 @@ impl super::adapter::ImAdapter for TelegramAdapter {
             .map_err(|e| e.to_string())
     }
 
+    async fn send_document(
+        &self,
+        chat_id: &str,
+        file_name: &str,
+        data: &[u8],
+        caption: Option<&str>,
+    ) -> super::adapter::AdapterResult<()> {
+        self.send_document(chat_id, file_name, data, caption)
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+
     async fn ack_received(&self, chat_id: &str, message_id: &str) {
         if let Ok(mid) = message_id.parse::<i64>() {
             self.ack_received(chat_id, mid).await;
@@ -1266,8 +1408,60 @@ impl super::adapter::ImStreamAdapter for TelegramAdapter {
             .map_err(|e| e.to_string())
     }
 
+    async fn edit_draft(
+        &self,
+        chat_id: &str,
+        message_id: &str,
+        text: &str,
+    ) -> super::adapter::AdapterResult<bool> {
+        let mid = message_id
+            .parse::<i64>()
+            .map_err(|e| format!("Invalid message_id: {}", e))?;
+        match self.edit_message_with(chat_id, mid, text, Priority::BestEffort).await {
+            Ok(()) => Ok(true),
+            Err(TelegramError::RateLimited(_)) => Ok(false),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
     fn max_message_length(&self) -> usize {
-        4096
+        MAX_MESSAGE_LEN
+    }
+
+    fn length_unit(&self) -> LengthUnit {
+        LengthUnit::Utf16
+    }
+
+    async fn finish_response(
+        &self,
+        chat_id: &str,
+        message_id: &str,
+        text: &str,
+        meta: &super::adapter::ResponseMeta<'_>,
+    ) -> super::adapter::AdapterResult<()> {
+        // Plain messages — only a failover notice or usage footer is worth re-rendering for
+        let mut lines: Vec<String> = Vec::new();
+        if let Some(notice) = meta.notice {
+            lines.push(format!("⚠️ {}", notice));
+        }
+        if let Some(usage) = meta.usage {
+            lines.push(super::usage::format_footer(usage));
+        }
+        if lines.is_empty() {
+            return Ok(());
+        }
+        let mid = message_id
+            .parse::<i64>()
+            .map_err(|e| format!("Invalid message_id: {}", e))?;
+        let footer = lines.iter().map(|l| format!("_{}_", l)).collect::<Vec<_>>().join("\n");
+        if self.edit_message(chat_id, mid, &format!("{}\n\n{}", text, footer)).await.is_ok() {
+            return Ok(());
+        }
+        // Footer pushed the reply over the limit — send it on its own
+        self.send_message(chat_id, &lines.join("\n"))
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string())
     }
 
     async fn send_approval_card(
@@ -1292,6 +1486,28 @@ impl super::adapter::ImStreamAdapter for TelegramAdapter {
             .await
             .map_err(|e| e.to_string())
     }
+
+    async fn send_menu(
+        &self,
+        chat_id: &str,
+        text: &str,
+        options: &[MenuOption],
+    ) -> super::adapter::AdapterResult<Option<String>> {
+        self.send_menu(chat_id, text, options)
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    async fn update_menu(
+        &self,
+        chat_id: &str,
+        message_id: &str,
+        text: &str,
+    ) -> super::adapter::AdapterResult<()> {
+        self.update_menu(chat_id, message_id, text)
+            .await
+            .map_err(|e| e.to_string())
+    }
 }
 
 #[cfg(test)]
@@ -1334,55 +1550,83 @@ mod tests {
         );
     }
 
-    fn make_test_msg(chat_id: &str, msg_id: i64, text: &str) -> ImMessage {
-        ImMessage {
-            chat_id: chat_id.to_string(),
-            message_id: msg_id.to_string(),
-            text: text.to_string(),
-            sender_id: "42".to_string(),
-            sender_name: Some("testuser".to_string()),
-            source_type: ImSourceType::Private,
-            platform: ImPlatform::Telegram,
-            timestamp: chrono::Utc::now(),
-            attachments: Vec::new(),
-            media_group_id: None,
-        }
+    #[test]
+    fn test_parse_rich_content() {
+        let venue = json!({
+            "location": { "latitude": 39.9087, "longitude": 116.4605 },
+            "venue": {
+                "location": { "latitude": 39.9087, "longitude": 116.4605 },
+                "title": "国贸",
+                "address": "建国门外大街1号"
+            }
+        });
+        let content = parse_rich_content(&venue);
+        assert_eq!(content.len(), 1);
+        assert_eq!(content[0].render(), "[位置: 国贸, 建国门外大街1号 (39.9087, 116.4605)]");
+
+        let msg = json!({
+            "contact": { "first_name": "Ada", "last_name": "Lovelace", "phone_number": "+44 20" },
+            "poll": { "question": "午饭?", "options": [{ "text": "面" }, { "text": "饭" }] }
+        });
+        let rendered: Vec<String> = parse_rich_content(&msg).iter().map(|c| c.render()).collect();
+        assert_eq!(rendered, vec!["[联系人: Ada Lovelace, +44 20]", "[投票: 午饭? | 选项: 面 / 饭]"]);
+        assert!(parse_rich_content(&json!({ "text": "hi" })).is_empty());
     }
 
     #[test]
-    fn test_coalescer_single_short_message_immediate() {
-        let mut c = MessageCoalescer::new();
-        // Short message should be returned immediately (not buffered)
-        let msg = make_test_msg("chat1", 1, "hello");
-        let result = c.push(&msg);
-        assert_eq!(result.len(), 1);
-        assert_eq!(result[0].text, "hello");
-        assert_eq!(result[0].sender_id, "42");
-        assert_eq!(result[0].sender_name.as_deref(), Some("testuser"));
+    fn test_forward_origin() {
+        let user = json!({ "forward_origin": { "type": "user", "sender_user": { "first_name": "Bob", "username": "bob" } } });
+        assert_eq!(forward_origin(&user).as_deref(), Some("@bob"));
+        let channel = json!({ "forward_origin": { "type": "channel", "chat": { "title": "News" } } });
+        assert_eq!(forward_origin(&channel).as_deref(), Some("News"));
+        let legacy = json!({ "forward_sender_name": "Hidden" });
+        assert_eq!(forward_origin(&legacy).as_deref(), Some("Hidden"));
+        assert!(forward_origin(&json!({ "text": "hi" })).is_none());
     }
 
-    #[test]
-    fn test_coalescer_fragment_merge() {
-        let mut c = MessageCoalescer::new();
-        let long_text = "a".repeat(4100);
-        // First fragment — buffered, waiting for more
-        let msg1 = make_test_msg("chat1", 1, &long_text);
-        let result = c.push(&msg1);
-        assert!(result.is_empty());
-
-        // Second fragment (continuation: >= 4000 chars, consecutive msg_id)
-        let long_text2 = "b".repeat(4100);
-        let msg2 = make_test_msg("chat1", 2, &long_text2);
-        let result = c.push(&msg2);
-        assert!(result.is_empty()); // Still pending
-
-        // Non-fragment message flushes old batch and is returned immediately
-        let msg3 = make_test_msg("chat1", 100, "new message");
-        let result = c.push(&msg3);
-        assert_eq!(result.len(), 2); // flushed batch + new message
-        assert!(result[0].text.contains("aaa"));
-        assert!(result[0].text.contains("bbb"));
-        assert_eq!(result[0].sender_id, "42"); // sender metadata preserved
-        assert_eq!(result[1].text, "new message");
+    fn test_adapter() -> TelegramAdapter {
+        let (msg_tx, _) = mpsc::channel(1);
+        let (approval_tx, _) = mpsc::channel(1);
+        let allowed = Arc::new(RwLock::new(vec!["7".to_string()]));
+        TelegramAdapter::new(&ImConfig::default(), msg_tx, allowed, approval_tx)
+    }
+
+    fn text_message(message_id: i64, text: &str) -> Value {
+        json!({
+            "message_id": message_id,
+            "date": 1_760_000_000,
+            "chat": { "id": 100, "type": "private" },
+            "from": { "id": 7, "is_bot": false, "first_name": "Ann" },
+            "text": text,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_edited_message_reruns_last_turn() {
+        let adapter = test_adapter();
+        let last = adapter
+            .process_update(&json!({ "update_id": 1, "message": text_message(42, "wrong question") }))
+            .await
+            .unwrap();
+        assert!(!last.edited);
+
+        let mut edited_message = text_message(42, "right question");
+        edited_message["edit_date"] = json!(1_760_000_060);
+        let edit = adapter
+            .process_update(&json!({ "update_id": 2, "edited_message": edited_message }))
+            .await
+            .unwrap();
+        assert!(edit.edited);
+        assert_eq!(edit.message_id, "42");
+
+        let rerun = crate::im::rerun_message(edit, Some(last.clone())).unwrap();
+        assert_eq!(rerun.text, "right question");
+
+        // An edit of an earlier message doesn't re-run anything
+        let old_edit = adapter
+            .process_update(&json!({ "update_id": 3, "edited_message": text_message(41, "older") }))
+            .await
+            .unwrap();
+        assert!(crate::im::rerun_message(old_edit, Some(last)).is_none());
     }
 }
@@ -0,0 +1,511 @@
+// Markdown → Telegram HTML renderer.
+// Telegram's HTML parse mode accepts a small tag set (b, i, s, u, code, pre, a, blockquote)
+// and only the &lt; &gt; &amp; &quot; entities. Anything the agent writes outside that set
+// (headings, lists, tables, raw HTML) is flattened into text Telegram can display.
+// `split_html` chunks rendered output without breaking tags or entities: open tags are
+// closed at the end of a chunk and reopened at the start of the next.
+
+use pulldown_cmark::{CodeBlockKind, Event, Options, Parser, Tag, TagEnd};
+
+/// Room left for the "(1/3)" part label when splitting
+pub const CONTINUATION_RESERVE: usize = 40;
+
+enum ListKind {
+    Unordered,
+    Ordered(u64),
+}
+
+/// Table being collected (rendered as monospace once complete)
+#[derive(Default)]
+struct TableBuf {
+    rows: Vec<Vec<String>>,
+    header_rows: usize,
+    in_head: bool,
+}
+
+/// Escape text for Telegram HTML (also safe inside attribute values)
+pub fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '&' => out.push_str("&amp;"),
+            '"' => out.push_str("&quot;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Convert Markdown to Telegram HTML (parse_mode = "HTML").
+pub fn markdown_to_telegram_html(md: &str) -> String {
+    let mut opts = Options::empty();
+    opts.insert(Options::ENABLE_STRIKETHROUGH);
+    opts.insert(Options::ENABLE_TABLES);
+    opts.insert(Options::ENABLE_TASKLISTS);
+    let parser = Parser::new_ext(md, opts);
+
+    let mut out = String::new();
+    let mut list_stack: Vec<ListKind> = Vec::new();
+    let mut quote_depth = 0usize;
+    let mut in_code_block = false;
+    let mut code_buf = String::new();
+    let mut code_has_lang = false;
+    let mut table: Option<TableBuf> = None;
+    // Links are only emitted when the URL is non-empty; remember which ones opened a tag
+    let mut link_stack: Vec<bool> = Vec::new();
+
+    for event in parser {
+        // Inside a table, only collect plain cell text
+        if let Some(ref mut t) = table {
+            match event {
+                Event::Start(Tag::TableHead) => t.in_head = true,
+                Event::End(TagEnd::TableHead) => {
+                    t.in_head = false;
+                    t.header_rows = t.rows.len();
+                }
+                Event::Start(Tag::TableRow) => t.rows.push(Vec::new()),
+                Event::Start(Tag::TableCell) => {
+                    // Header cells arrive directly under TableHead (no TableRow)
+                    if t.in_head && t.rows.len() == t.header_rows {
+                        t.rows.push(Vec::new());
+                    }
+                    if let Some(row) = t.rows.last_mut() {
+                        row.push(String::new());
+                    }
+                }
+                Event::Text(s) | Event::Code(s) | Event::InlineHtml(s) | Event::Html(s) => {
+                    if let Some(cell) = t.rows.last_mut().and_then(|r| r.last_mut()) {
+                        cell.push_str(&s);
+                    }
+                }
+                Event::SoftBreak | Event::HardBreak => {
+                    if let Some(cell) = t.rows.last_mut().and_then(|r| r.last_mut()) {
+                        cell.push(' ');
+                    }
+                }
+                Event::End(TagEnd::Table) => {
+                    let t = table.take().unwrap_or_default();
+                    out.push_str("<pre>");
+                    out.push_str(&escape_html(&render_table(&t.rows, t.header_rows)));
+                    out.push_str("</pre>");
+                    block_end(&mut out, !list_stack.is_empty());
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        match event {
+            Event::Start(Tag::Strong) => out.push_str("<b>"),
+            Event::End(TagEnd::Strong) => out.push_str("</b>"),
+            Event::Start(Tag::Emphasis) => out.push_str("<i>"),
+            Event::End(TagEnd::Emphasis) => out.push_str("</i>"),
+            Event::Start(Tag::Strikethrough) => out.push_str("<s>"),
+            Event::End(TagEnd::Strikethrough) => out.push_str("</s>"),
+            Event::Start(Tag::Link { dest_url, .. }) | Event::Start(Tag::Image { dest_url, .. }) => {
+                let has_url = !dest_url.is_empty();
+                if has_url {
+                    out.push_str(&format!("<a href=\"{}\">", escape_html(&dest_url)));
+                }
+                link_stack.push(has_url);
+            }
+            Event::End(TagEnd::Link) | Event::End(TagEnd::Image) if link_stack.pop().unwrap_or(false) => {
+                out.push_str("</a>");
+            }
+            Event::Start(Tag::Heading { .. }) => out.push_str("<b>"),
+            Event::End(TagEnd::Heading(_)) => {
+                out.push_str("</b>");
+                block_end(&mut out, !list_stack.is_empty());
+            }
+            Event::Start(Tag::Paragraph) => {}
+            Event::End(TagEnd::Paragraph) => block_end(&mut out, !list_stack.is_empty()),
+            Event::Start(Tag::BlockQuote(_)) => {
+                // Telegram rejects nested blockquotes — only the outermost becomes a tag
+                if quote_depth == 0 {
+                    out.push_str("<blockquote>");
+                }
+                quote_depth += 1;
+            }
+            Event::End(TagEnd::BlockQuote(_)) => {
+                quote_depth = quote_depth.saturating_sub(1);
+                if quote_depth == 0 {
+                    trim_trailing_newlines(&mut out);
+                    out.push_str("</blockquote>");
+                    block_end(&mut out, !list_stack.is_empty());
+                }
+            }
+            Event::Start(Tag::List(start)) => {
+                if !out.is_empty() && !out.ends_with('\n') {
+                    out.push('\n');
+                }
+                list_stack.push(match start {
+                    Some(n) => ListKind::Ordered(n),
+                    None => ListKind::Unordered,
+                });
+            }
+            Event::End(TagEnd::List(_)) => {
+                list_stack.pop();
+                if list_stack.is_empty() {
+                    block_end(&mut out, false);
+                }
+            }
+            Event::Start(Tag::Item) => {
+                if !out.is_empty() && !out.ends_with('\n') {
+                    out.push('\n');
+                }
+                let indent = "  ".repeat(list_stack.len().saturating_sub(1));
+                match list_stack.last_mut() {
+                    Some(ListKind::Ordered(n)) => {
+                        out.push_str(&format!("{}{}. ", indent, n));
+                        *n += 1;
+                    }
+                    _ => out.push_str(&format!("{}• ", indent)),
+                }
+            }
+            Event::End(TagEnd::Item) if !out.ends_with('\n') => out.push('\n'),
+            Event::TaskListMarker(checked) => out.push_str(if checked { "☑ " } else { "☐ " }),
+            Event::Start(Tag::CodeBlock(kind)) => {
+                in_code_block = true;
+                code_buf.clear();
+                let lang = match kind {
+                    CodeBlockKind::Fenced(info) => info.split_whitespace().next().unwrap_or("").to_string(),
+                    CodeBlockKind::Indented => String::new(),
+                };
+                code_has_lang = !lang.is_empty();
+                if lang.is_empty() {
+                    out.push_str("<pre>");
+                } else {
+                    out.push_str(&format!("<pre><code class=\"language-{}\">", escape_html(&lang)));
+                }
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                in_code_block = false;
+                out.push_str(&escape_html(code_buf.trim_end_matches('\n')));
+                out.push_str(if code_has_lang { "</code></pre>" } else { "</pre>" });
+                block_end(&mut out, !list_stack.is_empty());
+            }
+            Event::Start(Tag::Table(_)) => {
+                table = Some(TableBuf::default());
+            }
+            Event::Text(text) => {
+                if in_code_block {
+                    code_buf.push_str(&text);
+                } else {
+                    out.push_str(&escape_html(&text));
+                }
+            }
+            Event::Code(code) => {
+                out.push_str("<code>");
+                out.push_str(&escape_html(&code));
+                out.push_str("</code>");
+            }
+            Event::Html(html) | Event::InlineHtml(html) => {
+                // Raw HTML from the model is shown literally, never interpreted
+                out.push_str(&escape_html(&html));
+            }
+            Event::SoftBreak | Event::HardBreak => {
+                if in_code_block {
+                    code_buf.push('\n');
+                } else {
+                    out.push('\n');
+                }
+            }
+            Event::Rule => {
+                out.push_str("──────────");
+                block_end(&mut out, !list_stack.is_empty());
+            }
+            Event::FootnoteReference(name) => out.push_str(&format!("[{}]", escape_html(&name))),
+            _ => {}
+        }
+    }
+
+    out.trim_end().to_string()
+}
+
+/// Terminate a block: single newline inside lists, blank line otherwise
+fn block_end(out: &mut String, in_list: bool) {
+    trim_trailing_newlines(out);
+    out.push_str(if in_list { "\n" } else { "\n\n" });
+}
+
+fn trim_trailing_newlines(out: &mut String) {
+    while out.ends_with('\n') {
+        out.pop();
+    }
+}
+
+/// Display width in a monospace font (CJK / fullwidth characters take two cells)
+fn display_width(s: &str) -> usize {
+    s.chars()
+        .map(|c| match c as u32 {
+            0x1100..=0x115F | 0x2E80..=0xA4CF | 0xAC00..=0xD7A3 | 0xF900..=0xFAFF
+            | 0xFE30..=0xFE4F | 0xFF00..=0xFF60 | 0xFFE0..=0xFFE6 | 0x1F300..=0x1FAFF => 2,
+            _ => 1,
+        })
+        .sum()
+}
+
+/// Render table rows as aligned plain text (header separated by a rule). Also used for
+/// Feishu post code blocks.
+pub(super) fn render_table(rows: &[Vec<String>], header_rows: usize) -> String {
+    let cols = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+    let mut widths = vec![0usize; cols];
+    for row in rows {
+        for (i, cell) in row.iter().enumerate() {
+            widths[i] = widths[i].max(display_width(cell.trim()));
+        }
+    }
+
+    let mut lines = Vec::with_capacity(rows.len() + 1);
+    for (r, row) in rows.iter().enumerate() {
+        let mut line = String::new();
+        for (i, width) in widths.iter().enumerate() {
+            let cell = row.get(i).map(|c| c.trim()).unwrap_or("");
+            if i > 0 {
+                line.push_str(" │ ");
+            }
+            line.push_str(cell);
+            line.push_str(&" ".repeat(width - display_width(cell)));
+        }
+        lines.push(line.trim_end().to_string());
+        if header_rows > 0 && r + 1 == header_rows {
+            let rule: Vec<String> = widths.iter().map(|w| "─".repeat(*w)).collect();
+            lines.push(rule.join("─┼─"));
+        }
+    }
+    lines.join("\n")
+}
+
+/// One lexical piece of rendered HTML
+enum Token<'a> {
+    /// `<tag ...>` — (tag name, full text)
+    Open(&'a str, &'a str),
+    /// `</tag>`
+    Close(&'a str),
+    /// Text atom: a single char or a whole entity like `&amp;`
+    Atom(&'a str),
+}
+
+fn tokenize(html: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < html.len() {
+        let rest = &html[i..];
+        let len = if rest.starts_with('<') {
+            let end = rest.find('>').map(|p| p + 1).unwrap_or(rest.len());
+            let tag = &rest[..end];
+            if let Some(name) = tag.strip_prefix("</") {
+                tokens.push(Token::Close(name.trim_end_matches('>')));
+            } else {
+                let name_end = tag[1..]
+                    .find([' ', '>'])
+                    .map(|p| p + 1)
+                    .unwrap_or(tag.len());
+                tokens.push(Token::Open(&tag[1..name_end], tag));
+            }
+            end
+        } else if rest.starts_with('&') && rest.find(';').is_some_and(|p| p <= 6) {
+            let end = rest.find(';').map(|p| p + 1).unwrap_or(1);
+            tokens.push(Token::Atom(&rest[..end]));
+            end
+        } else {
+            let c_len = rest.chars().next().map(char::len_utf8).unwrap_or(1);
+            tokens.push(Token::Atom(&rest[..c_len]));
+            c_len
+        };
+        i += len;
+    }
+    tokens
+}
+
+/// Length as Telegram counts it (UTF-16 code units). Measured on the HTML itself,
+/// which over-counts markup — always on the safe side of the limit.
+fn tg_len(s: &str) -> usize {
+    s.encode_utf16().count()
+}
+
+/// Split rendered HTML into chunks of at most `max_len`, preferring line breaks.
+/// Every chunk is well-formed: tags open at a cut are closed and reopened in the next chunk.
+pub fn split_html(html: &str, max_len: usize) -> Vec<String> {
+    if tg_len(html) <= max_len {
+        return vec![html.to_string()];
+    }
+
+    let tokens = tokenize(html);
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    // Open tags: (name, full open tag)
+    let mut stack: Vec<(&str, &str)> = Vec::new();
+    // Last line break in `current`: (byte offset after '\n', open-tag stack at that point)
+    let mut last_break: Option<(usize, Vec<(&str, &str)>)> = None;
+    let mut has_text = false;
+
+    let closing_len = |stack: &[(&str, &str)]| -> usize {
+        stack.iter().map(|(name, _)| name.len() + 3).sum()
+    };
+    let opening_len = |stack: &[(&str, &str)]| -> usize { stack.iter().map(|(_, open)| tg_len(open)).sum() };
+
+    for token in tokens {
+        let piece = match token {
+            Token::Open(_, full) | Token::Atom(full) => full,
+            Token::Close(name) => {
+                current.push_str("</");
+                current.push_str(name);
+                current.push('>');
+                if let Some(pos) = stack.iter().rposition(|(n, _)| *n == name) {
+                    stack.remove(pos);
+                }
+                continue;
+            }
+        };
+        // An opening tag also needs room for its own closing tag
+        let piece_len = match token {
+            Token::Open(name, full) => tg_len(full) + name.len() + 3,
+            _ => tg_len(piece),
+        };
+
+        if has_text && tg_len(&current) + piece_len + closing_len(&stack) > max_len {
+            // Cut at the last line break if the carried-over tail (with its reopened tags)
+            // still leaves room for this piece; otherwise cut right here
+            let (cut, cut_stack) = match last_break.take() {
+                Some((pos, s))
+                    if pos > 0
+                        && opening_len(&s) + tg_len(&current[pos..]) + piece_len + closing_len(&stack)
+                            <= max_len =>
+                {
+                    (pos, s)
+                }
+                _ => (current.len(), stack.clone()),
+            };
+            let tail = current.split_off(cut);
+            let mut chunk = std::mem::take(&mut current);
+            trim_trailing_newlines(&mut chunk);
+            for (name, _) in cut_stack.iter().rev() {
+                chunk.push_str(&format!("</{}>", name));
+            }
+            chunks.push(chunk);
+            for (_, open) in &cut_stack {
+                current.push_str(open);
+            }
+            current.push_str(&tail);
+            has_text = !tail.is_empty();
+        }
+
+        current.push_str(piece);
+        match token {
+            Token::Open(name, full) => stack.push((name, full)),
+            Token::Atom(a) => {
+                has_text = true;
+                if a == "\n" {
+                    last_break = Some((current.len(), stack.clone()));
+                }
+            }
+            Token::Close(_) => {}
+        }
+    }
+
+    if !current.trim().is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+/// Strip tags and decode entities — plain-text fallback when Telegram rejects the HTML
+pub fn html_to_plain(html: &str) -> String {
+    let mut out = String::with_capacity(html.len());
+    for token in tokenize(html) {
+        if let Token::Atom(a) = token {
+            match a {
+                "&lt;" => out.push('<'),
+                "&gt;" => out.push('>'),
+                "&amp;" => out.push('&'),
+                "&quot;" => out.push('"'),
+                _ => out.push_str(a),
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inline_formatting_and_escaping() {
+        let html = markdown_to_telegram_html("**bold** _it_ ~~del~~ `a<b` & [link](https://x.io/?a=1&b=2)");
+        assert_eq!(
+            html,
+            "<b>bold</b> <i>it</i> <s>del</s> <code>a&lt;b</code> &amp; <a href=\"https://x.io/?a=1&amp;b=2\">link</a>"
+        );
+    }
+
+    #[test]
+    fn test_code_block_with_language() {
+        let html = markdown_to_telegram_html("Run:\n\n```rust\nfn main() { a < b }\n```\n\n```\nplain\n```");
+        assert_eq!(
+            html,
+            "Run:\n\n<pre><code class=\"language-rust\">fn main() { a &lt; b }</code></pre>\n\n<pre>plain</pre>"
+        );
+    }
+
+    #[test]
+    fn test_lists_headings_and_raw_html() {
+        let html = markdown_to_telegram_html("# Title\n\n- one\n  1. nested\n- <div>two</div>\n\ntail");
+        assert_eq!(html, "<b>Title</b>\n\n• one\n  1. nested\n• &lt;div&gt;two&lt;/div&gt;\n\ntail");
+    }
+
+    #[test]
+    fn test_table_rendered_as_monospace() {
+        let html = markdown_to_telegram_html("| a | bb |\n|---|----|\n| 中文 | x |");
+        assert_eq!(html, "<pre>a    │ bb\n─────┼───\n中文 │ x</pre>");
+    }
+
+    #[test]
+    fn test_split_never_breaks_tags_or_entities() {
+        let body = "line &amp; more\n".repeat(20);
+        let html = format!("<b>{}</b>", body);
+        let chunks = split_html(&html, 50);
+        assert!(chunks.len() > 1);
+        for chunk in &chunks {
+            assert!(tg_len(chunk) <= 50, "chunk too long: {}", chunk);
+            assert!(chunk.starts_with("<b>") && chunk.ends_with("</b>"), "unbalanced: {}", chunk);
+            assert!(!chunk.contains("&amp</b>"));
+        }
+        assert_eq!(
+            chunks.iter().map(|c| html_to_plain(c)).collect::<Vec<_>>().join("\n").trim_end(),
+            html_to_plain(&html).trim_end()
+        );
+    }
+
+    #[test]
+    fn test_split_deeply_nested_tags_near_limit() {
+        let open = "<b><i><u><s><a href=\"https://example.com/some/long/path\">";
+        let close = "</a></s></u></i></b>";
+        let line = "word <code>c</code> <i><b>x</b></i> tail\n";
+        let html = format!("{}{}{}", open, line.repeat(8), close);
+        // Sweep the limit so a cut lands on every position, including right before a nested open tag
+        for max_len in 100..220 {
+            let chunks = split_html(&html, max_len);
+            assert!(chunks.len() > 1);
+            for chunk in &chunks {
+                assert!(tg_len(chunk) <= max_len, "chunk over {}: {}", max_len, chunk);
+                assert!(chunk.starts_with(open) && chunk.ends_with(close), "unbalanced: {}", chunk);
+            }
+            assert_eq!(
+                chunks.iter().map(|c| html_to_plain(c)).collect::<String>().replace('\n', ""),
+                html_to_plain(&html).replace('\n', "")
+            );
+        }
+    }
+
+    #[test]
+    fn test_split_long_line_without_breaks() {
+        let html = format!("<pre>{}</pre>", "x".repeat(30));
+        let chunks = split_html(&html, 20);
+        assert!(chunks.iter().all(|c| c.starts_with("<pre>") && c.ends_with("</pre>") && tg_len(c) <= 20));
+        assert_eq!(chunks.iter().map(|c| html_to_plain(c)).collect::<String>(), "x".repeat(30));
+    }
+}
@@ -0,0 +1,127 @@
+// Voice transcription for IM audio attachments.
+// `Transcriber` is the backend seam; `HttpTranscriber` speaks the OpenAI-compatible
+// `POST {baseUrl}/audio/transcriptions` multipart API, which local servers (whisper.cpp
+// server, faster-whisper-server, ...) implement as well — point `baseUrl` at them.
+
+use std::future::Future;
+use std::time::Duration;
+
+use reqwest::Client;
+
+use super::types::TranscriptionConfig;
+use super::util::multipart_body;
+use crate::proxy_config;
+
+/// Upload limit of the OpenAI endpoint; larger files are left untranscribed
+pub const MAX_AUDIO_BYTES: usize = 25 * 1024 * 1024;
+const DEFAULT_MODEL: &str = "whisper-1";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Speech-to-text backend
+pub trait Transcriber: Send + Sync + 'static {
+    /// Transcript of one audio file (plain text, may be empty for silence)
+    fn transcribe(
+        &self,
+        file_name: &str,
+        mime_type: &str,
+        data: &[u8],
+    ) -> impl Future<Output = Result<String, String>> + Send;
+}
+
+/// Whether an attachment should go through transcription
+pub fn is_transcribable(mime_type: &str, size: usize) -> bool {
+    mime_type.starts_with("audio/") && size > 0 && size <= MAX_AUDIO_BYTES
+}
+
+/// OpenAI-compatible `/audio/transcriptions` client
+pub struct HttpTranscriber {
+    client: Client,
+    endpoint: String,
+    api_key: Option<String>,
+    model: String,
+    language: Option<String>,
+}
+
+impl HttpTranscriber {
+    /// None when transcription is disabled or has no base URL
+    pub fn from_config(config: &TranscriptionConfig) -> Option<Self> {
+        if !config.enabled || config.base_url.trim().is_empty() {
+            return None;
+        }
+        let client = proxy_config::build_client_with_proxy(Client::builder().timeout(REQUEST_TIMEOUT))
+            .unwrap_or_else(|_| Client::new());
+        Some(Self {
+            client,
+            endpoint: endpoint(&config.base_url),
+            api_key: config.api_key.clone().filter(|k| !k.is_empty()),
+            model: config
+                .model
+                .clone()
+                .filter(|m| !m.is_empty())
+                .unwrap_or_else(|| DEFAULT_MODEL.to_string()),
+            language: config.language.clone().filter(|l| !l.is_empty()),
+        })
+    }
+}
+
+impl Transcriber for HttpTranscriber {
+    async fn transcribe(&self, file_name: &str, mime_type: &str, data: &[u8]) -> Result<String, String> {
+        let mut fields = vec![("model", self.model.as_str()), ("response_format", "json")];
+        if let Some(ref language) = self.language {
+            fields.push(("language", language.as_str()));
+        }
+        let (content_type, body) = multipart_body(&fields, "file", file_name, mime_type, data);
+
+        let mut request = self
+            .client
+            .post(&self.endpoint)
+            .header("Content-Type", content_type)
+            .body(body);
+        if let Some(ref key) = self.api_key {
+            request = request.bearer_auth(key);
+        }
+        let resp = request.send().await.map_err(|e| format!("request failed: {}", e))?;
+        let status = resp.status();
+        let text = resp.text().await.unwrap_or_default();
+        if !status.is_success() {
+            return Err(format!("HTTP {}: {}", status.as_u16(), text.chars().take(200).collect::<String>()));
+        }
+        serde_json::from_str::<serde_json::Value>(&text)
+            .ok()
+            .and_then(|v| v["text"].as_str().map(|t| t.trim().to_string()))
+            .ok_or_else(|| "response has no text".to_string())
+    }
+}
+
+/// "https://api.openai.com/v1/" → "https://api.openai.com/v1/audio/transcriptions"
+fn endpoint(base_url: &str) -> String {
+    format!("{}/audio/transcriptions", base_url.trim().trim_end_matches('/'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_endpoint_and_config() {
+        assert_eq!(endpoint("http://127.0.0.1:8080/v1/"), "http://127.0.0.1:8080/v1/audio/transcriptions");
+
+        let mut config = TranscriptionConfig {
+            enabled: true,
+            base_url: "https://api.openai.com/v1".into(),
+            ..Default::default()
+        };
+        let t = HttpTranscriber::from_config(&config).unwrap();
+        assert_eq!(t.model, DEFAULT_MODEL);
+        assert!(t.api_key.is_none());
+        config.enabled = false;
+        assert!(HttpTranscriber::from_config(&config).is_none());
+    }
+
+    #[test]
+    fn test_is_transcribable() {
+        assert!(is_transcribable("audio/ogg", 10));
+        assert!(!is_transcribable("video/mp4", 10));
+        assert!(!is_transcribable("audio/mpeg", MAX_AUDIO_BYTES + 1));
+    }
+}
@@ -0,0 +1,411 @@
+// Conversation export — a peer's sessions (oldest first) as Markdown or JSONL, for /export
+// and cmd_im_export_conversation. Messages come from the Sidecar's session store (see
+// sessions.rs); tool approvals from the bot's audit log.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use super::types::{AuditAction, AuditEntry, TranscriptExport, TranscriptFormat};
+
+#[derive(Deserialize)]
+struct StoredMessage {
+    #[serde(default)]
+    role: String,
+    #[serde(default)]
+    content: String,
+    #[serde(default)]
+    timestamp: String,
+    #[serde(default)]
+    attachments: Vec<StoredAttachment>,
+    #[serde(default)]
+    metadata: Option<StoredMetadata>,
+}
+
+#[derive(Deserialize)]
+struct StoredAttachment {
+    #[serde(default)]
+    name: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StoredMetadata {
+    #[serde(default)]
+    sender_name: Option<String>,
+}
+
+/// Assistant content block (assistant content is stored as a JSON array of these)
+#[derive(Deserialize)]
+struct StoredBlock {
+    #[serde(rename = "type", default)]
+    kind: String,
+    #[serde(default)]
+    text: Option<String>,
+    #[serde(default)]
+    tool: Option<StoredTool>,
+}
+
+#[derive(Deserialize)]
+struct StoredTool {
+    #[serde(default)]
+    name: String,
+}
+
+/// One entry of an exported conversation (also the JSONL line format)
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum TranscriptItem {
+    #[serde(rename_all = "camelCase")]
+    User {
+        timestamp: String,
+        session_id: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        sender: Option<String>,
+        text: String,
+        /// Attachment names and workspace file references (`@path`)
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        attachments: Vec<String>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Assistant {
+        timestamp: String,
+        session_id: String,
+        text: String,
+        /// Tools invoked during the reply, in order
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        tools: Vec<String>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Approval {
+        timestamp: String,
+        user_id: String,
+        tool_name: String,
+        decision: String,
+    },
+}
+
+impl TranscriptItem {
+    fn timestamp(&self) -> &str {
+        match self {
+            Self::User { timestamp, .. } | Self::Assistant { timestamp, .. } | Self::Approval { timestamp, .. } => {
+                timestamp
+            }
+        }
+    }
+}
+
+/// Workspace files referenced in a user message ("@myagents_files/inbox/...")
+fn file_refs(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split_whitespace()
+        .filter(|w| w.len() > 1 && w.starts_with('@') && w.contains('/'))
+        .map(String::from)
+}
+
+fn parse_message(session_id: &str, message: StoredMessage) -> Option<TranscriptItem> {
+    match message.role.as_str() {
+        "user" => {
+            let mut attachments: Vec<String> = message.attachments.into_iter().map(|a| a.name).collect();
+            attachments.extend(file_refs(&message.content));
+            Some(TranscriptItem::User {
+                timestamp: message.timestamp,
+                session_id: session_id.to_string(),
+                sender: message.metadata.and_then(|m| m.sender_name),
+                text: message.content,
+                attachments,
+            })
+        }
+        "assistant" => {
+            let (text, tools) = match serde_json::from_str::<Vec<StoredBlock>>(&message.content) {
+                Ok(blocks) => {
+                    let text = blocks
+                        .iter()
+                        .filter(|b| b.kind == "text")
+                        .filter_map(|b| b.text.as_deref())
+                        .filter(|t| !t.trim().is_empty())
+                        .collect::<Vec<_>>()
+                        .join("\n\n");
+                    let tools = blocks
+                        .into_iter()
+                        .filter(|b| matches!(b.kind.as_str(), "tool_use" | "server_tool_use"))
+                        .filter_map(|b| b.tool.map(|t| t.name))
+                        .collect();
+                    (text, tools)
+                }
+                // Plain-text content (older sessions)
+                Err(_) => (message.content, Vec::new()),
+            };
+            if text.trim().is_empty() && tools.is_empty() {
+                return None;
+            }
+            Some(TranscriptItem::Assistant {
+                timestamp: message.timestamp,
+                session_id: session_id.to_string(),
+                text,
+                tools,
+            })
+        }
+        _ => None,
+    }
+}
+
+fn approval_item(entry: AuditEntry) -> Option<TranscriptItem> {
+    if entry.action != AuditAction::Approval {
+        return None;
+    }
+    let field = |key: &str| entry.detail[key].as_str().unwrap_or("").to_string();
+    Some(TranscriptItem::Approval {
+        tool_name: field("toolName"),
+        decision: field("decision"),
+        timestamp: entry.timestamp,
+        user_id: entry.user_id,
+    })
+}
+
+/// Entries of `session_ids` (oldest session first) from the session store in `store_dir`,
+/// merged in time order with the approval entries among `audit` (already filtered to the
+/// peer's chat). Approvals before the first message are left out.
+pub fn load(store_dir: &Path, session_ids: &[String], audit: Vec<AuditEntry>) -> Vec<TranscriptItem> {
+    let mut items: Vec<TranscriptItem> = Vec::new();
+    for id in session_ids {
+        let Ok(jsonl) = std::fs::read_to_string(store_dir.join("sessions").join(format!("{}.jsonl", id))) else {
+            continue;
+        };
+        items.extend(
+            jsonl
+                .lines()
+                .filter_map(|line| serde_json::from_str::<StoredMessage>(line).ok())
+                .filter_map(|m| parse_message(id, m)),
+        );
+    }
+    let Some(first) = items.iter().filter_map(|i| parse_time(i.timestamp())).min() else {
+        return items;
+    };
+    items.extend(
+        audit
+            .into_iter()
+            .filter_map(approval_item)
+            .filter(|i| parse_time(i.timestamp()).is_some_and(|t| t >= first)),
+    );
+    // A message without a parsable timestamp sorts with the last timestamp seen before it,
+    // so it stays after the message it followed (stable sort keeps ties in order)
+    let mut last_seen = None;
+    let mut keyed: Vec<_> = items
+        .into_iter()
+        .map(|item| {
+            last_seen = parse_time(item.timestamp()).or(last_seen);
+            (last_seen, item)
+        })
+        .collect();
+    keyed.sort_by_key(|(time, _)| *time);
+    keyed.into_iter().map(|(_, item)| item).collect()
+}
+
+fn parse_time(ts: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    chrono::DateTime::parse_from_rfc3339(ts)
+        .ok()
+        .map(|t| t.with_timezone(&chrono::Utc))
+}
+
+fn decision_label(decision: &str) -> &str {
+    match decision {
+        "allow_once" => "允许",
+        "always_allow" => "始终允许",
+        "deny" => "拒绝",
+        other => other,
+    }
+}
+
+/// Markdown transcript: a heading per session, one section per message
+pub fn render_markdown(title: &str, items: &[TranscriptItem]) -> String {
+    let mut out = format!(
+        "# 对话记录 · {}\n\n导出时间: {}\n",
+        title,
+        chrono::Local::now().format("%Y-%m-%d %H:%M")
+    );
+    let mut current_session: Option<&str> = None;
+    for item in items {
+        match item {
+            TranscriptItem::User { timestamp, session_id, sender, text, attachments } => {
+                if current_session != Some(session_id.as_str()) {
+                    out.push_str(&format!("\n## 会话 {}\n", &session_id[..8.min(session_id.len())]));
+                    current_session = Some(session_id);
+                }
+                out.push_str(&format!(
+                    "\n**👤 {}** · {}\n\n{}\n",
+                    sender.as_deref().unwrap_or("用户"),
+                    super::sessions::format_timestamp(timestamp),
+                    text.trim()
+                ));
+                if !attachments.is_empty() {
+                    out.push_str(&format!("\n📎 附件: {}\n", attachments.join(", ")));
+                }
+            }
+            TranscriptItem::Assistant { timestamp, session_id, text, tools } => {
+                if current_session != Some(session_id.as_str()) {
+                    out.push_str(&format!("\n## 会话 {}\n", &session_id[..8.min(session_id.len())]));
+                    current_session = Some(session_id);
+                }
+                out.push_str(&format!("\n**🤖 Bot** · {}\n", super::sessions::format_timestamp(timestamp)));
+                if !text.trim().is_empty() {
+                    out.push_str(&format!("\n{}\n", text.trim()));
+                }
+                if !tools.is_empty() {
+                    out.push_str(&format!("\n🛠 工具: {}\n", tools.join(", ")));
+                }
+            }
+            TranscriptItem::Approval { timestamp, user_id, tool_name, decision } => {
+                out.push_str(&format!(
+                    "\n> 🔒 工具审批 `{}`: {}（{} · {}）\n",
+                    tool_name,
+                    decision_label(decision),
+                    user_id,
+                    super::sessions::format_timestamp(timestamp)
+                ));
+            }
+        }
+    }
+    out
+}
+
+/// JSONL transcript: one `TranscriptItem` per line
+pub fn render_jsonl(items: &[TranscriptItem]) -> String {
+    items
+        .iter()
+        .filter_map(|i| serde_json::to_string(i).ok())
+        .map(|line| line + "\n")
+        .collect()
+}
+
+/// Render `items` as an export of the chat `chat_id`
+pub fn export(chat_id: &str, items: &[TranscriptItem], format: TranscriptFormat) -> TranscriptExport {
+    let message_count = items
+        .iter()
+        .filter(|i| !matches!(i, TranscriptItem::Approval { .. }))
+        .count();
+    let safe_id: String = chat_id
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    let stamp = chrono::Local::now().format("%Y%m%d-%H%M");
+    let (file_name, content) = match format {
+        TranscriptFormat::Markdown => (
+            format!("conversation-{}-{}.md", safe_id, stamp),
+            render_markdown(chat_id, items),
+        ),
+        TranscriptFormat::Jsonl => (
+            format!("conversation-{}-{}.jsonl", safe_id, stamp),
+            render_jsonl(items),
+        ),
+    };
+    TranscriptExport { file_name, content, message_count }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approval(timestamp: &str, decision: &str) -> AuditEntry {
+        AuditEntry {
+            timestamp: timestamp.to_string(),
+            action: AuditAction::Approval,
+            user_id: "42".to_string(),
+            user_name: None,
+            chat_id: Some("42".to_string()),
+            session_key: None,
+            detail: serde_json::json!({ "toolName": "Bash", "decision": decision }),
+        }
+    }
+
+    #[test]
+    fn test_load_merges_sessions_and_approvals() {
+        let dir = std::env::temp_dir().join(format!("im-transcript-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("sessions")).unwrap();
+        std::fs::write(
+            dir.join("sessions").join("old.jsonl"),
+            concat!(
+                "{\"role\":\"user\",\"content\":\"hi\",\"timestamp\":\"2026-10-17T10:00:00.000Z\"}\n",
+                "{\"role\":\"assistant\",\"content\":\"hello\",\"timestamp\":\"2026-10-17T10:00:05.000Z\"}\n",
+                "{\"role\":\"user\",\"content\":\"no timestamp\"}\n",
+            ),
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("sessions").join("new.jsonl"),
+            concat!(
+                "{\"role\":\"user\",\"content\":\"看下 @myagents_files/inbox/b/2026-10-18/a.pdf\",",
+                "\"timestamp\":\"2026-10-18T10:00:00.000Z\",\"attachments\":[{\"id\":\"1\",\"name\":\"shot.png\",",
+                "\"mimeType\":\"image/png\",\"path\":\"x\"}],\"metadata\":{\"source\":\"telegram_private\",\"senderName\":\"Ann\"}}\n",
+                "{\"role\":\"assistant\",\"content\":\"[{\\\"type\\\":\\\"thinking\\\",\\\"thinking\\\":\\\"...\\\"},",
+                "{\\\"type\\\":\\\"tool_use\\\",\\\"tool\\\":{\\\"name\\\":\\\"Bash\\\"}},",
+                "{\\\"type\\\":\\\"text\\\",\\\"text\\\":\\\"done\\\"}]\",\"timestamp\":\"2026-10-18T10:01:00.000Z\"}\n",
+            ),
+        )
+        .unwrap();
+
+        let audit = vec![
+            approval("2026-10-01T00:00:00+00:00", "deny"),
+            approval("2026-10-18T10:00:30+00:00", "allow_once"),
+        ];
+        let ids = vec!["old".to_string(), "missing".to_string(), "new".to_string()];
+        let items = load(&dir, &ids, audit);
+        std::fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(items.len(), 6);
+        assert!(matches!(&items[0], TranscriptItem::User { text, .. } if text == "hi"));
+        // Sorted with the message before it, not ahead of everything
+        assert!(matches!(&items[2], TranscriptItem::User { text, .. } if text == "no timestamp"));
+        match &items[3] {
+            TranscriptItem::User { sender, attachments, session_id, .. } => {
+                assert_eq!(sender.as_deref(), Some("Ann"));
+                assert_eq!(session_id, "new");
+                assert_eq!(attachments, &vec!["shot.png".to_string(), "@myagents_files/inbox/b/2026-10-18/a.pdf".to_string()]);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(matches!(&items[4], TranscriptItem::Approval { decision, .. } if decision == "allow_once"));
+        assert_eq!(
+            items[5],
+            TranscriptItem::Assistant {
+                timestamp: "2026-10-18T10:01:00.000Z".to_string(),
+                session_id: "new".to_string(),
+                text: "done".to_string(),
+                tools: vec!["Bash".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn test_export_formats() {
+        let items = vec![
+            TranscriptItem::User {
+                timestamp: "2026-10-18T10:00:00Z".to_string(),
+                session_id: "session-1".to_string(),
+                sender: None,
+                text: "hi".to_string(),
+                attachments: Vec::new(),
+            },
+            TranscriptItem::Approval {
+                timestamp: "2026-10-18T10:00:30Z".to_string(),
+                user_id: "42".to_string(),
+                tool_name: "Bash".to_string(),
+                decision: "always_allow".to_string(),
+            },
+        ];
+
+        let md = export("oc_a:b", &items, TranscriptFormat::Markdown);
+        assert!(md.file_name.starts_with("conversation-oc_a_b-") && md.file_name.ends_with(".md"));
+        assert_eq!(md.message_count, 1);
+        assert!(md.content.contains("## 会话 session-"));
+        assert!(md.content.contains("**👤 用户**"));
+        assert!(md.content.contains("`Bash`: 始终允许"));
+
+        let jsonl = export("42", &items, TranscriptFormat::Jsonl);
+        let lines: Vec<serde_json::Value> = jsonl.content.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["kind"], "user");
+        assert_eq!(lines[0]["sessionId"], "session-1");
+        assert!(lines[0].get("attachments").is_none());
+        assert_eq!(lines[1]["toolName"], "Bash");
+    }
+}
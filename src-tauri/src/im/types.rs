@@ -22,6 +22,19 @@ impl std::fmt::Display for ImPlatform {
     }
 }
 
+/// How much tool activity a streamed reply shows before its text arrives (opt-in)
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ActivityMode {
+    /// Only the "🤖 生成中..." placeholder
+    #[default]
+    Off,
+    /// The latest running step: "🔧 Running Bash: npm test (00:42)"
+    Compact,
+    /// Finished steps plus the running ones
+    Verbose,
+}
+
 /// IM Bot operational status
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
@@ -32,12 +45,26 @@ pub enum ImStatus {
     Stopped,
 }
 
-/// IM source type (private chat vs group)
+/// IM source type (private chat, group, or broadcast channel)
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum ImSourceType {
     Private,
     Group,
+    Channel,
+}
+
+/// One button of an inline menu; clicking it runs `command` as if the user had typed it
+#[derive(Debug, Clone)]
+pub struct MenuOption {
+    pub label: String,
+    pub command: String,
+}
+
+impl MenuOption {
+    pub fn new(label: impl Into<String>, command: impl Into<String>) -> Self {
+        Self { label: label.into(), command: command.into() }
+    }
 }
 
 /// Attachment type determines processing path
@@ -58,6 +85,54 @@ pub struct ImAttachment {
     pub attachment_type: ImAttachmentType,
 }
 
+/// Structured content beyond text and files. Adapters keep it on the message and render it
+/// into `ImMessage::text` with `render()`, so every platform prompts the AI the same way.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImRichContent {
+    /// Shared location, or a venue (named place) when title / address are set
+    Location {
+        latitude: f64,
+        longitude: f64,
+        title: Option<String>,
+        address: Option<String>,
+    },
+    /// Shared contact card
+    Contact { name: String, phone: Option<String> },
+    /// Poll (question and option texts)
+    Poll { question: String, options: Vec<String> },
+}
+
+impl ImRichContent {
+    /// One-line prompt form, e.g. `[位置: 国贸, 建国门外大街1号 (39.9087, 116.4605)]`
+    pub fn render(&self) -> String {
+        match self {
+            Self::Location { latitude, longitude, title, address } => {
+                let names: Vec<&str> = [title, address]
+                    .into_iter()
+                    .filter_map(|s| s.as_deref().filter(|s| !s.is_empty()))
+                    .collect();
+                if names.is_empty() {
+                    format!("[位置: {:.4}, {:.4}]", latitude, longitude)
+                } else {
+                    format!("[位置: {} ({:.4}, {:.4})]", names.join(", "), latitude, longitude)
+                }
+            }
+            Self::Contact { name, phone } => match phone {
+                Some(phone) => format!("[联系人: {}, {}]", name, phone),
+                None => format!("[联系人: {}]", name),
+            },
+            Self::Poll { question, options } => {
+                format!("[投票: {} | 选项: {}]", question, options.join(" / "))
+            }
+        }
+    }
+}
+
+/// Prompt line put before forwarded content
+pub fn render_forwarded_from(origin: &str) -> String {
+    format!("[转发自: {}]", origin)
+}
+
 /// Incoming IM message (from adapter)
 #[derive(Debug, Clone)]
 pub struct ImMessage {
@@ -71,14 +146,33 @@ pub struct ImMessage {
     pub timestamp: chrono::DateTime<chrono::Utc>,
     pub attachments: Vec<ImAttachment>,
     pub media_group_id: Option<String>,
+    /// Set when this is a menu button click: the menu message to update in place
+    pub menu_message_id: Option<String>,
+    /// Set when the user edited an earlier message (`message_id` is the edited message)
+    pub edited: bool,
+    /// Locations, contacts and polls in the message (already rendered into `text`)
+    pub rich_content: Vec<ImRichContent>,
+    /// Original author of a forwarded message (already rendered into `text`)
+    pub forwarded_from: Option<String>,
+    /// Other messages the coalescer merged into this one (album items, pictures before the
+    /// question) — acknowledged along with `message_id`
+    pub merged_message_ids: Vec<String>,
 }
 
 impl ImMessage {
+    /// Every user message this one stands for: the merged parts and `message_id`
+    pub fn part_ids(&self) -> Vec<String> {
+        let mut ids = self.merged_message_ids.clone();
+        ids.push(self.message_id.clone());
+        ids
+    }
+
     /// Canonical session key for routing (single source of truth for the format).
     pub fn session_key(&self) -> String {
         let source = match self.source_type {
             ImSourceType::Private => "private",
             ImSourceType::Group => "group",
+            ImSourceType::Channel => "channel",
         };
         format!("im:{}:{}:{}", self.platform, source, self.chat_id)
     }
@@ -109,12 +203,48 @@ pub struct ImConfig {
     pub provider_env_json: Option<String>,
     #[serde(default)]
     pub mcp_servers_json: Option<String>,
-    /// Available providers for /provider command: [{id, name, primaryModel, baseUrl?, authType?, apiKey?}]
+    /// Available providers for /provider and /model: [{id, name, primaryModel, baseUrl?, authType?, apiKey?,
+    /// models?, modelAliases?}] (see `providers.rs`)
     #[serde(default)]
     pub available_providers_json: Option<String>,
     // ===== Heartbeat (v0.1.21) =====
     #[serde(default)]
     pub heartbeat_config: Option<HeartbeatConfig>,
+    // ===== Rate limiting =====
+    #[serde(default)]
+    pub rate_limit: Option<RateLimitConfig>,
+    /// Share of the app-wide turn scheduler relative to other bots (default 1)
+    #[serde(default)]
+    pub scheduler_weight: Option<u32>,
+    /// Feishu: stream replies as interactive cards instead of post messages
+    #[serde(default)]
+    pub feishu_card_streaming: bool,
+    /// Feishu: post a "processing" placeholder card in chats where reactions are unavailable
+    #[serde(default)]
+    pub feishu_processing_card: bool,
+    /// Replies longer than this many characters go out as a short summary plus a `.md`
+    /// document (default 12000; 0 always sends split messages)
+    #[serde(default)]
+    pub document_reply_threshold: Option<u32>,
+    /// Provider IDs (from `available_providers_json`) to fail over to, in order, when the
+    /// current provider returns an auth, quota or server error. Empty disables failover.
+    #[serde(default)]
+    pub fallback_provider_ids: Vec<String>,
+    /// Transcribe voice / audio attachments into the message text
+    #[serde(default)]
+    pub transcription: Option<TranscriptionConfig>,
+    /// Where incoming files are saved in the workspace, and how long they are kept
+    #[serde(default)]
+    pub inbox: Option<InboxConfig>,
+    /// Live tool-activity status line in streamed replies
+    #[serde(default)]
+    pub tool_status: ActivityMode,
+    /// Append token usage and cost to the footer of each reply
+    #[serde(default)]
+    pub usage_footer: bool,
+    /// Enable the /usage chat command (daily and monthly totals)
+    #[serde(default)]
+    pub usage_command: bool,
 }
 
 fn default_platform() -> ImPlatform {
@@ -138,6 +268,17 @@ impl Default for ImConfig {
             mcp_servers_json: None,
             available_providers_json: None,
             heartbeat_config: None,
+            rate_limit: None,
+            scheduler_weight: None,
+            feishu_card_streaming: false,
+            feishu_processing_card: false,
+            document_reply_threshold: None,
+            fallback_provider_ids: Vec::new(),
+            transcription: None,
+            inbox: None,
+            tool_status: ActivityMode::default(),
+            usage_footer: false,
+            usage_command: false,
         }
     }
 }
@@ -152,6 +293,35 @@ pub struct ImActiveSession {
     pub workspace_path: String,
     pub message_count: u32,
     pub last_active: String,
+    /// Settings this chat changed via commands (persisted so they survive restarts)
+    #[serde(default, skip_serializing_if = "ChatOverrides::is_empty")]
+    pub overrides: ChatOverrides,
+    /// Sessions this chat used before, most recent first (for /sessions and /resume)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub previous_session_ids: Vec<String>,
+}
+
+/// Per-chat settings set via /model, /provider, /mode and /workspace.
+/// `None` fields follow the bot config.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChatOverrides {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Provider ID from `available_providers_json`. Its env is looked up on each turn,
+    /// so API keys are never written to the health state file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub permission_mode: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace_path: Option<String>,
+}
+
+impl ChatOverrides {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
 }
 
 /// IM Bot runtime status (returned to frontend)
@@ -170,6 +340,12 @@ pub struct ImBotStatus {
     pub bind_url: Option<String>,
     /// Plain bind code for platforms without deep links (e.g. Feishu)
     pub bind_code: Option<String>,
+    /// Rate limiter counters (rejections since start + turns in flight)
+    #[serde(default)]
+    pub rate_limit: RateLimitCounters,
+    /// Turns waiting for an app-wide scheduler slot
+    #[serde(default)]
+    pub queued_turns: usize,
 }
 
 impl Default for ImBotStatus {
@@ -185,6 +361,8 @@ impl Default for ImBotStatus {
             buffered_messages: 0,
             bind_url: None,
             bind_code: None,
+            rate_limit: RateLimitCounters::default(),
+            queued_turns: 0,
         }
     }
 }
@@ -213,6 +391,9 @@ pub struct PeerSession {
     pub source_id: String,
     pub message_count: u32,
     pub last_active: Instant,
+    pub overrides: ChatOverrides,
+    /// Earlier session IDs of this peer, most recent first
+    pub previous_session_ids: Vec<String>,
 }
 
 /// Buffered message (when Sidecar is unavailable)
@@ -265,6 +446,11 @@ impl BufferedMessage {
                 .unwrap_or_else(|_| chrono::Utc::now()),
             attachments: Vec::new(),
             media_group_id: None,
+            menu_message_id: None,
+            edited: false,
+            rich_content: Vec::new(),
+            forwarded_from: None,
+            merged_message_ids: Vec::new(),
         }
     }
 }
@@ -314,6 +500,258 @@ impl Default for ImHealthState {
     }
 }
 
+// ===== Rate limiting =====
+
+/// Per-sender / per-chat limits. A missing rule (or a missing / zero field) means unlimited.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimitConfig {
+    /// Applied to each sender (user ID) across all chats
+    #[serde(default)]
+    pub per_user: Option<RateLimitRule>,
+    /// Applied to each chat (session key) across all senders
+    #[serde(default)]
+    pub per_chat: Option<RateLimitRule>,
+}
+
+/// Limits for one scope
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimitRule {
+    /// Max messages routed to AI in any rolling 60s window
+    #[serde(default)]
+    pub messages_per_minute: Option<u32>,
+    /// Max turns in flight (queued or streaming) at once
+    #[serde(default)]
+    pub concurrent_turns: Option<u32>,
+    /// Max turns per local calendar day
+    #[serde(default)]
+    pub daily_turns: Option<u32>,
+}
+
+/// Speech-to-text for voice / audio attachments, via an OpenAI-compatible
+/// `/audio/transcriptions` endpoint (OpenAI, or a local whisper server)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptionConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// API base URL, e.g. "https://api.openai.com/v1" or "http://127.0.0.1:8080/v1"
+    #[serde(default)]
+    pub base_url: String,
+    /// Bearer token (optional for local servers)
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// Model name (default "whisper-1")
+    #[serde(default)]
+    pub model: Option<String>,
+    /// ISO-639-1 language hint, e.g. "zh"
+    #[serde(default)]
+    pub language: Option<String>,
+}
+
+/// Attachment inbox: incoming files go to `<workspace>/<dir>/<botId>/<YYYY-MM-DD>/`.
+/// Unset fields use the defaults in `inbox.rs`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InboxConfig {
+    /// Workspace-relative inbox directory (default "myagents_files/inbox")
+    #[serde(default)]
+    pub dir: Option<String>,
+    /// Days to keep inbox files (default 30; 0 keeps them forever)
+    #[serde(default)]
+    pub retention_days: Option<u32>,
+    /// Inbox size limit per workspace in MB; the oldest files are evicted to make room
+    /// (default 1024; 0 is unlimited)
+    #[serde(default)]
+    pub quota_mb: Option<u64>,
+}
+
+/// Disk usage of a bot's inbox in one workspace (`cmd_im_inbox_usage`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InboxUsage {
+    pub workspace_path: String,
+    /// Absolute inbox directory of the bot in this workspace
+    pub inbox_path: String,
+    pub files: u64,
+    pub bytes: u64,
+    /// None when unlimited
+    pub quota_bytes: Option<u64>,
+}
+
+/// Rate limiter counters reported in ImBotStatus
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimitCounters {
+    pub rejected_per_minute: u64,
+    pub rejected_concurrent: u64,
+    pub rejected_daily: u64,
+    /// Turns currently admitted and not yet finished
+    pub active_turns: u32,
+}
+
+// ===== Audit log =====
+
+/// Kind of privileged action recorded in the per-bot audit log
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum AuditAction {
+    /// Tool permission request answered (card button or text command)
+    Approval,
+    /// Permission mode switched via /mode
+    Mode,
+    /// AI provider switched via /provider
+    Provider,
+    /// Workspace switched via /workspace (or moved by /resume)
+    Workspace,
+    /// User bound via QR code / bind code
+    Bind,
+    /// Whitelist changed from Desktop settings
+    Allowlist,
+}
+
+/// Single audit log record (one JSON line in im_{bot_id}_audit.jsonl)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    /// RFC3339 timestamp
+    pub timestamp: String,
+    pub action: AuditAction,
+    /// Platform user ID of the actor ("desktop" for changes made in the app)
+    pub user_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chat_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_key: Option<String>,
+    /// Action-specific fields (e.g. toolName / inputDigest / decision for approvals)
+    #[serde(default)]
+    pub detail: serde_json::Value,
+}
+
+// ===== Usage ledger =====
+
+/// Token usage of one agent turn, as reported by the Sidecar (`usage` of the SSE `complete`
+/// event and of heartbeat / cron responses)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TurnUsage {
+    #[serde(default)]
+    pub input_tokens: u64,
+    #[serde(default)]
+    pub output_tokens: u64,
+    #[serde(default)]
+    pub cache_read_tokens: u64,
+    #[serde(default)]
+    pub cache_creation_tokens: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Cost estimated by the SDK; absent when it wasn't reported
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost_usd: Option<f64>,
+}
+
+/// Kind of run a usage record was produced by
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum UsageSource {
+    Chat,
+    Heartbeat,
+    Cron,
+}
+
+/// Single usage ledger record (one JSON line in im_{bot_id}_usage.jsonl)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageRecord {
+    /// RFC3339 timestamp
+    pub timestamp: String,
+    pub source: UsageSource,
+    /// Peer the turn ran for (chat turns and heartbeats)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_key: Option<String>,
+    /// Platform user ID of the sender (chat turns)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<String>,
+    /// Cron task ID (cron runs)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task_id: Option<String>,
+    #[serde(flatten)]
+    pub usage: TurnUsage,
+}
+
+/// Period usage is totalled by
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum UsagePeriod {
+    #[default]
+    Day,
+    Month,
+}
+
+/// Optional breakdown within each period
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum UsageGroup {
+    Peer,
+    User,
+    Task,
+    Source,
+    Model,
+}
+
+/// Summed usage of a set of records
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageTotals {
+    pub turns: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_read_tokens: u64,
+    pub cache_creation_tokens: u64,
+    /// Sum of the reported costs (turns without one count as 0)
+    pub cost_usd: f64,
+}
+
+/// Usage totals of one period, and of one group within it when grouped (cmd_im_usage)
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageBucket {
+    /// "2026-10-18" (day) or "2026-10" (month), local time
+    pub period: String,
+    /// Group key (peer session key, user ID, task ID, source or model); None when ungrouped
+    /// or the records have no such key
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    #[serde(flatten)]
+    pub totals: UsageTotals,
+}
+
+// ===== Conversation export =====
+
+/// File format of an exported conversation
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TranscriptFormat {
+    #[default]
+    Markdown,
+    /// One JSON object per message / approval
+    Jsonl,
+}
+
+/// Exported conversation of one peer (cmd_im_export_conversation)
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptExport {
+    /// Suggested file name, e.g. "conversation-123456-20261018-1730.md"
+    pub file_name: String,
+    pub content: String,
+    /// User and bot messages included (approvals not counted)
+    pub message_count: usize,
+}
+
 // ===== Heartbeat types (v0.1.21) =====
 
 /// Heartbeat configuration for periodic autonomous checks.
@@ -0,0 +1,345 @@
+// IM Usage ledger — token usage and cost of every agent turn a bot runs.
+// One JSON object per line in ~/.myagents/im_{bot_id}_usage.jsonl (beside the audit log).
+// Chat turns, heartbeats and bot-owned cron tasks are recorded; totals are computed on read.
+
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Datelike, Local, TimeZone};
+
+use super::types::{TurnUsage, UsageBucket, UsageGroup, UsagePeriod, UsageRecord, UsageSource, UsageTotals};
+use crate::ulog_warn;
+
+/// Append-only usage ledger for a single bot
+pub struct UsageLedger {
+    path: PathBuf,
+    /// Serializes appends so concurrent turns never interleave partial lines
+    write_lock: std::sync::Mutex<()>,
+}
+
+impl UsageLedger {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            write_lock: std::sync::Mutex::new(()),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append a record. Failures are logged, never propagated — accounting must not
+    /// break message handling.
+    pub fn record(
+        &self,
+        source: UsageSource,
+        session_key: Option<&str>,
+        user_id: Option<&str>,
+        task_id: Option<&str>,
+        usage: &TurnUsage,
+    ) {
+        let record = UsageRecord {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            source,
+            session_key: session_key.map(String::from),
+            user_id: user_id.map(String::from),
+            task_id: task_id.map(String::from),
+            usage: usage.clone(),
+        };
+        let line = match serde_json::to_string(&record) {
+            Ok(l) => l,
+            Err(e) => {
+                ulog_warn!("[im-usage] Serialize error: {}", e);
+                return;
+            }
+        };
+
+        let _guard = self.write_lock.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(parent) = self.path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        let result = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut f| writeln!(f, "{}", line));
+        if let Err(e) = result {
+            ulog_warn!("[im-usage] Failed to append to {:?}: {}", self.path, e);
+        }
+    }
+}
+
+/// Filter for reading the ledger (all fields optional)
+#[derive(Debug, Default)]
+pub struct UsageFilter {
+    /// Inclusive lower bound
+    pub since: Option<DateTime<chrono::Utc>>,
+    /// Exclusive upper bound
+    pub until: Option<DateTime<chrono::Utc>>,
+    pub session_key: Option<String>,
+}
+
+/// Read records matching `filter`, oldest first. Malformed lines are skipped.
+pub fn read_records(path: &Path, filter: &UsageFilter) -> Result<Vec<UsageRecord>, String> {
+    let file = match std::fs::File::open(path) {
+        Ok(f) => f,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to open usage ledger: {}", e)),
+    };
+
+    let mut records = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(|e| format!("Failed to read usage ledger: {}", e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: UsageRecord = match serde_json::from_str(&line) {
+            Ok(r) => r,
+            Err(_) => continue,
+        };
+        if let Some(ref key) = filter.session_key {
+            if record.session_key.as_ref() != Some(key) {
+                continue;
+            }
+        }
+        if filter.since.is_some() || filter.until.is_some() {
+            let Some(ts) = record_time(&record) else {
+                continue;
+            };
+            let ts = ts.with_timezone(&chrono::Utc);
+            if filter.since.is_some_and(|since| ts < since) {
+                continue;
+            }
+            if filter.until.is_some_and(|until| ts >= until) {
+                continue;
+            }
+        }
+        records.push(record);
+    }
+    Ok(records)
+}
+
+impl UsageTotals {
+    fn add(&mut self, usage: &TurnUsage) {
+        self.turns += 1;
+        self.input_tokens += usage.input_tokens;
+        self.output_tokens += usage.output_tokens;
+        self.cache_read_tokens += usage.cache_read_tokens;
+        self.cache_creation_tokens += usage.cache_creation_tokens;
+        self.cost_usd += usage.cost_usd.unwrap_or(0.0);
+    }
+}
+
+fn record_time(record: &UsageRecord) -> Option<DateTime<Local>> {
+    DateTime::parse_from_rfc3339(&record.timestamp)
+        .ok()
+        .map(|t| t.with_timezone(&Local))
+}
+
+fn period_key(ts: &DateTime<Local>, period: UsagePeriod) -> String {
+    match period {
+        UsagePeriod::Day => ts.format("%Y-%m-%d").to_string(),
+        UsagePeriod::Month => ts.format("%Y-%m").to_string(),
+    }
+}
+
+fn group_key(record: &UsageRecord, group: UsageGroup) -> Option<String> {
+    match group {
+        UsageGroup::Peer => record.session_key.clone(),
+        UsageGroup::User => record.user_id.clone(),
+        UsageGroup::Task => record.task_id.clone(),
+        UsageGroup::Source => serde_json::to_value(record.source)
+            .ok()
+            .and_then(|v| v.as_str().map(String::from)),
+        UsageGroup::Model => record.usage.model.clone(),
+    }
+}
+
+/// Total `records` per local day or month, optionally broken down by `group`.
+/// Buckets are ordered by period, then key.
+pub fn summarize(records: &[UsageRecord], period: UsagePeriod, group: Option<UsageGroup>) -> Vec<UsageBucket> {
+    let mut buckets: BTreeMap<(String, Option<String>), UsageTotals> = BTreeMap::new();
+    for record in records {
+        let Some(ts) = record_time(record) else {
+            continue;
+        };
+        let key = group.and_then(|g| group_key(record, g));
+        buckets
+            .entry((period_key(&ts, period), key))
+            .or_default()
+            .add(&record.usage);
+    }
+    buckets
+        .into_iter()
+        .map(|((period, key), totals)| UsageBucket { period, key, totals })
+        .collect()
+}
+
+/// Start of the local month containing `now` (lower bound for the /usage report)
+pub fn month_start(now: DateTime<Local>) -> DateTime<Local> {
+    Local
+        .with_ymd_and_hms(now.year(), now.month(), 1, 0, 0, 0)
+        .earliest()
+        .unwrap_or(now)
+}
+
+/// "850", "12.3k", "1.25M"
+fn format_tokens(n: u64) -> String {
+    match n {
+        0..=999 => n.to_string(),
+        1_000..=999_999 => format!("{:.1}k", n as f64 / 1_000.0),
+        _ => format!("{:.2}M", n as f64 / 1_000_000.0),
+    }
+}
+
+/// One-line usage summary for a reply footer: "📊 12.3k in · 1.2k out · $0.0123"
+pub fn format_footer(usage: &TurnUsage) -> String {
+    let mut footer = format!(
+        "📊 {} in · {} out",
+        format_tokens(usage.input_tokens + usage.cache_read_tokens + usage.cache_creation_tokens),
+        format_tokens(usage.output_tokens),
+    );
+    if let Some(cost) = usage.cost_usd.filter(|c| *c > 0.0) {
+        footer.push_str(&format!(" · ${:.4}", cost));
+    }
+    footer
+}
+
+fn format_totals(totals: &UsageTotals) -> String {
+    if totals.turns == 0 {
+        return "无".to_string();
+    }
+    let mut line = format!(
+        "{} 轮 · 输入 {} · 输出 {}",
+        totals.turns,
+        format_tokens(totals.input_tokens + totals.cache_read_tokens + totals.cache_creation_tokens),
+        format_tokens(totals.output_tokens),
+    );
+    if totals.cost_usd > 0.0 {
+        line.push_str(&format!(" · ${:.2}", totals.cost_usd));
+    }
+    line
+}
+
+/// /usage reply: today's and this month's totals for the chat and for the whole bot.
+/// `records` should cover at least the current month.
+pub fn format_report(records: &[UsageRecord], session_key: &str, now: DateTime<Local>) -> String {
+    let today = period_key(&now, UsagePeriod::Day);
+    let month = period_key(&now, UsagePeriod::Month);
+    let mut chat = [UsageTotals::default(), UsageTotals::default()];
+    let mut bot = [UsageTotals::default(), UsageTotals::default()];
+    for record in records {
+        let Some(ts) = record_time(record) else {
+            continue;
+        };
+        if period_key(&ts, UsagePeriod::Month) != month {
+            continue;
+        }
+        let is_today = period_key(&ts, UsagePeriod::Day) == today;
+        let is_chat = record.session_key.as_deref() == Some(session_key);
+        bot[1].add(&record.usage);
+        if is_today {
+            bot[0].add(&record.usage);
+        }
+        if is_chat {
+            chat[1].add(&record.usage);
+            if is_today {
+                chat[0].add(&record.usage);
+            }
+        }
+    }
+    format!(
+        "📈 用量统计\n\n本会话\n今日: {}\n本月: {}\n\nBot 合计（含心跳与定时任务）\n今日: {}\n本月: {}",
+        format_totals(&chat[0]),
+        format_totals(&chat[1]),
+        format_totals(&bot[0]),
+        format_totals(&bot[1]),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(timestamp: &str, source: UsageSource, session_key: Option<&str>, input: u64, cost: Option<f64>) -> UsageRecord {
+        UsageRecord {
+            timestamp: timestamp.to_string(),
+            source,
+            session_key: session_key.map(String::from),
+            user_id: None,
+            task_id: None,
+            usage: TurnUsage {
+                input_tokens: input,
+                output_tokens: 10,
+                cost_usd: cost,
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn test_summarize_by_day_and_peer() {
+        // Midday timestamps keep the local day stable across test machine time zones
+        let records = vec![
+            record("2026-10-17T12:00:00Z", UsageSource::Chat, Some("im:telegram:private:1"), 100, Some(0.01)),
+            record("2026-10-17T12:30:00Z", UsageSource::Chat, Some("im:telegram:private:1"), 200, None),
+            record("2026-10-17T13:00:00Z", UsageSource::Heartbeat, Some("im:telegram:private:2"), 50, Some(0.02)),
+            record("2026-10-18T12:00:00Z", UsageSource::Chat, Some("im:telegram:private:1"), 300, Some(0.03)),
+        ];
+
+        let days = summarize(&records, UsagePeriod::Day, None);
+        assert_eq!(days.len(), 2);
+        assert_eq!(days[0].period, "2026-10-17");
+        assert_eq!(days[0].totals.turns, 3);
+        assert_eq!(days[0].totals.input_tokens, 350);
+        assert!((days[0].totals.cost_usd - 0.03).abs() < 1e-9);
+
+        let peers = summarize(&records, UsagePeriod::Month, Some(UsageGroup::Peer));
+        assert_eq!(peers.len(), 2);
+        assert_eq!(peers[0].key.as_deref(), Some("im:telegram:private:1"));
+        assert_eq!(peers[0].totals.input_tokens, 600);
+        assert_eq!(peers[1].totals.turns, 1);
+
+        let sources = summarize(&records, UsagePeriod::Month, Some(UsageGroup::Source));
+        assert_eq!(sources[0].key.as_deref(), Some("chat"));
+        assert_eq!(sources[1].key.as_deref(), Some("heartbeat"));
+    }
+
+    #[test]
+    fn test_record_and_read_back() {
+        let path = std::env::temp_dir().join(format!("im-usage-test-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let ledger = UsageLedger::new(path.clone());
+        let usage = TurnUsage { input_tokens: 5, output_tokens: 7, ..Default::default() };
+        ledger.record(UsageSource::Cron, None, None, Some("task_1"), &usage);
+        ledger.record(UsageSource::Chat, Some("im:feishu:group:oc_1"), Some("ou_1"), None, &usage);
+
+        let all = read_records(&path, &UsageFilter::default()).unwrap();
+        let chat_only = read_records(
+            &path,
+            &UsageFilter { session_key: Some("im:feishu:group:oc_1".into()), ..Default::default() },
+        )
+        .unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].task_id.as_deref(), Some("task_1"));
+        assert_eq!(chat_only.len(), 1);
+        assert_eq!(chat_only[0].user_id.as_deref(), Some("ou_1"));
+    }
+
+    #[test]
+    fn test_format_footer() {
+        let usage = TurnUsage {
+            input_tokens: 800,
+            cache_read_tokens: 11_500,
+            output_tokens: 1_234,
+            cost_usd: Some(0.01234),
+            ..Default::default()
+        };
+        assert_eq!(format_footer(&usage), "📊 12.3k in · 1.2k out · $0.0123");
+        assert_eq!(format_footer(&TurnUsage::default()), "📊 0 in · 0 out");
+    }
+}
@@ -47,3 +47,35 @@ pub(super) fn sanitize_filename(name: &str) -> String {
         cleaned.to_string()
     }
 }
+
+/// Build a multipart/form-data body with text fields followed by one file part.
+/// Returns (Content-Type header value, body). reqwest is built without its multipart feature.
+pub(super) fn multipart_body(
+    fields: &[(&str, &str)],
+    file_field: &str,
+    file_name: &str,
+    mime: &str,
+    data: &[u8],
+) -> (String, Vec<u8>) {
+    let boundary = format!("----myagents-{}", uuid::Uuid::new_v4().simple());
+    let mut body = Vec::with_capacity(data.len() + 256 * (fields.len() + 1));
+    for (name, value) in fields {
+        body.extend_from_slice(
+            format!("--{}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n", boundary, name, value)
+                .as_bytes(),
+        );
+    }
+    body.extend_from_slice(
+        format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\nContent-Type: {}\r\n\r\n",
+            boundary,
+            file_field,
+            sanitize_filename(file_name).replace('"', "_"),
+            mime
+        )
+        .as_bytes(),
+    );
+    body.extend_from_slice(data);
+    body.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+    (format!("multipart/form-data; boundary={}", boundary), body)
+}
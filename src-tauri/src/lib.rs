@@ -10,6 +10,7 @@ mod proxy_config;
 mod sidecar;
 mod sse_proxy;
 mod tray;
+pub mod turn_scheduler;
 mod updater;
 
 use sidecar::{
@@ -152,6 +153,10 @@ pub fn run() {
             im::cmd_im_bot_status,
             im::cmd_im_all_bots_status,
             im::cmd_im_conversations,
+            im::cmd_im_audit_log,
+            im::cmd_im_usage,
+            im::cmd_im_export_conversation,
+            im::cmd_im_inbox_usage,
             im::cmd_update_heartbeat_config,
             // IM Bot hot-update commands
             im::cmd_update_im_bot_ai_config,
@@ -159,6 +164,9 @@ pub fn run() {
             im::cmd_update_im_bot_mcp_servers,
             im::cmd_update_im_bot_allowed_users,
             im::cmd_update_im_bot_workspace,
+            // Turn scheduler (shared by IM bots, heartbeats, cron)
+            turn_scheduler::cmd_update_turn_scheduler,
+            turn_scheduler::cmd_turn_scheduler_status,
         ])
         .setup(|app| {
             // Initialize logging for all builds
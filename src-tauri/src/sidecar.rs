@@ -2633,6 +2633,9 @@ pub struct CronExecuteResponse {
     pub ai_requested_exit: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub exit_reason: Option<String>,
+    /// Token usage of the run (recorded in the usage ledger of bot-owned tasks)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<crate::im::types::TurnUsage>,
 }
 
 /// Execute a cron task synchronously via Sidecar HTTP API
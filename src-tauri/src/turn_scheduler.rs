@@ -0,0 +1,444 @@
+// App-wide turn scheduler — bounds concurrent AI turns across all IM bots, heartbeats
+// and cron executions, with weighted fairness between owners and round-robin between peers.
+//
+// Owners are bot IDs (IM messages + heartbeats) or "cron" for cron tasks without a
+// source bot. Within an owner, each peer (session key / task ID) gets one turn at a time
+// in round-robin order, so a chatty chat cannot starve its neighbours.
+//
+// Between owners we use stride scheduling: every granted turn advances the owner's
+// `pass` by STRIDE / weight, and the waiting owner with the smallest pass goes next.
+// An owner that was idle re-enters at the current virtual time, so it can't bank credit.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
+
+use crate::{ulog_debug, ulog_info};
+
+/// Default max concurrent AI turns across the whole app
+pub const DEFAULT_CAPACITY: usize = 8;
+/// Owner key for cron executions that don't belong to an IM bot
+pub const CRON_OWNER: &str = "cron";
+/// Stride numerator (large so integer division keeps precision for weights up to 1000)
+const STRIDE: u64 = 1 << 20;
+
+struct Waiter {
+    id: u64,
+    tx: oneshot::Sender<SchedulerPermit>,
+}
+
+#[derive(Default)]
+struct OwnerQueue {
+    /// Peers with waiters, in round-robin order
+    peers: VecDeque<String>,
+    waiters: HashMap<String, VecDeque<Waiter>>,
+    queued: usize,
+    running: usize,
+    pass: u64,
+}
+
+struct SchedulerState {
+    capacity: usize,
+    running: usize,
+    weights: HashMap<String, u32>,
+    owners: HashMap<String, OwnerQueue>,
+    /// Pass of the most recently served owner (virtual time)
+    virtual_time: u64,
+    next_waiter_id: u64,
+}
+
+impl SchedulerState {
+    fn weight(&self, owner: &str) -> u64 {
+        self.weights.get(owner).copied().unwrap_or(1).max(1) as u64
+    }
+
+    fn total_queued(&self) -> usize {
+        self.owners.values().map(|o| o.queued).sum()
+    }
+
+    /// Account a granted turn against `owner`
+    fn charge(&mut self, owner: &str) {
+        let stride = STRIDE / self.weight(owner);
+        let queue = self.owners.entry(owner.to_string()).or_default();
+        queue.running += 1;
+        queue.pass = queue.pass.max(self.virtual_time) + stride;
+        self.virtual_time = self.virtual_time.max(queue.pass - stride);
+        self.running += 1;
+    }
+
+    /// Pop the next waiter: lowest-pass owner, then round-robin peer within it
+    fn pop_next(&mut self) -> Option<(String, Waiter)> {
+        let owner = self
+            .owners
+            .iter()
+            .filter(|(_, q)| q.queued > 0)
+            .min_by_key(|(_, q)| q.pass)
+            .map(|(k, _)| k.clone())?;
+        let queue = self.owners.get_mut(&owner)?;
+        let peer = queue.peers.pop_front()?;
+        let peer_waiters = queue.waiters.get_mut(&peer)?;
+        let waiter = peer_waiters.pop_front()?;
+        if peer_waiters.is_empty() {
+            queue.waiters.remove(&peer);
+        } else {
+            queue.peers.push_back(peer);
+        }
+        queue.queued -= 1;
+        Some((owner, waiter))
+    }
+
+    fn remove_waiter(&mut self, owner: &str, peer: &str, id: u64) {
+        let Some(queue) = self.owners.get_mut(owner) else { return };
+        let Some(peer_waiters) = queue.waiters.get_mut(peer) else { return };
+        let before = peer_waiters.len();
+        peer_waiters.retain(|w| w.id != id);
+        if peer_waiters.len() < before {
+            queue.queued -= 1;
+        }
+        if peer_waiters.is_empty() {
+            queue.waiters.remove(peer);
+            queue.peers.retain(|p| p != peer);
+        }
+    }
+}
+
+/// Snapshot for status display
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TurnSchedulerStatus {
+    pub capacity: usize,
+    pub running: usize,
+    pub queued: usize,
+    pub weights: HashMap<String, u32>,
+}
+
+/// Shared, bounded turn scheduler (cheap to clone)
+#[derive(Clone)]
+pub struct TurnScheduler {
+    state: Arc<Mutex<SchedulerState>>,
+}
+
+/// One running turn. Dropping it frees the slot and wakes the next waiter.
+pub struct SchedulerPermit {
+    state: Arc<Mutex<SchedulerState>>,
+    owner: String,
+    armed: bool,
+}
+
+impl Drop for SchedulerPermit {
+    fn drop(&mut self) {
+        if !self.armed {
+            return;
+        }
+        let scheduler = TurnScheduler {
+            state: Arc::clone(&self.state),
+        };
+        scheduler.release(&self.owner);
+    }
+}
+
+/// Removes a waiter from the queue if `acquire` is cancelled before being granted
+struct CancelGuard<'a> {
+    scheduler: &'a TurnScheduler,
+    owner: &'a str,
+    peer: &'a str,
+    id: u64,
+    done: bool,
+}
+
+impl Drop for CancelGuard<'_> {
+    fn drop(&mut self) {
+        if !self.done {
+            let mut state = self.scheduler.lock();
+            state.remove_waiter(self.owner, self.peer, self.id);
+        }
+    }
+}
+
+impl TurnScheduler {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(SchedulerState {
+                capacity: capacity.max(1),
+                running: 0,
+                weights: HashMap::new(),
+                owners: HashMap::new(),
+                virtual_time: 0,
+                next_waiter_id: 0,
+            })),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, SchedulerState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Wait for a turn slot. `owner` is the bot ID (or CRON_OWNER), `peer` the
+    /// session key / task ID used for round-robin within the owner.
+    pub async fn acquire(&self, owner: &str, peer: &str) -> SchedulerPermit {
+        loop {
+            if let Some(permit) = self.acquire_once(owner, peer).await {
+                return permit;
+            }
+        }
+    }
+
+    /// Single queueing attempt; `None` only if our sender was dropped without a permit
+    async fn acquire_once(&self, owner: &str, peer: &str) -> Option<SchedulerPermit> {
+        let (id, rx) = {
+            let mut state = self.lock();
+            if state.running < state.capacity && state.total_queued() == 0 {
+                state.charge(owner);
+                return Some(SchedulerPermit {
+                    state: Arc::clone(&self.state),
+                    owner: owner.to_string(),
+                    armed: true,
+                });
+            }
+
+            let id = state.next_waiter_id;
+            state.next_waiter_id += 1;
+            let (tx, rx) = oneshot::channel();
+            let virtual_time = state.virtual_time;
+            let queue = state.owners.entry(owner.to_string()).or_default();
+            if queue.queued == 0 && queue.running == 0 {
+                // Re-entering after idle: start from current virtual time
+                queue.pass = queue.pass.max(virtual_time);
+            }
+            let peer_waiters = queue.waiters.entry(peer.to_string()).or_default();
+            if peer_waiters.is_empty() {
+                queue.peers.push_back(peer.to_string());
+            }
+            peer_waiters.push_back(Waiter { id, tx });
+            queue.queued += 1;
+            ulog_debug!(
+                "[turn-scheduler] Queued turn for {} / {} ({} running, {} queued)",
+                owner,
+                peer,
+                state.running,
+                state.total_queued()
+            );
+            (id, rx)
+        };
+
+        let mut guard = CancelGuard {
+            scheduler: self,
+            owner,
+            peer,
+            id,
+            done: false,
+        };
+        let result = rx.await.ok();
+        // Granted: the waiter was already popped. Not granted: the guard dequeues it.
+        guard.done = result.is_some();
+        result
+    }
+
+    fn release(&self, owner: &str) {
+        let mut state = self.lock();
+        state.running = state.running.saturating_sub(1);
+        if let Some(queue) = state.owners.get_mut(owner) {
+            queue.running = queue.running.saturating_sub(1);
+            if queue.running == 0 && queue.queued == 0 {
+                state.owners.remove(owner);
+            }
+        }
+        self.dispatch(&mut state);
+    }
+
+    /// Hand free slots to waiters (caller holds the lock)
+    fn dispatch(&self, state: &mut SchedulerState) {
+        while state.running < state.capacity {
+            let Some((owner, waiter)) = state.pop_next() else { break };
+            state.charge(&owner);
+            let permit = SchedulerPermit {
+                state: Arc::clone(&self.state),
+                owner: owner.clone(),
+                armed: true,
+            };
+            if let Err(mut permit) = waiter.tx.send(permit) {
+                // Waiter was cancelled between queueing and now — undo without re-locking
+                permit.armed = false;
+                state.running -= 1;
+                if let Some(queue) = state.owners.get_mut(&owner) {
+                    queue.running -= 1;
+                }
+            }
+        }
+    }
+
+    /// Change total capacity (takes effect immediately for queued turns)
+    pub fn set_capacity(&self, capacity: usize) {
+        let mut state = self.lock();
+        state.capacity = capacity.max(1);
+        self.dispatch(&mut state);
+    }
+
+    /// Set the fairness weight for an owner (default 1; higher = larger share)
+    pub fn set_weight(&self, owner: &str, weight: u32) {
+        let mut state = self.lock();
+        if weight <= 1 {
+            state.weights.remove(owner);
+        } else {
+            state.weights.insert(owner.to_string(), weight);
+        }
+    }
+
+    /// Forget an owner's weight (bot stopped or deleted)
+    pub fn clear_weight(&self, owner: &str) {
+        self.lock().weights.remove(owner);
+    }
+
+    /// Turns waiting for a slot for this owner
+    pub fn queue_depth(&self, owner: &str) -> usize {
+        self.lock().owners.get(owner).map(|q| q.queued).unwrap_or(0)
+    }
+
+    pub fn status(&self) -> TurnSchedulerStatus {
+        let state = self.lock();
+        TurnSchedulerStatus {
+            capacity: state.capacity,
+            running: state.running,
+            queued: state.total_queued(),
+            weights: state.weights.clone(),
+        }
+    }
+}
+
+/// Global singleton instance
+static TURN_SCHEDULER: std::sync::OnceLock<TurnScheduler> = std::sync::OnceLock::new();
+
+/// Partial app config for reading the scheduler capacity
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct PartialAppConfig {
+    max_concurrent_turns: Option<usize>,
+}
+
+/// Read `maxConcurrentTurns` from ~/.myagents/config.json (DEFAULT_CAPACITY if unset or invalid)
+fn read_configured_capacity() -> usize {
+    let configured = dirs::home_dir()
+        .map(|home| home.join(".myagents").join("config.json"))
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str::<PartialAppConfig>(&content).ok())
+        .and_then(|config| config.max_concurrent_turns)
+        .filter(|c| *c > 0);
+    match configured {
+        Some(c) => {
+            ulog_info!("[turn-scheduler] maxConcurrentTurns from config: {}", c);
+            c
+        }
+        None => DEFAULT_CAPACITY,
+    }
+}
+
+/// Get the app-wide TurnScheduler
+pub fn get_turn_scheduler() -> &'static TurnScheduler {
+    TURN_SCHEDULER.get_or_init(|| TurnScheduler::new(read_configured_capacity()))
+}
+
+// ============ Tauri Commands ============
+
+/// Update scheduler capacity and/or per-bot weights.
+/// Capacity is persisted by the frontend as `maxConcurrentTurns` in config.json;
+/// this applies it to the running scheduler.
+/// `botWeights` replaces the weight of each listed owner (weight ≤ 1 resets to default).
+#[tauri::command]
+#[allow(non_snake_case)]
+pub async fn cmd_update_turn_scheduler(
+    capacity: Option<usize>,
+    botWeights: Option<HashMap<String, u32>>,
+) -> Result<TurnSchedulerStatus, String> {
+    let scheduler = get_turn_scheduler();
+    if let Some(c) = capacity {
+        if c == 0 {
+            return Err("Capacity must be at least 1".to_string());
+        }
+        scheduler.set_capacity(c);
+    }
+    if let Some(weights) = botWeights {
+        for (owner, weight) in weights {
+            scheduler.set_weight(&owner, weight);
+        }
+    }
+    Ok(scheduler.status())
+}
+
+/// Current scheduler capacity, usage and weights
+#[tauri::command]
+pub async fn cmd_turn_scheduler_status() -> Result<TurnSchedulerStatus, String> {
+    Ok(get_turn_scheduler().status())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    async fn settle() {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    #[tokio::test]
+    async fn test_capacity_bounds_running_turns() {
+        let scheduler = TurnScheduler::new(1);
+        let first = scheduler.acquire("bot", "a").await;
+        let s2 = scheduler.clone();
+        let waiting = tokio::spawn(async move { s2.acquire("bot", "b").await });
+        settle().await;
+        assert_eq!(scheduler.queue_depth("bot"), 1);
+        drop(first);
+        let second = waiting.await.unwrap();
+        assert_eq!(scheduler.status().running, 1);
+        drop(second);
+        assert_eq!(scheduler.status().running, 0);
+    }
+
+    #[tokio::test]
+    async fn test_round_robin_across_peers() {
+        let scheduler = TurnScheduler::new(1);
+        let blocker = scheduler.acquire("bot", "x").await;
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let mut handles = Vec::new();
+        // Peer "a" queues two turns before "b" queues one: b must run second
+        for peer in ["a", "a", "b"] {
+            let s = scheduler.clone();
+            let order = Arc::clone(&order);
+            handles.push(tokio::spawn(async move {
+                let _permit = s.acquire("bot", peer).await;
+                order.lock().unwrap().push(peer);
+            }));
+            settle().await;
+        }
+        drop(blocker);
+        for h in handles {
+            h.await.unwrap();
+        }
+        assert_eq!(*order.lock().unwrap(), vec!["a", "b", "a"]);
+    }
+
+    #[test]
+    fn test_clear_weight_forgets_owner() {
+        let scheduler = TurnScheduler::new(1);
+        scheduler.set_weight("bot", 3);
+        assert_eq!(scheduler.status().weights.get("bot"), Some(&3));
+        scheduler.clear_weight("bot");
+        assert!(scheduler.status().weights.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_waiter_is_dequeued() {
+        let scheduler = TurnScheduler::new(1);
+        let blocker = scheduler.acquire("bot", "a").await;
+        let s2 = scheduler.clone();
+        let waiting = tokio::spawn(async move { s2.acquire("bot", "b").await });
+        settle().await;
+        waiting.abort();
+        let _ = waiting.await;
+        assert_eq!(scheduler.queue_depth("bot"), 0);
+        drop(blocker);
+        assert_eq!(scheduler.status().running, 0);
+    }
+}
@@ -18,7 +18,16 @@ import BindCodePanel from './components/BindCodePanel';
 import AiConfigCard from './components/AiConfigCard';
 import McpToolsCard from './components/McpToolsCard';
 import HeartbeatConfigCard from './components/HeartbeatConfigCard';
-import type { ImBotConfig, ImBotStatus } from '../../../shared/types/im';
+import TranscriptionConfigCard from './components/TranscriptionConfigCard';
+import InboxConfigCard from './components/InboxConfigCard';
+import UsageCard from './components/UsageCard';
+import type { ImBotConfig, ImBotStatus, ImToolStatusMode } from '../../../shared/types/im';
+
+const TOOL_STATUS_OPTIONS: { value: ImToolStatusMode; label: string }[] = [
+    { value: 'off', label: '关闭' },
+    { value: 'compact', label: '简洁' },
+    { value: 'verbose', label: '详细' },
+];
 
 export default function ImBotDetail({
     botId,
@@ -191,6 +200,8 @@ export default function ImBotDetail({
                 baseUrl: p.config.baseUrl,
                 authType: p.authType,
                 apiKey: p.type !== 'subscription' ? apiKeys[p.id] : undefined,
+                models: p.models?.map(m => ({ model: m.model, modelName: m.modelName })),
+                modelAliases: p.modelAliases,
             }));
 
         const allServers = await getAllMcpServers();
@@ -214,6 +225,17 @@ export default function ImBotDetail({
             feishuAppId: cfg.feishuAppId || null,
             feishuAppSecret: cfg.feishuAppSecret || null,
             heartbeatConfigJson: cfg.heartbeat ? JSON.stringify(cfg.heartbeat) : null,
+            rateLimitJson: cfg.rateLimit ? JSON.stringify(cfg.rateLimit) : null,
+            schedulerWeight: cfg.schedulerWeight ?? null,
+            feishuCardStreaming: cfg.feishuCardStreaming ?? null,
+            feishuProcessingCard: cfg.feishuProcessingCard ?? null,
+            documentReplyThreshold: cfg.documentReplyThreshold ?? null,
+            fallbackProviderIds: cfg.fallbackProviderIds ?? null,
+            transcriptionJson: cfg.transcription ? JSON.stringify(cfg.transcription) : null,
+            inboxJson: cfg.inbox ? JSON.stringify(cfg.inbox) : null,
+            toolStatus: cfg.toolStatus ?? null,
+            usageFooter: cfg.usageFooter ?? null,
+            usageCommand: cfg.usageCommand ?? null,
         };
     }, [providers, apiKeys]);
 
@@ -307,6 +329,14 @@ export default function ImBotDetail({
         return options;
     }, [providers, apiKeys]);
 
+    // Providers that can back up the bot's own provider (same availability rule as /provider)
+    const fallbackCandidates = useMemo(
+        () => providers.filter(p =>
+            p.id !== (botConfig?.providerId || 'anthropic-sub')
+            && (p.type === 'subscription' || (p.type === 'api' && apiKeys[p.id]))),
+        [providers, apiKeys, botConfig?.providerId],
+    );
+
     const selectedProvider = useMemo(
         () => providers.find(p => p.id === (botConfig?.providerId || 'anthropic-sub')),
         [providers, botConfig?.providerId],
@@ -418,21 +448,61 @@ export default function ImBotDetail({
                 {isCredentialsExpanded && (
                     <div className="px-5 pb-5">
                         {botConfig.platform === 'feishu' ? (
-                            <FeishuCredentialInput
-                                appId={botConfig.feishuAppId ?? ''}
-                                appSecret={botConfig.feishuAppSecret ?? ''}
-                                onAppIdChange={(appId) => {
-                                    const others = (config.imBotConfigs ?? []).filter(b => b.id !== botId && b.setupCompleted);
-                                    if (others.some(b => b.feishuAppId === appId)) {
-                                        toastRef.current.error('该飞书应用凭证已被其他 Bot 使用');
-                                        return;
-                                    }
-                                    saveBotField({ feishuAppId: appId });
-                                }}
-                                onAppSecretChange={(appSecret) => saveBotField({ feishuAppSecret: appSecret })}
-                                verifyStatus={verifyStatus}
-                                botName={botUsername}
-                            />
+                            <>
+                                <FeishuCredentialInput
+                                    appId={botConfig.feishuAppId ?? ''}
+                                    appSecret={botConfig.feishuAppSecret ?? ''}
+                                    onAppIdChange={(appId) => {
+                                        const others = (config.imBotConfigs ?? []).filter(b => b.id !== botId && b.setupCompleted);
+                                        if (others.some(b => b.feishuAppId === appId)) {
+                                            toastRef.current.error('该飞书应用凭证已被其他 Bot 使用');
+                                            return;
+                                        }
+                                        saveBotField({ feishuAppId: appId });
+                                    }}
+                                    onAppSecretChange={(appSecret) => saveBotField({ feishuAppSecret: appSecret })}
+                                    verifyStatus={verifyStatus}
+                                    botName={botUsername}
+                                />
+                                <div className="mt-4 flex items-center justify-between">
+                                    <div>
+                                        <p className="text-sm text-[var(--ink)]">卡片流式回复</p>
+                                        <p className="text-xs text-[var(--ink-muted)]">以消息卡片实时更新回复，附工具调用摘要与模型/耗时（重启 Bot 后生效）</p>
+                                    </div>
+                                    <button
+                                        type="button"
+                                        onClick={() => saveBotField({ feishuCardStreaming: !botConfig.feishuCardStreaming })}
+                                        className={`relative inline-flex h-5 w-9 shrink-0 cursor-pointer rounded-full border-2 border-transparent transition-colors duration-200 ease-in-out focus:outline-none ${
+                                            botConfig.feishuCardStreaming ? 'bg-[var(--accent)]' : 'bg-[var(--ink-faint)]'
+                                        }`}
+                                    >
+                                        <span
+                                            className={`pointer-events-none inline-block h-4 w-4 transform rounded-full bg-white shadow ring-0 transition duration-200 ease-in-out ${
+                                                botConfig.feishuCardStreaming ? 'translate-x-4' : 'translate-x-0'
+                                            }`}
+                                        />
+                                    </button>
+                                </div>
+                                <div className="mt-3 flex items-center justify-between">
+                                    <div>
+                                        <p className="text-sm text-[var(--ink)]">处理中提示卡片</p>
+                                        <p className="text-xs text-[var(--ink-muted)]">无法添加表情回应的会话中，处理期间显示「正在处理」卡片（重启 Bot 后生效）</p>
+                                    </div>
+                                    <button
+                                        type="button"
+                                        onClick={() => saveBotField({ feishuProcessingCard: !botConfig.feishuProcessingCard })}
+                                        className={`relative inline-flex h-5 w-9 shrink-0 cursor-pointer rounded-full border-2 border-transparent transition-colors duration-200 ease-in-out focus:outline-none ${
+                                            botConfig.feishuProcessingCard ? 'bg-[var(--accent)]' : 'bg-[var(--ink-faint)]'
+                                        }`}
+                                    >
+                                        <span
+                                            className={`pointer-events-none inline-block h-4 w-4 transform rounded-full bg-white shadow ring-0 transition duration-200 ease-in-out ${
+                                                botConfig.feishuProcessingCard ? 'translate-x-4' : 'translate-x-0'
+                                            }`}
+                                        />
+                                    </button>
+                                </div>
+                            </>
                         ) : (
                             <BotTokenInput
                                 value={botConfig.botToken}
@@ -571,6 +641,8 @@ export default function ImBotDetail({
                             id: p.id, name: p.name, primaryModel: p.primaryModel,
                             baseUrl: p.config.baseUrl, authType: p.authType,
                             apiKey: p.type !== 'subscription' ? apiKeys[p.id] : undefined,
+                            models: p.models?.map(m => ({ model: m.model, modelName: m.modelName })),
+                            modelAliases: p.modelAliases,
                         }));
                     await hotUpdateRunning('cmd_update_im_bot_ai_config', {
                         model: newModel || null,
@@ -585,6 +657,87 @@ export default function ImBotDetail({
                 }}
             />
 
+            {/* Provider failover */}
+            <div className="rounded-xl border border-[var(--line)] bg-[var(--paper-elevated)] p-5">
+                <h3 className="mb-1 text-sm font-semibold text-[var(--ink)]">备用供应商</h3>
+                <p className="mb-3 text-xs text-[var(--ink-muted)]">
+                    当前供应商认证失败、额度不足或服务异常时，按勾选顺序自动切换重试，失败的供应商 5 分钟内不再尝试（重启 Bot 后生效）
+                </p>
+                <div className="flex flex-col gap-2">
+                    {fallbackCandidates.map(p => {
+                        const order = (botConfig.fallbackProviderIds ?? []).indexOf(p.id);
+                        return (
+                            <label key={p.id} className="flex cursor-pointer items-center gap-2 text-sm text-[var(--ink)]">
+                                <input
+                                    type="checkbox"
+                                    checked={order >= 0}
+                                    onChange={() => {
+                                        const current = botConfig.fallbackProviderIds ?? [];
+                                        const updated = order >= 0
+                                            ? current.filter(id => id !== p.id)
+                                            : [...current, p.id];
+                                        saveBotField({ fallbackProviderIds: updated.length > 0 ? updated : undefined });
+                                    }}
+                                />
+                                <span>{p.name}</span>
+                                {order >= 0 && <span className="text-xs text-[var(--ink-muted)]">#{order + 1}</span>}
+                            </label>
+                        );
+                    })}
+                    {fallbackCandidates.length === 0 && (
+                        <p className="text-xs text-[var(--ink-muted)]">暂无其他可用供应商</p>
+                    )}
+                </div>
+            </div>
+
+            {/* Tool-activity status line */}
+            <div className="rounded-xl border border-[var(--line)] bg-[var(--paper-elevated)] p-5">
+                <h3 className="mb-1 text-sm font-semibold text-[var(--ink)]">工具进度</h3>
+                <p className="mb-3 text-xs text-[var(--ink-muted)]">
+                    回复生成时在消息中显示正在运行的工具，例如「🔧 Running Bash: npm test (00:42)」，正文到达后自动替换（重启 Bot 后生效）
+                </p>
+                <div className="flex gap-2">
+                    {TOOL_STATUS_OPTIONS.map(opt => {
+                        const selected = (botConfig.toolStatus ?? 'off') === opt.value;
+                        return (
+                            <button
+                                key={opt.value}
+                                onClick={() => saveBotField({ toolStatus: opt.value })}
+                                className={`rounded-lg border px-3 py-1.5 text-xs transition-colors ${
+                                    selected
+                                        ? 'border-[var(--button-primary-bg)] bg-[var(--paper-contrast)] text-[var(--ink)]'
+                                        : 'border-[var(--line)] text-[var(--ink-muted)] hover:border-[var(--line-strong)]'
+                                }`}
+                            >
+                                {opt.label}
+                            </button>
+                        );
+                    })}
+                </div>
+            </div>
+
+            {/* Voice transcription */}
+            <TranscriptionConfigCard
+                transcription={botConfig.transcription}
+                onChange={(transcription) => saveBotField({ transcription })}
+            />
+
+            {/* Attachment inbox */}
+            <InboxConfigCard
+                botId={botId}
+                isRunning={isRunning}
+                inbox={botConfig.inbox}
+                onChange={(inbox) => saveBotField({ inbox })}
+            />
+
+            {/* Usage accounting */}
+            <UsageCard
+                botId={botId}
+                usageFooter={botConfig.usageFooter ?? false}
+                usageCommand={botConfig.usageCommand ?? false}
+                onChange={(patch) => saveBotField(patch)}
+            />
+
             {/* MCP Tools */}
             <McpToolsCard
                 availableMcpServers={availableMcpServers}
@@ -108,6 +108,8 @@ export default function ImBotList({
                 baseUrl: p.config.baseUrl,
                 authType: p.authType,
                 apiKey: p.type !== 'subscription' ? apiKeys[p.id] : undefined,
+                models: p.models?.map(m => ({ model: m.model, modelName: m.modelName })),
+                modelAliases: p.modelAliases,
             }));
 
         const allServers = await getAllMcpServers();
@@ -131,6 +133,17 @@ export default function ImBotList({
             feishuAppId: cfg.feishuAppId || null,
             feishuAppSecret: cfg.feishuAppSecret || null,
             heartbeatConfigJson: cfg.heartbeat ? JSON.stringify(cfg.heartbeat) : null,
+            rateLimitJson: cfg.rateLimit ? JSON.stringify(cfg.rateLimit) : null,
+            schedulerWeight: cfg.schedulerWeight ?? null,
+            feishuCardStreaming: cfg.feishuCardStreaming ?? null,
+            feishuProcessingCard: cfg.feishuProcessingCard ?? null,
+            documentReplyThreshold: cfg.documentReplyThreshold ?? null,
+            fallbackProviderIds: cfg.fallbackProviderIds ?? null,
+            transcriptionJson: cfg.transcription ? JSON.stringify(cfg.transcription) : null,
+            inboxJson: cfg.inbox ? JSON.stringify(cfg.inbox) : null,
+            toolStatus: cfg.toolStatus ?? null,
+            usageFooter: cfg.usageFooter ?? null,
+            usageCommand: cfg.usageCommand ?? null,
         };
     }, [providers, apiKeys]);
 
@@ -189,6 +189,8 @@ export default function ImBotWizard({
                 baseUrl: p.config.baseUrl,
                 authType: p.authType,
                 apiKey: p.type !== 'subscription' ? apiKeys[p.id] : undefined,
+                models: p.models?.map(m => ({ model: m.model, modelName: m.modelName })),
+                modelAliases: p.modelAliases,
             }));
 
         const allServers = await getAllMcpServers();
@@ -212,6 +214,17 @@ export default function ImBotWizard({
             feishuAppId: cfg.feishuAppId || null,
             feishuAppSecret: cfg.feishuAppSecret || null,
             heartbeatConfigJson: cfg.heartbeat ? JSON.stringify(cfg.heartbeat) : null,
+            rateLimitJson: cfg.rateLimit ? JSON.stringify(cfg.rateLimit) : null,
+            schedulerWeight: cfg.schedulerWeight ?? null,
+            feishuCardStreaming: cfg.feishuCardStreaming ?? null,
+            feishuProcessingCard: cfg.feishuProcessingCard ?? null,
+            documentReplyThreshold: cfg.documentReplyThreshold ?? null,
+            fallbackProviderIds: cfg.fallbackProviderIds ?? null,
+            transcriptionJson: cfg.transcription ? JSON.stringify(cfg.transcription) : null,
+            inboxJson: cfg.inbox ? JSON.stringify(cfg.inbox) : null,
+            toolStatus: cfg.toolStatus ?? null,
+            usageFooter: cfg.usageFooter ?? null,
+            usageCommand: cfg.usageCommand ?? null,
         };
     }, [providers, apiKeys]);
 
@@ -634,7 +647,7 @@ export default function ImBotWizard({
                         <ol className="mt-3 space-y-1.5 text-sm text-[var(--ink-muted)]">
                             <li>左侧菜单进入 <span className="font-medium text-[var(--ink)]">事件与回调</span> &gt; <span className="font-medium text-[var(--ink)]">事件配置</span></li>
                             <li>请求方式选择：<span className="font-medium text-[var(--ink)]">使用长连接接收事件</span>（不需要公网服务器）</li>
-                            <li>添加事件：搜索 <code className="rounded bg-[var(--paper-contrast)] px-1.5 py-0.5 text-[11px]">im.message.receive_v1</code>（接收消息），勾选添加</li>
+                            <li>添加事件：搜索 <code className="rounded bg-[var(--paper-contrast)] px-1.5 py-0.5 text-[11px]">im.message.receive_v1</code>（接收消息）和 <code className="rounded bg-[var(--paper-contrast)] px-1.5 py-0.5 text-[11px]">im.message.updated_v1</code>（消息被编辑，用于修改后重新回答），勾选添加</li>
                         </ol>
                         <img
                             src={feishuStep2EventImg}
@@ -0,0 +1,105 @@
+import React, { useEffect, useState } from 'react';
+import type { ImInboxConfig, ImInboxUsage } from '../../../../shared/types/im';
+import { isTauriEnvironment } from '@/utils/browserMock';
+import { shortenPathForDisplay } from '@/utils/pathDetection';
+
+const DEFAULT_RETENTION_DAYS = 30;
+const DEFAULT_QUOTA_MB = 1024;
+
+const INPUT_CLASS =
+    'w-24 rounded-lg border border-[var(--line)] bg-[var(--paper)] px-2 py-1.5 text-xs text-[var(--ink)] focus:border-[var(--accent)] focus:outline-none focus:ring-1 focus:ring-[var(--accent)]';
+
+function formatBytes(bytes: number): string {
+    if (bytes < 1024) return `${bytes} B`;
+    if (bytes < 1024 * 1024) return `${(bytes / 1024).toFixed(1)} KB`;
+    if (bytes < 1024 * 1024 * 1024) return `${(bytes / 1024 / 1024).toFixed(1)} MB`;
+    return `${(bytes / 1024 / 1024 / 1024).toFixed(2)} GB`;
+}
+
+export default function InboxConfigCard({
+    botId,
+    isRunning,
+    inbox,
+    onChange,
+}: {
+    botId: string;
+    isRunning: boolean;
+    inbox: ImInboxConfig | undefined;
+    onChange: (config: ImInboxConfig | undefined) => void;
+}) {
+    const [retentionDays, setRetentionDays] = useState(String(inbox?.retentionDays ?? DEFAULT_RETENTION_DAYS));
+    const [quotaMb, setQuotaMb] = useState(String(inbox?.quotaMb ?? DEFAULT_QUOTA_MB));
+    const [usage, setUsage] = useState<ImInboxUsage[]>([]);
+
+    useEffect(() => {
+        setRetentionDays(String(inbox?.retentionDays ?? DEFAULT_RETENTION_DAYS));
+        setQuotaMb(String(inbox?.quotaMb ?? DEFAULT_QUOTA_MB));
+    }, [inbox]);
+
+    useEffect(() => {
+        if (!isRunning || !isTauriEnvironment()) {
+            setUsage([]);
+            return;
+        }
+        let cancelled = false;
+        (async () => {
+            try {
+                const { invoke } = await import('@tauri-apps/api/core');
+                const result = await invoke<ImInboxUsage[]>('cmd_im_inbox_usage', { botId });
+                if (!cancelled) setUsage(result);
+            } catch {
+                // Non-critical: usage is informational
+            }
+        })();
+        return () => { cancelled = true; };
+    }, [botId, isRunning]);
+
+    const commit = (key: 'retentionDays' | 'quotaMb', raw: string) => {
+        const value = parseInt(raw, 10);
+        if (isNaN(value) || value < 0 || value === inbox?.[key]) return;
+        onChange({ ...inbox, [key]: value });
+    };
+
+    return (
+        <div className="rounded-xl border border-[var(--line)] bg-[var(--paper-elevated)] p-5">
+            <h3 className="mb-1 text-sm font-semibold text-[var(--ink)]">附件收件箱</h3>
+            <p className="mb-3 text-xs text-[var(--ink-muted)]">
+                收到的文件按日期保存在工作区的 {inbox?.dir || 'myagents_files/inbox'}/ 下，超出保留天数或空间上限时自动清理最旧的文件（0 表示不限；重启 Bot 后生效）
+            </p>
+            <div className="flex flex-wrap items-center gap-4">
+                <label className="flex items-center gap-2 text-xs text-[var(--ink)]">
+                    保留天数
+                    <input
+                        type="number"
+                        min={0}
+                        value={retentionDays}
+                        onChange={e => setRetentionDays(e.target.value)}
+                        onBlur={() => commit('retentionDays', retentionDays)}
+                        className={INPUT_CLASS}
+                    />
+                </label>
+                <label className="flex items-center gap-2 text-xs text-[var(--ink)]">
+                    每个工作区上限 (MB)
+                    <input
+                        type="number"
+                        min={0}
+                        value={quotaMb}
+                        onChange={e => setQuotaMb(e.target.value)}
+                        onBlur={() => commit('quotaMb', quotaMb)}
+                        className={INPUT_CLASS}
+                    />
+                </label>
+            </div>
+            {usage.length > 0 && (
+                <div className="mt-3 space-y-1">
+                    {usage.map(u => (
+                        <p key={u.inboxPath} className="text-xs text-[var(--ink-muted)]" title={u.inboxPath}>
+                            {shortenPathForDisplay(u.workspacePath)}：{u.files} 个文件，{formatBytes(u.bytes)}
+                            {u.quotaBytes != null && ` / ${formatBytes(u.quotaBytes)}`}
+                        </p>
+                    ))}
+                </div>
+            )}
+        </div>
+    );
+}
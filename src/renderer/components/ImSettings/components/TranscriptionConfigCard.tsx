@@ -0,0 +1,85 @@
+import React, { useCallback, useEffect, useState } from 'react';
+import type { ImTranscriptionConfig } from '../../../../shared/types/im';
+
+const DEFAULT_TRANSCRIPTION_CONFIG: ImTranscriptionConfig = {
+    enabled: false,
+    baseUrl: 'https://api.openai.com/v1',
+};
+
+const INPUT_CLASS =
+    'w-full rounded-lg border border-[var(--line)] bg-[var(--paper)] px-3 py-1.5 text-xs text-[var(--ink)] focus:border-[var(--accent)] focus:outline-none focus:ring-1 focus:ring-[var(--accent)]';
+
+type TextField = 'baseUrl' | 'apiKey' | 'model' | 'language';
+
+const FIELDS: { key: TextField; label: string; placeholder: string; secret?: boolean }[] = [
+    { key: 'baseUrl', label: '接口地址', placeholder: 'https://api.openai.com/v1 或 http://127.0.0.1:8080/v1' },
+    { key: 'apiKey', label: 'API Key', placeholder: '本地服务可留空', secret: true },
+    { key: 'model', label: '模型', placeholder: 'whisper-1' },
+    { key: 'language', label: '语言', placeholder: '自动识别（如 zh、en）' },
+];
+
+export default function TranscriptionConfigCard({
+    transcription,
+    onChange,
+}: {
+    transcription: ImTranscriptionConfig | undefined;
+    onChange: (config: ImTranscriptionConfig | undefined) => void;
+}) {
+    const config = transcription ?? DEFAULT_TRANSCRIPTION_CONFIG;
+    // Text fields are edited locally and saved on blur
+    const [draft, setDraft] = useState<ImTranscriptionConfig>(config);
+    useEffect(() => setDraft(transcription ?? DEFAULT_TRANSCRIPTION_CONFIG), [transcription]);
+
+    const commit = useCallback(
+        (key: TextField) => {
+            const value = (draft[key] ?? '').trim();
+            if (value === (config[key] ?? '')) return;
+            // baseUrl is required (kept as a string); optional fields are cleared when empty
+            onChange({ ...config, [key]: key === 'baseUrl' ? value : value || undefined });
+        },
+        [draft, config, onChange],
+    );
+
+    return (
+        <div className="rounded-xl border border-[var(--line)] bg-[var(--paper-elevated)] p-5">
+            {/* Header with toggle */}
+            <div className="mb-1 flex items-center justify-between">
+                <h3 className="text-sm font-semibold text-[var(--ink)]">语音转写</h3>
+                <button
+                    type="button"
+                    onClick={() => onChange({ ...config, enabled: !config.enabled })}
+                    className={`relative inline-flex h-5 w-9 shrink-0 cursor-pointer rounded-full border-2 border-transparent transition-colors duration-200 ease-in-out focus:outline-none ${
+                        config.enabled ? 'bg-[var(--accent)]' : 'bg-[var(--ink-faint)]'
+                    }`}
+                >
+                    <span
+                        className={`pointer-events-none inline-block h-4 w-4 transform rounded-full bg-white shadow ring-0 transition duration-200 ease-in-out ${
+                            config.enabled ? 'translate-x-4' : 'translate-x-0'
+                        }`}
+                    />
+                </button>
+            </div>
+            <p className="mb-3 text-xs text-[var(--ink-muted)]">
+                将语音消息转为文字附在消息中（兼容 OpenAI /audio/transcriptions 接口，可指向本地 Whisper 服务；重启 Bot 后生效）
+            </p>
+
+            {config.enabled && (
+                <div className="space-y-3">
+                    {FIELDS.map(field => (
+                        <div key={field.key}>
+                            <p className="mb-1 text-xs font-medium text-[var(--ink)]">{field.label}</p>
+                            <input
+                                type={field.secret ? 'password' : 'text'}
+                                value={draft[field.key] ?? ''}
+                                placeholder={field.placeholder}
+                                onChange={e => setDraft(prev => ({ ...prev, [field.key]: e.target.value }))}
+                                onBlur={() => commit(field.key)}
+                                className={INPUT_CLASS}
+                            />
+                        </div>
+                    ))}
+                </div>
+            )}
+        </div>
+    );
+}
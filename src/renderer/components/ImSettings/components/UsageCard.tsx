@@ -0,0 +1,106 @@
+import React, { useEffect, useState } from 'react';
+import type { ImUsageBucket } from '../../../../shared/types/im';
+import { isTauriEnvironment } from '@/utils/browserMock';
+
+function formatTokens(n: number): string {
+    if (n < 1000) return String(n);
+    if (n < 1_000_000) return `${(n / 1000).toFixed(1)}k`;
+    return `${(n / 1_000_000).toFixed(2)}M`;
+}
+
+function formatBucket(bucket: ImUsageBucket | undefined): string {
+    if (!bucket || bucket.turns === 0) return '无';
+    const input = bucket.inputTokens + bucket.cacheReadTokens + bucket.cacheCreationTokens;
+    let text = `${bucket.turns} 轮 · 输入 ${formatTokens(input)} · 输出 ${formatTokens(bucket.outputTokens)}`;
+    if (bucket.costUsd > 0) text += ` · $${bucket.costUsd.toFixed(2)}`;
+    return text;
+}
+
+function localPeriod(date: Date, withDay: boolean): string {
+    const y = date.getFullYear();
+    const m = String(date.getMonth() + 1).padStart(2, '0');
+    if (!withDay) return `${y}-${m}`;
+    return `${y}-${m}-${String(date.getDate()).padStart(2, '0')}`;
+}
+
+function Toggle({ checked, onClick }: { checked: boolean; onClick: () => void }) {
+    return (
+        <button
+            type="button"
+            onClick={onClick}
+            className={`relative inline-flex h-5 w-9 shrink-0 cursor-pointer rounded-full border-2 border-transparent transition-colors duration-200 ease-in-out focus:outline-none ${
+                checked ? 'bg-[var(--accent)]' : 'bg-[var(--ink-faint)]'
+            }`}
+        >
+            <span
+                className={`pointer-events-none inline-block h-4 w-4 transform rounded-full bg-white shadow ring-0 transition duration-200 ease-in-out ${
+                    checked ? 'translate-x-4' : 'translate-x-0'
+                }`}
+            />
+        </button>
+    );
+}
+
+export default function UsageCard({
+    botId,
+    usageFooter,
+    usageCommand,
+    onChange,
+}: {
+    botId: string;
+    usageFooter: boolean;
+    usageCommand: boolean;
+    onChange: (patch: { usageFooter?: boolean; usageCommand?: boolean }) => void;
+}) {
+    const [today, setToday] = useState<ImUsageBucket | undefined>();
+    const [month, setMonth] = useState<ImUsageBucket | undefined>();
+
+    useEffect(() => {
+        if (!isTauriEnvironment()) return;
+        let cancelled = false;
+        (async () => {
+            try {
+                const { invoke } = await import('@tauri-apps/api/core');
+                const now = new Date();
+                const since = new Date(now.getFullYear(), now.getMonth(), 1).toISOString();
+                const [days, months] = await Promise.all([
+                    invoke<ImUsageBucket[]>('cmd_im_usage', { botId, period: 'day', since }),
+                    invoke<ImUsageBucket[]>('cmd_im_usage', { botId, period: 'month', since }),
+                ]);
+                if (cancelled) return;
+                setToday(days.find(b => b.period === localPeriod(now, true)));
+                setMonth(months.find(b => b.period === localPeriod(now, false)));
+            } catch {
+                // Non-critical: usage is informational
+            }
+        })();
+        return () => { cancelled = true; };
+    }, [botId]);
+
+    return (
+        <div className="rounded-xl border border-[var(--line)] bg-[var(--paper-elevated)] p-5">
+            <h3 className="mb-1 text-sm font-semibold text-[var(--ink)]">用量统计</h3>
+            <p className="mb-3 text-xs text-[var(--ink-muted)]">
+                对话、心跳与该 Bot 的定时任务消耗的 Token 和费用（费用为 SDK 估算值）
+            </p>
+            <div className="mb-4 space-y-1">
+                <p className="text-xs text-[var(--ink)]">今日：{formatBucket(today)}</p>
+                <p className="text-xs text-[var(--ink)]">本月：{formatBucket(month)}</p>
+            </div>
+            <div className="flex items-center justify-between">
+                <div>
+                    <p className="text-sm text-[var(--ink)]">回复显示用量</p>
+                    <p className="text-xs text-[var(--ink-muted)]">在每条回复末尾附上本轮 Token 与费用（重启 Bot 后生效）</p>
+                </div>
+                <Toggle checked={usageFooter} onClick={() => onChange({ usageFooter: !usageFooter })} />
+            </div>
+            <div className="mt-3 flex items-center justify-between">
+                <div>
+                    <p className="text-sm text-[var(--ink)]">/usage 命令</p>
+                    <p className="text-xs text-[var(--ink-muted)]">允许在聊天中发送 /usage 查看今日与本月用量（重启 Bot 后生效）</p>
+                </div>
+                <Toggle checked={usageCommand} onClick={() => onChange({ usageCommand: !usageCommand })} />
+            </div>
+        </div>
+    );
+}
@@ -115,6 +115,10 @@ export interface Provider {
   // 模型列表 - 使用新的 ModelEntity 结构
   models: ModelEntity[];
 
+  // 模型简称 (IM Bot /model 命令使用)，如 { sonnet: 'claude-sonnet-4-6' }
+  // 预设值为默认简称；用户修改保存在 AppConfig.providerModelAliases，加载时合并覆盖
+  modelAliases?: Record<string, string>;
+
   // 用户输入的 API Key (运行时填充，不持久化到 provider 定义)
   apiKey?: string;
 }
@@ -215,6 +219,9 @@ export interface AppConfig {
   // General settings
   autoStart: boolean; // 开机启动
   cronNotifications: boolean; // 定时任务通知
+  // Max concurrent AI turns across all IM bots, heartbeats and cron tasks
+  // (read by the Rust turn scheduler at startup; undefined = DEFAULT_MAX_CONCURRENT_TURNS)
+  maxConcurrentTurns?: number;
   // API Keys for providers (stored separately for security)
   providerApiKeys?: Record<string, string>;
   // Provider verification status (persisted after API key validation)
@@ -226,6 +233,9 @@ export interface AppConfig {
   // These are merged with preset models at runtime, allowing users to add models
   // while keeping preset definitions unchanged (updated with app releases)
   presetCustomModels?: Record<string, ModelEntity[]>;
+  // User-edited model aliases per provider (key = provider ID), replacing the provider's
+  // default modelAliases. Also read by the Rust side for IM /model resolution.
+  providerModelAliases?: Record<string, Record<string, string>>;
 
   // ===== MCP Configuration =====
   // Custom MCP servers added by user (merged with presets)
@@ -286,6 +296,13 @@ const ANTHROPIC_MODELS: ModelEntity[] = [
   { model: 'claude-haiku-4-5', modelName: 'Claude Haiku 4.5', modelSeries: 'claude' },
 ];
 
+/** Anthropic 模型默认简称（IM Bot /model 命令；用户可在服务商设置中修改） */
+const ANTHROPIC_MODEL_ALIASES: Record<string, string> = {
+  sonnet: 'claude-sonnet-4-6',
+  opus: 'claude-opus-4-6',
+  haiku: 'claude-haiku-4-5',
+};
+
 export const PRESET_PROVIDERS: Provider[] = [
   {
     id: 'anthropic-sub',
@@ -297,6 +314,7 @@ export const PRESET_PROVIDERS: Provider[] = [
     isBuiltin: true,
     config: {},
     models: ANTHROPIC_MODELS,
+    modelAliases: ANTHROPIC_MODEL_ALIASES,
   },
   {
     id: 'anthropic-api',
@@ -311,6 +329,7 @@ export const PRESET_PROVIDERS: Provider[] = [
       baseUrl: 'https://api.anthropic.com',
     },
     models: ANTHROPIC_MODELS,
+    modelAliases: ANTHROPIC_MODEL_ALIASES,
   },
   {
     id: 'deepseek',
@@ -561,6 +580,9 @@ export function getPresetMcpServer(id: string): McpServerDefinition | undefined
   return PRESET_MCP_SERVERS.find(s => s.id === id);
 }
 
+/** Default app-wide concurrent AI turn limit (matches turn_scheduler::DEFAULT_CAPACITY) */
+export const DEFAULT_MAX_CONCURRENT_TURNS = 8;
+
 export const DEFAULT_CONFIG: AppConfig = {
   defaultProviderId: 'anthropic-sub',
   defaultPermissionMode: 'auto',
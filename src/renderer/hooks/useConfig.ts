@@ -57,6 +57,9 @@ interface UseConfigResult {
     savePresetCustomModels: (providerId: string, models: ModelEntity[]) => Promise<void>;
     removePresetCustomModel: (providerId: string, modelId: string) => Promise<void>;
 
+    // Model aliases per provider (null = restore the provider's default aliases)
+    saveProviderModelAliases: (providerId: string, aliases: Record<string, string> | null) => Promise<void>;
+
     // API Keys
     apiKeys: Record<string, string>;
     saveApiKey: (providerId: string, apiKey: string) => Promise<void>;
@@ -99,6 +102,20 @@ function mergePresetCustomModels(
     });
 }
 
+// Helper: Apply user-edited model aliases over providers' default aliases
+function mergeModelAliases(
+    providers: Provider[],
+    providerModelAliases: Record<string, Record<string, string>> | undefined
+): Provider[] {
+    if (!providerModelAliases || Object.keys(providerModelAliases).length === 0) {
+        return providers;
+    }
+    return providers.map(provider => {
+        const aliases = providerModelAliases[provider.id];
+        return aliases ? { ...provider, modelAliases: aliases } : provider;
+    });
+}
+
 export function useConfig(): UseConfigResult {
     const [config, setConfig] = useState<AppConfig>(DEFAULT_CONFIG);
     const [projects, setProjects] = useState<Project[]>([]);
@@ -108,10 +125,14 @@ export function useConfig(): UseConfigResult {
     const [isLoading, setIsLoading] = useState(true);
     const [error, setError] = useState<string | null>(null);
 
-    // Merge preset custom models into providers for consumers (memoized to avoid unnecessary recalculations)
+    // Merge preset custom models and user model aliases into providers for consumers
+    // (memoized to avoid unnecessary recalculations)
     const providers = useMemo(
-        () => mergePresetCustomModels(rawProviders, config.presetCustomModels),
-        [rawProviders, config.presetCustomModels]
+        () => mergeModelAliases(
+            mergePresetCustomModels(rawProviders, config.presetCustomModels),
+            config.providerModelAliases
+        ),
+        [rawProviders, config.presetCustomModels, config.providerModelAliases]
     );
 
     const load = useCallback(async () => {
@@ -330,6 +351,20 @@ export function useConfig(): UseConfigResult {
         setConfig(newConfig);
     }, []);
 
+    // Save model aliases for a provider (null restores the provider's defaults)
+    const saveProviderModelAliases = useCallback(async (providerId: string, aliases: Record<string, string> | null) => {
+        const newConfig = await atomicModifyConfig(config => {
+            const newProviderModelAliases = { ...config.providerModelAliases };
+            if (aliases === null) {
+                delete newProviderModelAliases[providerId];
+            } else {
+                newProviderModelAliases[providerId] = aliases;
+            }
+            return { ...config, providerModelAliases: newProviderModelAliases };
+        });
+        setConfig(newConfig);
+    }, []);
+
     return {
         config,
         isLoading,
@@ -347,6 +382,7 @@ export function useConfig(): UseConfigResult {
         refreshProviders,
         savePresetCustomModels,
         removePresetCustomModel,
+        saveProviderModelAliases,
         apiKeys,
         saveApiKey,
         deleteApiKey,
@@ -25,6 +25,7 @@ import {
     isVerifyExpired,
     SUBSCRIPTION_PROVIDER_ID,
     PROXY_DEFAULTS,
+    DEFAULT_MAX_CONCURRENT_TURNS,
     isValidProxyHost,
     getPresetMcpServer,
 } from '@/config/types';
@@ -90,6 +91,9 @@ interface ProviderEditForm {
     customModels: string[];  // 用户添加的自定义模型
     removedModels: string[]; // 用户标记删除的已保存模型（model ID）
     newModelInput: string;
+    aliases: Record<string, string>; // 模型简称 → 模型 ID（IM Bot /model 命令）
+    newAliasName: string;
+    newAliasModel: string;
     // 自定义供应商编辑字段
     editName?: string;
     editCloudProvider?: string;
@@ -222,6 +226,7 @@ export default function Settings({ initialSection, onSectionChange, isActive, up
         deleteCustomProvider: deleteCustomProviderService,
         savePresetCustomModels,
         removePresetCustomModel: _removePresetCustomModel,
+        saveProviderModelAliases,
         reload: reloadConfig,
     } = useConfig();
     const toast = useToast();
@@ -1021,6 +1026,9 @@ export default function Settings({ initialSection, onSectionChange, isActive, up
             customModels: [],  // TODO: Load from persisted custom models if any
             removedModels: [], // 标记要删除的已保存模型
             newModelInput: '',
+            aliases: { ...provider.modelAliases },
+            newAliasName: '',
+            newAliasModel: provider.models[0]?.model ?? '',
             // 为自定义供应商初始化编辑字段
             ...(provider.isBuiltin ? {} : {
                 editName: provider.name,
@@ -1068,10 +1076,52 @@ export default function Settings({ initialSection, onSectionChange, isActive, up
         });
     };
 
+    // Add a model alias to the editing provider
+    const addAliasToProvider = () => {
+        if (!editingProvider) return;
+        const name = editingProvider.newAliasName.trim();
+        const target = editingProvider.newAliasModel;
+        if (!name || !target) return;
+        if (/\s/.test(name)) {
+            toast.error('简称不能包含空格');
+            return;
+        }
+        setEditingProvider({
+            ...editingProvider,
+            aliases: { ...editingProvider.aliases, [name]: target },
+            newAliasName: '',
+        });
+    };
+
+    // Remove a model alias from the editing provider
+    const removeAliasFromProvider = (name: string) => {
+        if (!editingProvider) return;
+        const { [name]: _removed, ...rest } = editingProvider.aliases;
+        setEditingProvider({ ...editingProvider, aliases: rest });
+    };
+
+    // Persist alias edits; aliases equal to the provider's defaults are stored as "no override"
+    const saveAliasEdits = async (provider: Provider, aliases: Record<string, string>) => {
+        const sameAliases = (a: Record<string, string>, b: Record<string, string>) =>
+            Object.keys(a).length === Object.keys(b).length && Object.entries(a).every(([k, v]) => b[k] === v);
+        if (sameAliases(aliases, provider.modelAliases ?? {})) return;
+        const defaults = PRESET_PROVIDERS.find(p => p.id === provider.id)?.modelAliases ?? {};
+        await saveProviderModelAliases(provider.id, sameAliases(aliases, defaults) ? null : aliases);
+        toast.success('模型简称已更新');
+    };
+
     // Save provider edits
     const saveProviderEdits = async () => {
         if (!editingProvider) return;
-        const { provider, customModels, removedModels, editName, editCloudProvider, editBaseUrl, editAuthType } = editingProvider;
+        const { provider, customModels, removedModels, editName, editCloudProvider, editBaseUrl, editAuthType, aliases } = editingProvider;
+
+        try {
+            await saveAliasEdits(provider, aliases);
+        } catch (error) {
+            console.error('[Settings] Failed to save model aliases:', error);
+            toast.error('保存失败');
+            return;
+        }
 
         if (provider.isBuiltin) {
             // For preset providers: save user-added custom models
@@ -1765,6 +1815,39 @@ export default function Settings({ initialSection, onSectionChange, isActive, up
                                 </div>
                             </div>
 
+                            {/* Concurrency Settings */}
+                            <div className="rounded-xl border border-[var(--line)] bg-[var(--paper-elevated)] p-5">
+                                <h3 className="text-base font-medium text-[var(--ink)]">并发任务</h3>
+                                <div className="mt-4 flex items-center justify-between">
+                                    <div className="flex-1 pr-4">
+                                        <p className="text-sm font-medium text-[var(--ink)]">最大并发轮次</p>
+                                        <p className="text-xs text-[var(--ink-muted)]">
+                                            IM Bot、心跳与定时任务同时运行的 AI 轮次上限，超出的将排队等待
+                                        </p>
+                                    </div>
+                                    <input
+                                        type="number"
+                                        min={1}
+                                        max={64}
+                                        value={config.maxConcurrentTurns ?? DEFAULT_MAX_CONCURRENT_TURNS}
+                                        onChange={async (e) => {
+                                            const capacity = parseInt(e.target.value, 10);
+                                            if (isNaN(capacity) || capacity < 1 || capacity > 64) return;
+                                            await updateConfig({ maxConcurrentTurns: capacity });
+                                            if (isTauriEnvironment()) {
+                                                try {
+                                                    const { invoke } = await import('@tauri-apps/api/core');
+                                                    await invoke('cmd_update_turn_scheduler', { capacity });
+                                                } catch (err) {
+                                                    console.error('[Settings] Update turn scheduler failed:', err);
+                                                }
+                                            }
+                                        }}
+                                        className="w-20 rounded-lg border border-[var(--line)] bg-[var(--paper)] px-3 py-1.5 text-xs text-[var(--ink)] focus:border-[var(--ink)] focus:outline-none"
+                                    />
+                                </div>
+                            </div>
+
                             {/* Network Proxy Settings */}
                             <div className="rounded-xl border border-[var(--line)] bg-[var(--paper-elevated)] p-5">
                                 <h3 className="text-base font-medium text-[var(--ink)]">网络代理</h3>
@@ -2988,6 +3071,71 @@ export default function Settings({ initialSection, onSectionChange, isActive, up
                                     </button>
                                 </div>
                             </div>
+
+                            {/* Model aliases (IM Bot /model <简称>) */}
+                            <div>
+                                <label className="mb-1.5 block text-sm font-medium text-[var(--ink)]">
+                                    模型简称
+                                </label>
+                                <p className="mb-2 text-xs text-[var(--ink-muted)]">
+                                    IM Bot 中可用 /model &lt;简称&gt; 切换到对应模型
+                                </p>
+                                {Object.keys(editingProvider.aliases).length > 0 && (
+                                    <div className="mb-2 flex flex-wrap gap-1.5">
+                                        {Object.entries(editingProvider.aliases)
+                                            .sort(([a], [b]) => a.localeCompare(b))
+                                            .map(([name, target]) => (
+                                                <div
+                                                    key={`alias-${name}`}
+                                                    className="group flex items-center gap-1 rounded-md bg-[var(--paper-contrast)] px-2 py-1 text-xs font-medium text-[var(--ink)] hover:bg-[var(--paper-inset)]"
+                                                >
+                                                    <span>{name} → {target}</span>
+                                                    <button
+                                                        type="button"
+                                                        onClick={() => removeAliasFromProvider(name)}
+                                                        className="ml-0.5 rounded p-0.5 text-[var(--ink-muted)] opacity-0 transition-opacity hover:bg-[var(--paper-contrast)] hover:text-[var(--ink)] group-hover:opacity-100"
+                                                    >
+                                                        <X className="h-3 w-3" />
+                                                    </button>
+                                                </div>
+                                            ))}
+                                    </div>
+                                )}
+                                <div className="flex gap-2">
+                                    <input
+                                        type="text"
+                                        value={editingProvider.newAliasName}
+                                        onChange={(e) => setEditingProvider((p) => p ? { ...p, newAliasName: e.target.value } : null)}
+                                        onKeyDown={(e) => {
+                                            if (e.key === 'Enter') {
+                                                e.preventDefault();
+                                                addAliasToProvider();
+                                            }
+                                        }}
+                                        placeholder="简称，如 fast"
+                                        className="w-28 rounded-lg border border-[var(--line)] bg-[var(--paper-elevated)] px-3 py-2.5 text-sm transition-colors focus:border-[var(--ink)] focus:outline-none"
+                                    />
+                                    <CustomSelect
+                                        value={editingProvider.newAliasModel}
+                                        options={[
+                                            ...editingProvider.provider.models
+                                                .filter(m => !editingProvider.removedModels.includes(m.model))
+                                                .map(m => m.model),
+                                            ...editingProvider.customModels,
+                                        ].map(m => ({ value: m, label: m }))}
+                                        onChange={(val) => setEditingProvider((p) => p ? { ...p, newAliasModel: val } : null)}
+                                        className="flex-1"
+                                    />
+                                    <button
+                                        type="button"
+                                        onClick={addAliasToProvider}
+                                        disabled={!editingProvider.newAliasName.trim() || !editingProvider.newAliasModel}
+                                        className="rounded-lg bg-[var(--paper-contrast)] px-3 py-2.5 text-sm font-medium text-[var(--ink)] transition-colors hover:bg-[var(--paper-inset)] disabled:opacity-50"
+                                    >
+                                        <Plus className="h-4 w-4" />
+                                    </button>
+                                </div>
+                            </div>
                         </div>
 
                         <div className="mt-6 flex items-center justify-between">
@@ -115,9 +115,10 @@ export type MessageWire = {
     isImage?: boolean;
   }[];
   metadata?: {
-    source: 'desktop' | 'telegram_private' | 'telegram_group' | 'feishu_private' | 'feishu_group';
+    source: 'desktop' | 'telegram_private' | 'telegram_group' | 'telegram_channel' | 'feishu_private' | 'feishu_group' | 'feishu_channel';
     sourceId?: string;
     senderName?: string;
+    imMessageId?: string;
   };
 };
 
@@ -141,12 +142,29 @@ const streamIndexToToolId: Map<number, string> = new Map();
 const toolResultIndexToId: Map<number, string> = new Map();
 
 // IM Draft Stream: callback for streaming text to Telegram
-type ImStreamCallback = (event: 'delta' | 'block-end' | 'complete' | 'error' | 'permission-request' | 'activity', data: string) => void;
+// `status`: HTTP status of the API error behind an 'error' event, when the raw error carries one
+type ImStreamCallback = (event: 'delta' | 'block-end' | 'complete' | 'error' | 'permission-request' | 'activity', data: string, status?: number) => void;
 let imStreamCallback: ImStreamCallback | null = null;
 // Flag: auto-reset session after image content pollutes conversation history
 let shouldResetSessionAfterError = false;
 // Track text block indices for detecting text-type content_block_stop
 const imTextBlockIndices = new Set<number>();
+// Longest string value forwarded in an IM tool-activity event (tool inputs can be whole files)
+const IM_ACTIVITY_MAX_VALUE_LEN = 200;
+
+/** Shallow copy of a tool input with long string values cut, for IM tool-activity events */
+function truncateToolInputForIm(input: unknown): Record<string, unknown> {
+  if (!input || typeof input !== 'object') return {};
+  const out: Record<string, unknown> = {};
+  for (const [key, value] of Object.entries(input as Record<string, unknown>)) {
+    if (typeof value === 'string') {
+      out[key] = value.length > IM_ACTIVITY_MAX_VALUE_LEN ? value.slice(0, IM_ACTIVITY_MAX_VALUE_LEN) : value;
+    } else if (typeof value === 'number' || typeof value === 'boolean') {
+      out[key] = value;
+    }
+  }
+  return out;
+}
 const childToolToParent: Map<string, string> = new Map();
 let messageSequence = 0;
 let sessionId = randomUUID();
@@ -337,6 +355,7 @@ let currentTurnUsage = {
   cacheCreationTokens: 0,
   model: undefined as string | undefined,
   modelUsage: undefined as Record<string, ModelUsageEntry> | undefined,
+  costUsd: undefined as number | undefined,
 };
 // Timestamp when current assistant response started
 let currentTurnStartTime: number | null = null;
@@ -351,11 +370,34 @@ function resetTurnUsage(): void {
     cacheCreationTokens: 0,
     model: undefined,
     modelUsage: undefined,
+    costUsd: undefined,
   };
   currentTurnStartTime = null;
   currentTurnToolCount = 0;
 }
 
+/**
+ * Token usage of the last completed turn (IM `complete` event, heartbeat and cron responses).
+ * Shape matches Rust `TurnUsage`.
+ */
+export function getLastTurnUsage(): {
+  inputTokens: number;
+  outputTokens: number;
+  cacheReadTokens: number;
+  cacheCreationTokens: number;
+  model?: string;
+  costUsd?: number;
+} {
+  return {
+    inputTokens: currentTurnUsage.inputTokens,
+    outputTokens: currentTurnUsage.outputTokens,
+    cacheReadTokens: currentTurnUsage.cacheReadTokens,
+    cacheCreationTokens: currentTurnUsage.cacheCreationTokens,
+    model: currentTurnUsage.model,
+    costUsd: currentTurnUsage.costUsd,
+  };
+}
+
 // ===== MCP Configuration =====
 import type { McpServerDefinition } from '../renderer/config/types';
 
@@ -1240,9 +1282,20 @@ export function getSessionId(): string {
   return sessionId;
 }
 
+/**
+ * HTTP status of an API error, read only from where the status is stated
+ * ("API Error: 529 …", "status 500", "status code: 401", "HTTP 503", "HTTP/1.1 502").
+ * Bare numbers are ignored — token counts and ports contain digits like 401 or 500.
+ */
+function extractHttpStatus(rawError: string): number | undefined {
+  const match = rawError.match(/\b(?:API Error:?|status(?: code)?:?|HTTP(?:\/[\d.]+)?)\s*([1-5]\d\d)\b/i);
+  return match ? Number(match[1]) : undefined;
+}
+
 /** Localize SDK/system error messages for IM end-users */
 function localizeImError(rawError: string): string {
   if (!rawError) return '模型处理消息时出错';
+  const status = extractHttpStatus(rawError);
 
   // Image content not supported by model
   if (rawError.includes('unknown variant') && rawError.includes('image')) {
@@ -1257,11 +1310,11 @@ function localizeImError(rawError: string): string {
     return 'AI 引擎异常退出，正在自动恢复，请稍后重试';
   }
   // API authentication errors
-  if (rawError.includes('authentication') || rawError.includes('unauthorized') || rawError.includes('401')) {
+  if (rawError.includes('authentication') || rawError.includes('unauthorized') || status === 401) {
     return 'API 认证失败，请检查 API Key 配置';
   }
   // Rate limiting
-  if (rawError.includes('rate_limit') || rawError.includes('429')) {
+  if (rawError.includes('rate_limit') || status === 429) {
     return 'API 请求频率超限，请稍后重试';
   }
   // Billing errors
@@ -1269,7 +1322,7 @@ function localizeImError(rawError: string): string {
     return 'API 余额不足，请充值后重试';
   }
   // Server overloaded
-  if (rawError.includes('overloaded') || rawError.includes('503')) {
+  if (rawError.includes('overloaded') || status === 503 || status === 529) {
     return 'AI 服务繁忙，请稍后重试';
   }
   // Callback replaced
@@ -1866,9 +1919,9 @@ function handleToolResultComplete(toolUseId: string, content: string, isError?:
 
 function handleMessageComplete(): void {
   isStreamingMessage = false;
-  // Notify IM stream: turn complete
+  // Notify IM stream: turn complete (with its token usage for the usage ledger)
   if (imStreamCallback) {
-    imStreamCallback('complete', '');
+    imStreamCallback('complete', JSON.stringify(getLastTurnUsage()));
     imStreamCallback = null;
   }
   // 跨回合状态清理（持久 session 下多回合共享同一个 for-await 循环）
@@ -1940,7 +1993,7 @@ function handleMessageError(error: string): void {
   isStreamingMessage = false;
   // Notify IM stream: localized error
   if (imStreamCallback) {
-    imStreamCallback('error', localizeImError(error));
+    imStreamCallback('error', localizeImError(error), extractHttpStatus(error));
     imStreamCallback = null;
   }
   setSessionState('idle');
@@ -2621,7 +2674,7 @@ export async function enqueueUserMessage(
   permissionMode?: PermissionMode,
   model?: string,
   providerEnv?: ProviderEnv,
-  metadata?: { source: 'desktop' | 'telegram_private' | 'telegram_group' | 'feishu_private' | 'feishu_group'; sourceId?: string; senderName?: string },
+  metadata?: { source: 'desktop' | 'telegram_private' | 'telegram_group' | 'telegram_channel' | 'feishu_private' | 'feishu_group' | 'feishu_channel'; sourceId?: string; senderName?: string; imMessageId?: string },
 ): Promise<EnqueueResult> {
   // 等待进行中的时间回溯完成，防止并发写入 messages/session 状态
   if (rewindPromise) {
@@ -3439,7 +3492,12 @@ async function startStreamingSession(preWarm = false): Promise<void> {
               imTextBlockIndices.add(streamEvent.index);
             } else {
               // Notify non-text block activity (thinking, tool_use) so IM can show placeholder
-              imStreamCallback('activity', streamEvent.content_block.type);
+              // and list the tools used in the turn
+              imStreamCallback('activity', JSON.stringify({
+                kind: streamEvent.content_block.type,
+                toolName: streamEvent.content_block.type === 'tool_use' ? streamEvent.content_block.name : undefined,
+                toolUseId: 'id' in streamEvent.content_block ? streamEvent.content_block.id : undefined,
+              }));
             }
           }
           if (streamEvent.content_block.type === 'thinking') {
@@ -3584,6 +3642,18 @@ async function startStreamingSession(preWarm = false): Promise<void> {
               imStreamCallback('block-end', '');
               imTextBlockIndices.delete(streamEvent.index);
             }
+            // IM stream: tool input is complete — details for the tool-activity status line
+            if (imStreamCallback && toolId) {
+              const toolBlock = findToolBlockById(toolId);
+              if (toolBlock?.tool.parsedInput) {
+                imStreamCallback('activity', JSON.stringify({
+                  kind: 'tool_input',
+                  toolName: toolBlock.tool.name,
+                  toolUseId: toolId,
+                  input: truncateToolInputForIm(toolBlock.tool.parsedInput),
+                }));
+              }
+            }
           }
         }
       } else if (sdkMessage.type === 'user') {
@@ -3793,6 +3863,13 @@ async function startStreamingSession(preWarm = false): Promise<void> {
                   content: stripped ? PLAYWRIGHT_RESULT_SENTINEL : contentStr,
                   isError: toolResultBlock.is_error || false
                 });
+                // IM stream: tool finished (ends its line in the tool-activity status)
+                imStreamCallback?.('activity', JSON.stringify({
+                  kind: 'tool_result',
+                  toolName: findToolBlockById(toolResultBlock.tool_use_id)?.tool.name,
+                  toolUseId: toolResultBlock.tool_use_id,
+                  isError: toolResultBlock.is_error || false,
+                }));
               }
               handleToolResultComplete(
                 toolResultBlock.tool_use_id,
@@ -3823,6 +3900,7 @@ async function startStreamingSession(preWarm = false): Promise<void> {
             cacheReadInputTokens?: number;
             cacheCreationInputTokens?: number;
           }>;
+          total_cost_usd?: number;
         };
 
         // Forward SDK error results to IM callback (prevents "(No Response)")
@@ -3836,7 +3914,7 @@ async function startStreamingSession(preWarm = false): Promise<void> {
           if (imStreamCallback) {
             const errorText = localizeImError(rawError);
             console.warn('[agent] SDK result is_error, forwarding to IM:', errorText);
-            imStreamCallback('error', errorText);
+            imStreamCallback('error', errorText, extractHttpStatus(rawError));
             imStreamCallback = null;
           }
         }
@@ -3899,6 +3977,7 @@ async function startStreamingSession(preWarm = false): Promise<void> {
         } else {
           console.warn('[agent] Result message has no usage data, token statistics may be incomplete');
         }
+        currentTurnUsage.costUsd = resultMessage.total_cost_usd;
 
         // Calculate duration for analytics
         const durationMs = currentTurnStartTime ? Date.now() - currentTurnStartTime : 0;
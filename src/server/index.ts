@@ -85,6 +85,7 @@ import {
   getQueueStatus,
   getAgentState,
   getLogLines,
+  getLastTurnUsage,
   getMessages,
   getSessionId,
   getSystemInitInfo,
@@ -1193,7 +1194,8 @@ async function main() {
           const response = {
             success: true,
             aiRequestedExit,
-            exitReason
+            exitReason,
+            usage: getLastTurnUsage(),
           };
           console.log(`[cron] execute-sync taskId=${taskId} returning response:`, JSON.stringify(response));
           return jsonResponse(response);
@@ -4521,9 +4523,10 @@ async function main() {
         try {
           const payload = (await request.json()) as {
             message: string;
-            source: 'telegram_private' | 'telegram_group' | 'feishu_private' | 'feishu_group';
+            source: 'telegram_private' | 'telegram_group' | 'telegram_channel' | 'feishu_private' | 'feishu_group' | 'feishu_channel';
             sourceId: string;
             senderName?: string;
+            messageId?: string;
             permissionMode?: string;
             providerEnv?: ProviderEnv;
             images?: Array<{ name: string; mimeType: string; data: string }>;
@@ -4564,6 +4567,7 @@ async function main() {
             source: payload.source,
             sourceId: payload.sourceId,
             senderName: payload.senderName,
+            imMessageId: payload.messageId,
           };
 
           // Use enqueueUserMessage — shares the same persistent generator as Desktop
@@ -4642,7 +4646,7 @@ async function main() {
                 }
               }, 600000);
 
-              setImStreamCallback((event, data) => {
+              setImStreamCallback((event, data, status) => {
                 if (event === 'permission-request') {
                   // Forward permission request to Rust for interactive approval
                   sendEvent({ type: 'permission-request', ...JSON.parse(data) });
@@ -4658,17 +4662,23 @@ async function main() {
                     sendEvent({ type: 'block-end', text: imAccText });
                     imAccText = '';
                   }
-                  sendEvent({ type: 'complete', sessionId: getSessionId() });
+                  // Token usage of the turn (absent when it was stopped) — Rust records it
+                  // in the bot's usage ledger and optionally shows it in the reply footer
+                  const usage = data ? JSON.parse(data) : undefined;
+                  sendEvent({ type: 'complete', sessionId: getSessionId(), usage });
                   // Notify Desktop: IM turn completed
                   broadcast('im:response_sent', {
                     sessionId: getSessionId(),
                   });
                   closeStream();
                 } else if (event === 'activity') {
-                  // Non-text block started (thinking, tool_use) — Rust uses this for placeholder
-                  sendEvent({ type: 'activity' });
+                  // Non-text block started (thinking, tool_use), tool input complete (tool_input)
+                  // or tool finished (tool_result) — Rust uses this for the placeholder, the
+                  // tool-activity status line and summary ({ kind, toolName?, input?, isError? })
+                  sendEvent({ type: 'activity', ...JSON.parse(data) });
                 } else if (event === 'error') {
-                  sendEvent({ type: 'error', error: data });
+                  // status: HTTP status of the underlying API error, if known (Rust failover)
+                  sendEvent({ type: 'error', error: data, status });
                   closeStream();
                 }
               });
@@ -4763,7 +4773,7 @@ async function main() {
             undefined,
             undefined,
             {
-              source: payload.source as 'desktop' | 'telegram_private' | 'telegram_group' | 'feishu_private' | 'feishu_group',
+              source: payload.source as 'desktop' | 'telegram_private' | 'telegram_group' | 'telegram_channel' | 'feishu_private' | 'feishu_group' | 'feishu_channel',
               sourceId: payload.sourceId,
             },
           );
@@ -4798,7 +4808,7 @@ async function main() {
           const ackMaxChars = payload.ackMaxChars ?? 300;
           const result = stripHeartbeatToken(text, ackMaxChars);
 
-          return jsonResponse(result);
+          return jsonResponse({ ...result, usage: getLastTurnUsage() });
         } catch (error) {
           console.error('[im/heartbeat] Error:', error);
           return jsonResponse(
@@ -4842,6 +4852,31 @@ async function main() {
         }
       }
 
+      // POST /api/im/rewind — Drop an IM turn (its user message and everything after) before it is re-run
+      if (pathname === '/api/im/rewind' && request.method === 'POST') {
+        try {
+          const payload = (await request.json()) as { messageId: string };
+          if (!payload.messageId) {
+            return jsonResponse({ success: false, error: 'messageId is required' }, 400);
+          }
+          const target = [...getMessages()]
+            .reverse()
+            .find(m => m.role === 'user' && m.metadata?.imMessageId === payload.messageId);
+          if (!target) {
+            // The turn never reached this session (e.g. failed before the Sidecar was up)
+            return jsonResponse({ success: true, rewound: false });
+          }
+          const result = await rewindSession(target.id);
+          return jsonResponse({ success: result.success, rewound: result.success, error: result.error });
+        } catch (error) {
+          console.error('[im/rewind] Error:', error);
+          return jsonResponse(
+            { success: false, error: error instanceof Error ? error.message : 'Rewind error' },
+            500,
+          );
+        }
+      }
+
       // POST /api/im/session/new — Start a new session (preserving workspace)
       if (pathname === '/api/im/session/new' && request.method === 'POST') {
         try {
@@ -29,7 +29,7 @@ export interface SessionMetadata {
     /** Associated cron task ID (if this session is used by a scheduled task) */
     cronTaskId?: string;
     /** Session origin — undefined or 'desktop' for Desktop, IM sources for Telegram */
-    source?: 'desktop' | 'telegram_private' | 'telegram_group' | 'feishu_private' | 'feishu_group';
+    source?: 'desktop' | 'telegram_private' | 'telegram_group' | 'telegram_channel' | 'feishu_private' | 'feishu_group' | 'feishu_channel';
 }
 
 /**
@@ -77,9 +77,11 @@ export interface MessageUsage {
  * Message source metadata (IM integration)
  */
 export interface MessageSourceMetadata {
-    source: 'desktop' | 'telegram_private' | 'telegram_group' | 'feishu_private' | 'feishu_group';
+    source: 'desktop' | 'telegram_private' | 'telegram_group' | 'telegram_channel' | 'feishu_private' | 'feishu_group' | 'feishu_channel';
     sourceId?: string;
     senderName?: string;
+    /** Platform message ID of an IM user message (lets /retry and edits rewind that turn) */
+    imMessageId?: string;
 }
 
 /**
@@ -8,7 +8,7 @@ export type ImPlatform = 'telegram' | 'feishu';
 /**
  * Message source identifier
  */
-export type MessageSource = 'desktop' | 'telegram_private' | 'telegram_group' | 'feishu_private' | 'feishu_group';
+export type MessageSource = 'desktop' | 'telegram_private' | 'telegram_group' | 'telegram_channel' | 'feishu_private' | 'feishu_group' | 'feishu_channel';
 
 /**
  * Metadata attached to each message indicating its origin
@@ -27,7 +27,7 @@ export type ImStatus = 'online' | 'connecting' | 'error' | 'stopped';
 /**
  * IM source type (private chat vs group)
  */
-export type ImSourceType = 'private' | 'group';
+export type ImSourceType = 'private' | 'group' | 'channel';
 
 /**
  * IM Bot configuration (stored in AppConfig)
@@ -65,6 +65,114 @@ export interface ImBotConfig {
 
   // ===== Heartbeat (v0.1.21) =====
   heartbeat?: HeartbeatConfig;
+
+  // ===== Rate limiting =====
+  rateLimit?: RateLimitConfig;
+
+  // ===== Turn scheduler =====
+  /** Share of the app-wide turn scheduler relative to other bots (default 1) */
+  schedulerWeight?: number;
+
+  // ===== Feishu =====
+  /** Stream replies as interactive cards (tool summary + footer) instead of post messages */
+  feishuCardStreaming?: boolean;
+  /** Post a "processing" placeholder card in chats where ack reactions are unavailable */
+  feishuProcessingCard?: boolean;
+
+  // ===== Long replies =====
+  /** Replies longer than this many characters are sent as a summary plus a .md file (default 12000, 0 = never) */
+  documentReplyThreshold?: number;
+
+  // ===== Provider failover =====
+  /** Provider IDs to retry on, in order, when the current provider fails with an auth / quota / server error */
+  fallbackProviderIds?: string[];
+
+  // ===== Voice transcription =====
+  transcription?: ImTranscriptionConfig;
+
+  // ===== Attachment inbox =====
+  inbox?: ImInboxConfig;
+
+  // ===== Streaming =====
+  /** Live tool-activity status line shown while a reply streams (default 'off') */
+  toolStatus?: ImToolStatusMode;
+
+  // ===== Usage =====
+  /** Append token usage and cost to the footer of each reply */
+  usageFooter?: boolean;
+  /** Enable the /usage chat command (today's and this month's totals) */
+  usageCommand?: boolean;
+}
+
+/** off: only the "generating" placeholder; compact: the latest running step; verbose: finished steps too */
+export type ImToolStatusMode = 'off' | 'compact' | 'verbose';
+
+/**
+ * Incoming files are saved to `<workspace>/<dir>/<botId>/<YYYY-MM-DD>/`.
+ * Unset fields use the defaults.
+ */
+export interface ImInboxConfig {
+  /** Workspace-relative directory (default "myagents_files/inbox") */
+  dir?: string;
+  /** Days to keep inbox files (default 30, 0 = forever) */
+  retentionDays?: number;
+  /** Size limit per workspace in MB; the oldest files are evicted (default 1024, 0 = unlimited) */
+  quotaMb?: number;
+}
+
+/**
+ * Inbox disk usage in one workspace (cmd_im_inbox_usage)
+ */
+export interface ImInboxUsage {
+  workspacePath: string;
+  inboxPath: string;
+  files: number;
+  bytes: number;
+  quotaBytes: number | null;
+}
+
+/**
+ * Speech-to-text for voice / audio attachments via an OpenAI-compatible
+ * `POST {baseUrl}/audio/transcriptions` endpoint (OpenAI, or a local whisper server).
+ */
+export interface ImTranscriptionConfig {
+  enabled: boolean;
+  /** e.g. "https://api.openai.com/v1" or "http://127.0.0.1:8080/v1" */
+  baseUrl: string;
+  apiKey?: string;
+  /** Default "whisper-1" */
+  model?: string;
+  /** ISO-639-1 hint, e.g. "zh"; auto-detected when unset */
+  language?: string;
+}
+
+/**
+ * Per-sender / per-chat rate limits. A missing rule or field means unlimited.
+ */
+export interface RateLimitConfig {
+  /** Applied to each sender across all chats */
+  perUser?: RateLimitRule;
+  /** Applied to each chat across all senders */
+  perChat?: RateLimitRule;
+}
+
+export interface RateLimitRule {
+  /** Max messages routed to AI in any rolling 60s window */
+  messagesPerMinute?: number;
+  /** Max turns in flight at once */
+  concurrentTurns?: number;
+  /** Max turns per local calendar day */
+  dailyTurns?: number;
+}
+
+/**
+ * Rate limiter counters (rejections since start + turns in flight)
+ */
+export interface RateLimitCounters {
+  rejectedPerMinute: number;
+  rejectedConcurrent: number;
+  rejectedDaily: number;
+  activeTurns: number;
 }
 
 /**
@@ -115,6 +223,18 @@ export interface ImActiveSession {
   workspacePath: string;
   messageCount: number;
   lastActive: string;         // ISO timestamp
+  overrides?: ImChatOverrides; // Settings this chat changed via /model, /provider, /mode, /workspace
+  previousSessionIds?: string[]; // Earlier sessions of this chat, most recent first (/sessions, /resume)
+}
+
+/**
+ * Per-chat settings that take precedence over the bot config. Missing fields follow the bot.
+ */
+export interface ImChatOverrides {
+  model?: string;
+  providerId?: string;
+  permissionMode?: string;
+  workspacePath?: string;
 }
 
 /**
@@ -133,6 +253,10 @@ export interface ImBotStatus {
   bindUrl?: string;
   /** Plain bind code for platforms without deep links (e.g. Feishu) */
   bindCode?: string;
+  /** Rate limiter counters */
+  rateLimit?: RateLimitCounters;
+  /** Turns waiting for an app-wide scheduler slot */
+  queuedTurns?: number;
 }
 
 /**
@@ -148,6 +272,65 @@ export interface ImConversation {
   lastActive: string;           // ISO timestamp
 }
 
+/**
+ * Audit log action kind (returned by cmd_im_audit_log)
+ */
+export type ImAuditAction = 'approval' | 'mode' | 'provider' | 'workspace' | 'bind' | 'allowlist';
+
+/**
+ * Audit log entry — one line in ~/.myagents/im_{botId}_audit.jsonl
+ */
+export interface ImAuditEntry {
+  timestamp: string;            // ISO timestamp
+  action: ImAuditAction;
+  userId: string;               // Platform user ID, or "desktop" for app-side changes
+  userName?: string;
+  chatId?: string;
+  sessionKey?: string;
+  /** Action-specific fields, e.g. { toolName, inputDigest, decision } for approvals */
+  detail: Record<string, unknown>;
+}
+
+/**
+ * Period usage is totalled by (cmd_im_usage), in local time
+ */
+export type ImUsagePeriod = 'day' | 'month';
+
+/**
+ * Optional breakdown within each period (cmd_im_usage)
+ */
+export type ImUsageGroup = 'peer' | 'user' | 'task' | 'source' | 'model';
+
+/**
+ * Usage totals of one period (and group) — returned by cmd_im_usage.
+ * Computed from ~/.myagents/im_{botId}_usage.jsonl (chat turns, heartbeats, bot-owned cron tasks).
+ */
+export interface ImUsageBucket {
+  period: string;               // "2026-10-18" (day) or "2026-10" (month)
+  key?: string;                 // Group key; absent when ungrouped
+  turns: number;
+  inputTokens: number;
+  outputTokens: number;
+  cacheReadTokens: number;
+  cacheCreationTokens: number;
+  /** Sum of SDK-reported costs (turns without one count as 0) */
+  costUsd: number;
+}
+
+/**
+ * Exported conversation of one peer (cmd_im_export_conversation): its sessions oldest first,
+ * with user messages, bot replies (and the tools they used), tool approvals and attachments
+ */
+export interface ImTranscriptExport {
+  fileName: string;             // e.g. "conversation-123456-20261018-1730.md"
+  content: string;
+  /** User and bot messages included (approvals not counted) */
+  messageCount: number;
+}
+
+/** Format accepted by cmd_im_export_conversation (default 'markdown') */
+export type ImTranscriptFormat = 'markdown' | 'jsonl';
+
 /**
  * Default Telegram Bot configuration
  */
@@ -191,8 +374,10 @@ export const SOURCE_LABELS: Record<MessageSource, string> = {
   desktop: '桌面端',
   telegram_private: 'Telegram 私聊',
   telegram_group: 'Telegram 群聊',
+  telegram_channel: 'Telegram 频道',
   feishu_private: '飞书私聊',
   feishu_group: '飞书群聊',
+  feishu_channel: '飞书频道',
 };
 
 /**
@@ -202,6 +387,8 @@ export const SOURCE_ICONS: Record<MessageSource, string> = {
   desktop: '🖥',
   telegram_private: '📱',
   telegram_group: '👥',
+  telegram_channel: '📢',
   feishu_private: '📱',
   feishu_group: '👥',
+  feishu_channel: '📢',
 };